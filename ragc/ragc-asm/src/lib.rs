@@ -0,0 +1,412 @@
+//! Assembler for a practical subset of yaYUL, used to build small ropes for
+//! tests and experiments.
+//!
+//! Source lines follow yaYUL's layout: a label starting in the first
+//! column, then an opcode and its operand, with `#` starting a comment.
+//! Address-field numbers are octal unless suffixed with `D`. Supported are
+//! all basic instructions and extracodes (the latter only after an EXTEND),
+//! the usual implied-address mnemonics, and these directives and constants:
+//!
+//! * `SETLOC addr` moves the location counter, e.g. `SETLOC 4000` or
+//!   `SETLOC 24,2000` for a switched bank.
+//! * `BANK n` continues in fixed bank `n` after the last word placed there.
+//! * `ERASE` reserves one erasable word, `ERASE +n` reserves n+1.
+//! * `label EQUALS expr` (or `=`) defines a symbol. Without an operand the
+//!   symbol takes the current location.
+//! * `OCT`, `DEC` and `2DEC` constants, `ADRES` and `CADR` address
+//!   constants.
+//! * `EBANK=`, `SBANK=`, `COUNT` and `COUNT*` are accepted and ignored.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use ragc_core::consts::{ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use ragc_core::mem::{set_rope_word, AgcAddr};
+
+mod ops;
+mod parse;
+mod symtab;
+
+use ops::Operand;
+use parse::{eval, offset_addr, parse_dec, parse_oct, split_line, Line};
+
+pub use symtab::{SymbolTable, SymbolTableError};
+
+/// Rope image in the layout `ragc_core::mem::MemoryMap` expects.
+pub type AgcRope = [[u16; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS];
+
+/// Value of a symbol or address expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsmValue {
+    /// Plain number, used as a CPU address when it ends up in an address
+    /// field.
+    Number(i32),
+    /// Memory location, such as a label.
+    Address(AgcAddr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsmErrorKind {
+    UnknownOpcode(String),
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    InvalidOperand(String),
+    InvalidConstant(String),
+    MissingOperand,
+    MissingLabel,
+    /// A word or label comes before any SETLOC or BANK.
+    NoLocation,
+    /// A plain address inside a switched window was used as a location.
+    AmbiguousAddress(i32),
+    /// An extracode without a preceding EXTEND.
+    MissingExtend(String),
+    /// A basic instruction right after an EXTEND.
+    UnexpectedExtend(String),
+    /// The operand does not fit or does not suit the address field.
+    OperandRange(String),
+    /// Words can only be placed in fixed memory.
+    NotFixed,
+    /// ERASE outside of erasable memory.
+    NotErasable,
+    BankOverflow,
+    /// A second word was placed at an already used location.
+    Overlap(AgcAddr),
+}
+
+/// Assembly error, with the 1-based number of the offending line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsmErrorKind::UnknownOpcode(x) => write!(f, "unknown opcode {}", x),
+            AsmErrorKind::UndefinedSymbol(x) => write!(f, "undefined symbol {}", x),
+            AsmErrorKind::DuplicateSymbol(x) => write!(f, "symbol {} defined twice", x),
+            AsmErrorKind::InvalidOperand(x) => write!(f, "invalid operand {}", x),
+            AsmErrorKind::InvalidConstant(x) => write!(f, "invalid constant {}", x),
+            AsmErrorKind::MissingOperand => write!(f, "missing operand"),
+            AsmErrorKind::MissingLabel => write!(f, "missing label"),
+            AsmErrorKind::NoLocation => write!(f, "no location set, use SETLOC or BANK"),
+            AsmErrorKind::AmbiguousAddress(x) => {
+                write!(
+                    f,
+                    "address {:04o} is in a switched window, give the bank",
+                    x
+                )
+            }
+            AsmErrorKind::MissingExtend(x) => write!(f, "{} needs a preceding EXTEND", x),
+            AsmErrorKind::UnexpectedExtend(x) => write!(f, "{} cannot follow EXTEND", x),
+            AsmErrorKind::OperandRange(x) => write!(f, "operand {} out of range", x),
+            AsmErrorKind::NotFixed => write!(f, "words can only be placed in fixed memory"),
+            AsmErrorKind::NotErasable => write!(f, "ERASE outside of erasable memory"),
+            AsmErrorKind::BankOverflow => write!(f, "bank overflow"),
+            AsmErrorKind::Overlap(x) => write!(f, "location {} used twice", x),
+        }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Result of a successful assembly.
+pub struct AgcAssembly {
+    rope: Box<AgcRope>,
+    symbols: HashMap<String, AsmValue>,
+}
+
+impl AgcAssembly {
+    pub fn rope(&self) -> &AgcRope {
+        &self.rope
+    }
+
+    pub fn into_rope(self) -> Box<AgcRope> {
+        self.rope
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<AsmValue> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Location of a label, or of a symbol equated to one.
+    pub fn addr(&self, name: &str) -> Option<AgcAddr> {
+        match self.symbol(name)? {
+            AsmValue::Address(x) => Some(x),
+            AsmValue::Number(_) => None,
+        }
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = (&str, AsmValue)> {
+        self.symbols.iter().map(|(k, v)| (k.as_str(), *v))
+    }
+
+    /// Symbol table of the labels and of symbols equated to locations.
+    pub fn symbol_table(&self) -> SymbolTable {
+        let mut table = SymbolTable::new();
+        let mut names: Vec<(&String, &AsmValue)> = self.symbols.iter().collect();
+        names.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in names {
+            if let AsmValue::Address(x) = value {
+                table.insert(name, *x);
+            }
+        }
+        table
+    }
+}
+
+/// Assembles yaYUL source into a rope. Unused words of the rope are zero.
+pub fn assemble(source: &str) -> Result<AgcAssembly, AsmError> {
+    let lines: Vec<Line> = source
+        .lines()
+        .enumerate()
+        .map(|(idx, text)| split_line(idx + 1, text))
+        .collect();
+
+    let mut asm = Assembler::new();
+    let words = asm.locate(&lines)?;
+    for (line, addr) in words {
+        asm.emit(line, addr).map_err(|kind| AsmError {
+            line: line.number,
+            kind,
+        })?;
+    }
+
+    Ok(AgcAssembly {
+        rope: asm.rope,
+        symbols: asm.symbols,
+    })
+}
+
+struct Assembler {
+    symbols: HashMap<String, AsmValue>,
+    rope: Box<AgcRope>,
+    used: Box<[[bool; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS]>,
+    bank_next: [u16; ROM_NUM_BANKS],
+    extended: bool,
+}
+
+impl Assembler {
+    fn new() -> Assembler {
+        Assembler {
+            symbols: HashMap::new(),
+            rope: Box::new([[0; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS]),
+            used: Box::new([[false; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS]),
+            bank_next: [0; ROM_NUM_BANKS],
+            extended: false,
+        }
+    }
+
+    /// First pass: assigns a location to every label and word, and returns
+    /// the lines that place words along with their location.
+    fn locate<'l, 's>(
+        &mut self,
+        lines: &'l [Line<'s>],
+    ) -> Result<Vec<(&'l Line<'s>, AgcAddr)>, AsmError> {
+        let mut loc: Option<AgcAddr> = None;
+        let mut words = Vec::new();
+
+        for line in lines.iter() {
+            let err = |kind| AsmError {
+                line: line.number,
+                kind,
+            };
+            let opcode = line.opcode.unwrap_or("");
+
+            if opcode == "EQUALS" || opcode == "=" {
+                let label = line.label.ok_or_else(|| err(AsmErrorKind::MissingLabel))?;
+                let value = if line.operand.is_empty() {
+                    AsmValue::Address(loc.ok_or_else(|| err(AsmErrorKind::NoLocation))?)
+                } else {
+                    eval(line.operand, loc, &self.symbols).map_err(err)?
+                };
+                self.define(label, value).map_err(err)?;
+                continue;
+            }
+
+            match opcode {
+                "SETLOC" => {
+                    let value = eval(line.operand, loc, &self.symbols).map_err(err)?;
+                    loc = Some(location(value).map_err(err)?);
+                }
+                "BANK" => match eval(line.operand, loc, &self.symbols).map_err(err)? {
+                    AsmValue::Number(bank) if (0..ROM_NUM_BANKS as i32).contains(&bank) => {
+                        loc = Some(AgcAddr::Fixed {
+                            bank: bank as u8,
+                            offset: self.bank_next[bank as usize],
+                        });
+                    }
+                    _ => return Err(err(AsmErrorKind::InvalidOperand(line.operand.into()))),
+                },
+                _ => {}
+            }
+
+            if let Some(label) = line.label {
+                let addr = loc.ok_or_else(|| err(AsmErrorKind::NoLocation))?;
+                self.define(label, AsmValue::Address(addr)).map_err(err)?;
+            }
+
+            let size = match opcode {
+                "" | "SETLOC" | "BANK" | "EBANK=" | "SBANK=" | "COUNT" | "COUNT*" => continue,
+                "ERASE" => {
+                    let addr = loc.ok_or_else(|| err(AsmErrorKind::NoLocation))?;
+                    if addr.is_fixed() {
+                        return Err(err(AsmErrorKind::NotErasable));
+                    }
+                    let size = match line.operand {
+                        "" => 1,
+                        x => match eval(x.trim_start_matches('+'), None, &self.symbols) {
+                            Ok(AsmValue::Number(n)) if n >= 0 => n + 1,
+                            Ok(_) => return Err(err(AsmErrorKind::InvalidOperand(x.into()))),
+                            Err(e) => return Err(err(e)),
+                        },
+                    };
+                    loc = Some(
+                        offset_addr(addr, size).ok_or_else(|| err(AsmErrorKind::BankOverflow))?,
+                    );
+                    continue;
+                }
+                "2DEC" => 2,
+                "OCT" | "DEC" | "ADRES" | "CADR" | "FCADR" => 1,
+                x if ops::lookup(x, false).is_some() || ops::lookup(x, true).is_some() => 1,
+                x => return Err(err(AsmErrorKind::UnknownOpcode(x.into()))),
+            };
+
+            let addr = loc.ok_or_else(|| err(AsmErrorKind::NoLocation))?;
+            let (bank, offset) = match addr {
+                AgcAddr::Fixed { bank, offset } => (bank, offset),
+                AgcAddr::Erasable { .. } => return Err(err(AsmErrorKind::NotFixed)),
+            };
+            if offset as usize + size as usize > ROM_BANK_NUM_WORDS {
+                return Err(err(AsmErrorKind::BankOverflow));
+            }
+
+            words.push((line, addr));
+            let next = offset + size as u16;
+            loc = offset_addr(addr, size);
+            let bank_next = &mut self.bank_next[bank as usize];
+            *bank_next = (*bank_next).max(next);
+        }
+        Ok(words)
+    }
+
+    fn define(&mut self, label: &str, value: AsmValue) -> Result<(), AsmErrorKind> {
+        if self.symbols.insert(label.to_string(), value).is_some() {
+            return Err(AsmErrorKind::DuplicateSymbol(label.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Second pass: encodes the words of a line at its location.
+    fn emit(&mut self, line: &Line, addr: AgcAddr) -> Result<(), AsmErrorKind> {
+        let opcode = line.opcode.unwrap_or("");
+        let operand = line.operand;
+
+        let words: Vec<u16> = match opcode {
+            "OCT" => vec![parse_oct(operand)?],
+            "DEC" => vec![parse_dec(operand, false)?[0]],
+            "2DEC" => parse_dec(operand, true)?.to_vec(),
+            "ADRES" => vec![self.field(operand, addr, 0o7777)?],
+            "CADR" | "FCADR" => vec![match eval(operand, Some(addr), &self.symbols)? {
+                AsmValue::Address(AgcAddr::Fixed { bank, offset }) => {
+                    ((bank as u16 & 0o37) << 10) | offset
+                }
+                AsmValue::Address(AgcAddr::Erasable { bank, offset }) => {
+                    ((bank as u16) << 8) | offset
+                }
+                AsmValue::Number(_) => {
+                    return Err(AsmErrorKind::InvalidOperand(operand.to_string()))
+                }
+            }],
+            _ => {
+                let word = self.instruction(opcode, operand, addr)?;
+                self.extended =
+                    word == ops::EXTEND_WORD || (self.extended && word & 0o70000 == 0o50000);
+                self.place(addr, word)?;
+                return Ok(());
+            }
+        };
+
+        self.extended = false;
+        let mut addr = addr;
+        for word in words {
+            self.place(addr, word)?;
+            addr = offset_addr(addr, 1).ok_or(AsmErrorKind::BankOverflow)?;
+        }
+        Ok(())
+    }
+
+    fn instruction(&self, opcode: &str, operand: &str, addr: AgcAddr) -> Result<u16, AsmErrorKind> {
+        let op = ops::lookup(opcode, self.extended)
+            .ok_or_else(|| AsmErrorKind::UnknownOpcode(opcode.to_string()))?;
+        if op.extended && !self.extended {
+            return Err(AsmErrorKind::MissingExtend(opcode.to_string()));
+        }
+        if !op.extended && self.extended {
+            return Err(AsmErrorKind::UnexpectedExtend(opcode.to_string()));
+        }
+
+        let k = match op.operand {
+            Operand::None => 0,
+            Operand::Noop if addr.is_fixed() => {
+                let next = offset_addr(addr, 1).ok_or(AsmErrorKind::BankOverflow)?;
+                return Ok(0o10000 | next.cpu_addr());
+            }
+            Operand::Noop => 0,
+            Operand::Memory => self.field(operand, addr, 0o7777)?,
+            Operand::Erasable => self.field(operand, addr, 0o1777)?,
+            Operand::Channel => self.field(operand, addr, 0o777)?,
+            Operand::Fixed => match self.field(operand, addr, 0o7777)? {
+                k @ 0o2000..=0o7777 => k,
+                _ => return Err(AsmErrorKind::OperandRange(operand.to_string())),
+            },
+            Operand::DoubleMemory => (self.field(operand, addr, 0o7777)? + 1) & 0o7777,
+            Operand::DoubleErasable => (self.field(operand, addr, 0o1777)? + 1) & 0o1777,
+        };
+        Ok(op.word | k)
+    }
+
+    /// Evaluates an operand into an address field no larger than `max`.
+    fn field(&self, operand: &str, addr: AgcAddr, max: u16) -> Result<u16, AsmErrorKind> {
+        let k = match eval(operand, Some(addr), &self.symbols)? {
+            AsmValue::Number(x) => x,
+            AsmValue::Address(x) => x.cpu_addr() as i32,
+        };
+        if k < 0 || k > max as i32 {
+            return Err(AsmErrorKind::OperandRange(operand.to_string()));
+        }
+        Ok(k as u16)
+    }
+
+    fn place(&mut self, addr: AgcAddr, word: u16) -> Result<(), AsmErrorKind> {
+        let (bank, offset) = match addr {
+            AgcAddr::Fixed { bank, offset } => (bank as usize, offset as usize),
+            AgcAddr::Erasable { .. } => return Err(AsmErrorKind::NotFixed),
+        };
+        if self.used[bank][offset] {
+            return Err(AsmErrorKind::Overlap(addr));
+        }
+        self.used[bank][offset] = true;
+        set_rope_word(&mut self.rope, bank, offset, word);
+        Ok(())
+    }
+}
+
+/// Turns a SETLOC operand into a location. Plain numbers are CPU addresses
+/// and must not fall into a switched window.
+fn location(value: AsmValue) -> Result<AgcAddr, AsmErrorKind> {
+    match value {
+        AsmValue::Address(x) => Ok(x),
+        AsmValue::Number(x @ (0o0000..=0o1377 | 0o4000..=0o7777)) => {
+            Ok(AgcAddr::from_cpu(x as u16, 0, 0))
+        }
+        AsmValue::Number(x) => Err(AsmErrorKind::AmbiguousAddress(x)),
+    }
+}
@@ -0,0 +1,163 @@
+/// Kind of operand an instruction takes, which decides how the operand is
+/// checked and placed into the address field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Operand {
+    /// Implied-address instruction, the word is complete.
+    None,
+    /// 12-bit address anywhere in memory.
+    Memory,
+    /// 10-bit erasable address.
+    Erasable,
+    /// 12-bit fixed memory address.
+    Fixed,
+    /// 9-bit IO channel number.
+    Channel,
+    /// Double precision operand in memory, encoded as K+1.
+    DoubleMemory,
+    /// Double precision operand in erasable, encoded as K+1.
+    DoubleErasable,
+    /// yaYUL's NOOP, which is a TCF to the next word in fixed memory.
+    Noop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Op {
+    pub word: u16,
+    pub operand: Operand,
+    /// Extracodes only assemble after an EXTEND.
+    pub extended: bool,
+}
+
+const fn basic(word: u16, operand: Operand) -> Op {
+    Op {
+        word,
+        operand,
+        extended: false,
+    }
+}
+
+const fn extra(word: u16, operand: Operand) -> Op {
+    Op {
+        word,
+        operand,
+        extended: true,
+    }
+}
+
+pub(crate) const EXTEND_WORD: u16 = 0o00006;
+
+/// Looks up an instruction mnemonic. INDEX has a basic and an extended
+/// form, `extended` tells which one the assembler is at.
+pub(crate) fn lookup(name: &str, extended: bool) -> Option<Op> {
+    use Operand::*;
+
+    let op = match name {
+        "TC" | "TCR" => basic(0o00000, Memory),
+        "CCS" => basic(0o10000, Erasable),
+        "TCF" => basic(0o10000, Fixed),
+        "DAS" => basic(0o20000, DoubleErasable),
+        "LXCH" => basic(0o22000, Erasable),
+        "INCR" => basic(0o24000, Erasable),
+        "ADS" => basic(0o26000, Erasable),
+        "CA" => basic(0o30000, Memory),
+        "CAE" => basic(0o30000, Erasable),
+        "CAF" => basic(0o30000, Fixed),
+        "CS" => basic(0o40000, Memory),
+        "INDEX" | "NDX" if extended => extra(0o50000, Memory),
+        "INDEX" | "NDX" => basic(0o50000, Erasable),
+        "DXCH" => basic(0o52000, DoubleErasable),
+        "TS" => basic(0o54000, Erasable),
+        "XCH" => basic(0o56000, Erasable),
+        "AD" => basic(0o60000, Memory),
+        "MASK" | "MSK" => basic(0o70000, Memory),
+
+        "XXALQ" => basic(0o00000, None),
+        "XLQ" => basic(0o00001, None),
+        "RETURN" => basic(0o00002, None),
+        "RELINT" => basic(0o00003, None),
+        "INHINT" => basic(0o00004, None),
+        "EXTEND" => basic(EXTEND_WORD, None),
+        "DDOUBL" => basic(0o20001, None),
+        "ZL" => basic(0o22007, None),
+        "NOOP" => basic(0o30000, Noop),
+        "COM" => basic(0o40000, None),
+        "RESUME" => basic(0o50017, None),
+        "DTCF" => basic(0o52005, None),
+        "DTCB" => basic(0o52006, None),
+        "OVSK" => basic(0o54000, None),
+        "TCAA" => basic(0o54005, None),
+        "DOUBLE" => basic(0o60000, None),
+
+        "READ" => extra(0o00000, Channel),
+        "WRITE" => extra(0o01000, Channel),
+        "RAND" => extra(0o02000, Channel),
+        "WAND" => extra(0o03000, Channel),
+        "ROR" => extra(0o04000, Channel),
+        "WOR" => extra(0o05000, Channel),
+        "RXOR" => extra(0o06000, Channel),
+        "EDRUPT" => extra(0o07000, Channel),
+        "DV" => extra(0o10000, Erasable),
+        "BZF" => extra(0o10000, Fixed),
+        "MSU" => extra(0o20000, Erasable),
+        "QXCH" => extra(0o22000, Erasable),
+        "AUG" => extra(0o24000, Erasable),
+        "DIM" => extra(0o26000, Erasable),
+        "DCA" => extra(0o30000, DoubleMemory),
+        "DCS" => extra(0o40000, DoubleMemory),
+        "SU" => extra(0o60000, Erasable),
+        "BZMF" => extra(0o60000, Fixed),
+        "MP" => extra(0o70000, Memory),
+
+        "ZQ" => extra(0o22007, None),
+        "DCOM" => extra(0o40001, None),
+        "SQUARE" => extra(0o70000, None),
+        _ => return Option::None,
+    };
+    Some(op)
+}
+
+/// Central and special registers and counters yaYUL knows without an
+/// EQUALS.
+pub(crate) fn predefined(name: &str) -> Option<u16> {
+    let addr = match name {
+        "A" => 0o00,
+        "L" => 0o01,
+        "Q" => 0o02,
+        "EB" | "EBANK" => 0o03,
+        "FB" | "FBANK" => 0o04,
+        "Z" => 0o05,
+        "BB" | "BBANK" => 0o06,
+        "ZERO" => 0o07,
+        "ARUPT" => 0o10,
+        "LRUPT" => 0o11,
+        "QRUPT" => 0o12,
+        "ZRUPT" => 0o15,
+        "BBRUPT" => 0o16,
+        "BRUPT" => 0o17,
+        "CYR" => 0o20,
+        "SR" => 0o21,
+        "CYL" => 0o22,
+        "EDOP" => 0o23,
+        "TIME2" => 0o24,
+        "TIME1" => 0o25,
+        "TIME3" => 0o26,
+        "TIME4" => 0o27,
+        "TIME5" => 0o30,
+        "TIME6" => 0o31,
+        "CDUX" => 0o32,
+        "CDUY" => 0o33,
+        "CDUZ" => 0o34,
+        "OPTY" => 0o35,
+        "OPTX" => 0o36,
+        "PIPAX" => 0o37,
+        "PIPAY" => 0o40,
+        "PIPAZ" => 0o41,
+        "INLINK" => 0o45,
+        "RNRAD" => 0o46,
+        "GYROCMD" => 0o47,
+        "OUTLINK" => 0o57,
+        "ALTM" => 0o60,
+        _ => return None,
+    };
+    Some(addr)
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use ragc_core::consts::{RAM_BANK_NUM_WORDS, RAM_NUM_BANKS, ROM_BANK_NUM_WORDS};
+use ragc_core::mem::AgcAddr;
+
+use crate::ops;
+use crate::{AsmErrorKind, AsmValue};
+
+/// One source line split into yaYUL's label, opcode and operand fields.
+pub(crate) struct Line<'a> {
+    pub number: usize,
+    pub label: Option<&'a str>,
+    pub opcode: Option<&'a str>,
+    pub operand: &'a str,
+}
+
+/// Splits a line into its fields. Labels start in the first column,
+/// everything after a `#` is a comment.
+pub(crate) fn split_line(number: usize, text: &str) -> Line<'_> {
+    let text = match text.find('#') {
+        Some(x) => &text[..x],
+        None => text,
+    };
+
+    let has_label = !text.starts_with(char::is_whitespace) && !text.trim().is_empty();
+    let mut rest = text.trim_start();
+    let label = if has_label {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let label = &rest[..end];
+        rest = rest[end..].trim_start();
+        Some(label)
+    } else {
+        None
+    };
+
+    let opcode = if rest.is_empty() {
+        None
+    } else {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let opcode = &rest[..end];
+        rest = &rest[end..];
+        Some(opcode)
+    };
+
+    Line {
+        number,
+        label,
+        opcode,
+        operand: rest.trim(),
+    }
+}
+
+/// Moves an address by `n` words. Erasable memory is treated as one
+/// contiguous range, fixed addresses stay in their bank; the word just past
+/// the end of a fixed bank is allowed so a full bank can be described.
+pub(crate) fn offset_addr(addr: AgcAddr, n: i32) -> Option<AgcAddr> {
+    match addr {
+        AgcAddr::Erasable { bank, offset } => {
+            let x = bank as i32 * RAM_BANK_NUM_WORDS as i32 + offset as i32 + n;
+            if x < 0 || x >= (RAM_NUM_BANKS * RAM_BANK_NUM_WORDS) as i32 {
+                return None;
+            }
+            Some(AgcAddr::Erasable {
+                bank: (x / RAM_BANK_NUM_WORDS as i32) as u8,
+                offset: (x % RAM_BANK_NUM_WORDS as i32) as u16,
+            })
+        }
+        AgcAddr::Fixed { bank, offset } => {
+            let x = offset as i32 + n;
+            if x < 0 || x > ROM_BANK_NUM_WORDS as i32 {
+                return None;
+            }
+            Some(AgcAddr::Fixed {
+                bank,
+                offset: x as u16,
+            })
+        }
+    }
+}
+
+/// Parses an address-field number: octal by default, decimal with a
+/// trailing `D`.
+pub(crate) fn parse_number(s: &str) -> Option<i32> {
+    if let Some(x) = s.strip_suffix('D') {
+        if !x.is_empty() && x.bytes().all(|c| c.is_ascii_digit()) {
+            return x.parse().ok();
+        }
+        return None;
+    }
+    if !s.is_empty() && s.bytes().all(|c| (b'0'..=b'7').contains(&c)) {
+        return i32::from_str_radix(s, 8).ok();
+    }
+    None
+}
+
+/// Evaluates an address expression: numbers, symbols and `bank,address`
+/// terms joined with `+` and `-`. An expression starting with a sign is
+/// relative to the current location, as in `TCF +2`.
+pub(crate) fn eval(
+    expr: &str,
+    loc: Option<AgcAddr>,
+    symbols: &HashMap<String, AsmValue>,
+) -> Result<AsmValue, AsmErrorKind> {
+    let expr: String = expr.chars().filter(|c| !c.is_whitespace()).collect();
+    if expr.is_empty() {
+        return Err(AsmErrorKind::MissingOperand);
+    }
+    let invalid = || AsmErrorKind::InvalidOperand(expr.clone());
+
+    let mut value = if expr.starts_with('+') || expr.starts_with('-') {
+        AsmValue::Address(loc.ok_or(AsmErrorKind::NoLocation)?)
+    } else {
+        AsmValue::Number(0)
+    };
+    let mut negate = false;
+    let mut rest = expr.as_str();
+    loop {
+        if let Some(x) = rest.strip_prefix('+') {
+            rest = x;
+        } else if let Some(x) = rest.strip_prefix('-') {
+            negate = true;
+            rest = x;
+        }
+
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let term = term(&rest[..end], symbols)?;
+        value = combine(value, term, negate).ok_or_else(invalid)?;
+
+        rest = &rest[end..];
+        negate = false;
+        if rest.is_empty() {
+            return Ok(value);
+        }
+    }
+}
+
+fn term(s: &str, symbols: &HashMap<String, AsmValue>) -> Result<AsmValue, AsmErrorKind> {
+    if s.contains(',') {
+        return AgcAddr::from_str(s)
+            .map(AsmValue::Address)
+            .map_err(|_| AsmErrorKind::InvalidOperand(s.to_string()));
+    }
+    if let Some(x) = parse_number(s) {
+        return Ok(AsmValue::Number(x));
+    }
+    if let Some(x) = symbols.get(s) {
+        return Ok(*x);
+    }
+    if let Some(x) = ops::predefined(s) {
+        return Ok(AsmValue::Number(x as i32));
+    }
+    if s.is_empty() {
+        return Err(AsmErrorKind::InvalidOperand(s.to_string()));
+    }
+    Err(AsmErrorKind::UndefinedSymbol(s.to_string()))
+}
+
+fn combine(lhs: AsmValue, rhs: AsmValue, negate: bool) -> Option<AsmValue> {
+    match (lhs, rhs, negate) {
+        (AsmValue::Number(a), AsmValue::Number(b), false) => Some(AsmValue::Number(a + b)),
+        (AsmValue::Number(a), AsmValue::Number(b), true) => Some(AsmValue::Number(a - b)),
+        (AsmValue::Address(a), AsmValue::Number(b), false) => {
+            offset_addr(a, b).map(AsmValue::Address)
+        }
+        (AsmValue::Address(a), AsmValue::Number(b), true) => {
+            offset_addr(a, -b).map(AsmValue::Address)
+        }
+        (AsmValue::Number(0), AsmValue::Address(b), false) => Some(AsmValue::Address(b)),
+        (AsmValue::Address(a), AsmValue::Address(b), true) => distance(b, a).map(AsmValue::Number),
+        _ => None,
+    }
+}
+
+/// Number of words from `from` to `to`, if both are in the same range.
+fn distance(from: AgcAddr, to: AgcAddr) -> Option<i32> {
+    match (from, to) {
+        (
+            AgcAddr::Erasable {
+                bank: b0,
+                offset: o0,
+            },
+            AgcAddr::Erasable {
+                bank: b1,
+                offset: o1,
+            },
+        ) => {
+            let words = RAM_BANK_NUM_WORDS as i32;
+            Some((b1 as i32 * words + o1 as i32) - (b0 as i32 * words + o0 as i32))
+        }
+        (
+            AgcAddr::Fixed {
+                bank: b0,
+                offset: o0,
+            },
+            AgcAddr::Fixed {
+                bank: b1,
+                offset: o1,
+            },
+        ) if b0 == b1 => Some(o1 as i32 - o0 as i32),
+        _ => None,
+    }
+}
+
+/// Parses the operand of OCT into a word.
+pub(crate) fn parse_oct(s: &str) -> Result<u16, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidConstant(s.to_string());
+    if s.is_empty() {
+        return Err(AsmErrorKind::MissingOperand);
+    }
+    match u16::from_str_radix(s, 8) {
+        Ok(x) if x <= 0o77777 => Ok(x),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses the operand of DEC (`double` false) or 2DEC into its words.
+///
+/// A plain integer is taken as is. A number with a decimal point or an
+/// `E` or `B` scale factor is a fraction, where 1.0 is 2^14 in single and
+/// 2^28 in double precision: `DEC 5 B-3` is 5/8. Negative values are ones'
+/// complemented, so `DEC -0` is minus zero.
+pub(crate) fn parse_dec(s: &str, double: bool) -> Result<[u16; 2], AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidConstant(s.to_string());
+    let text: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    if text.is_empty() {
+        return Err(AsmErrorKind::MissingOperand);
+    }
+
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(x) => (true, x),
+        None => (false, text.strip_prefix('+').unwrap_or(&text)),
+    };
+
+    let end = text.find(['E', 'B']).unwrap_or(text.len());
+    let (mantissa, mut scales) = text.split_at(end);
+    if mantissa.is_empty()
+        || mantissa == "."
+        || !mantissa.bytes().all(|c| c.is_ascii_digit() || c == b'.')
+    {
+        return Err(invalid());
+    }
+
+    let mut fraction = mantissa.contains('.');
+    let mut value: f64 = mantissa.parse().map_err(|_| invalid())?;
+    while !scales.is_empty() {
+        let kind = scales.as_bytes()[0];
+        scales = &scales[1..];
+        let end = scales.find(['E', 'B']).unwrap_or(scales.len());
+        let exp: i32 = scales[..end].parse().map_err(|_| invalid())?;
+        scales = &scales[end..];
+
+        value *= match kind {
+            b'E' => 10f64.powi(exp),
+            _ => 2f64.powi(exp),
+        };
+        fraction = true;
+    }
+
+    let bits = if double { 28 } else { 14 };
+    let magnitude = if fraction {
+        (value * 2f64.powi(bits)).round()
+    } else {
+        value
+    };
+    if magnitude >= 2f64.powi(bits) {
+        return Err(invalid());
+    }
+
+    let magnitude = magnitude as u32;
+    let mut words = if double {
+        [(magnitude >> 14) as u16, (magnitude & 0o37777) as u16]
+    } else {
+        [magnitude as u16, 0]
+    };
+    if negative {
+        for x in words.iter_mut() {
+            *x = !*x & 0o77777;
+        }
+    }
+    Ok(words)
+}
@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use ragc_core::consts::{RAM_NUM_BANKS, ROM_NUM_BANKS};
+use ragc_core::mem::AgcAddr;
+use ragc_core::symbols::AgcSymbols;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolTableError {
+    /// yaYUL's binary `.symtab`, whose layout depends on the host that
+    /// wrote it. The listing holds the same symbols.
+    Binary,
+    /// A line of a symbol file that is not a name and an address.
+    InvalidLine(usize),
+}
+
+impl fmt::Display for SymbolTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolTableError::Binary => write!(
+                f,
+                "binary yaYUL symbol tables are not supported, load the listing instead"
+            ),
+            SymbolTableError::InvalidLine(x) => {
+                write!(f, "line {}: expected a symbol and an address", x)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SymbolTableError {}
+
+/// Symbols of a rope, mapping names to bank-qualified addresses and back.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    by_name: HashMap<String, AgcAddr>,
+    by_addr: BTreeMap<AgcAddr, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    /// Loads symbols from a yaYUL listing, recognized by its symbol table
+    /// section, or from a symbol file.
+    pub fn parse(data: &[u8]) -> Result<SymbolTable, SymbolTableError> {
+        if data.contains(&0) {
+            return Err(SymbolTableError::Binary);
+        }
+        let text = String::from_utf8_lossy(data);
+        if text.contains("Symbol Table") {
+            Ok(SymbolTable::parse_listing(&text))
+        } else {
+            SymbolTable::parse_symbols(&text)
+        }
+    }
+
+    /// Reads the symbol table section at the end of a yaYUL listing, where
+    /// each entry is a running number, the name and the address:
+    /// `12:  CHECKMM  04,2060`. Symbols of constants rather than locations
+    /// are skipped.
+    pub fn parse_listing(text: &str) -> SymbolTable {
+        let mut table = SymbolTable::new();
+        let lines = text.lines().skip_while(|x| !x.contains("Symbol Table"));
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let mut idx = 0;
+            while idx + 2 < fields.len() {
+                let is_entry = fields[idx]
+                    .strip_suffix(':')
+                    .is_some_and(|x| !x.is_empty() && x.bytes().all(|c| c.is_ascii_digit()));
+                if !is_entry {
+                    idx += 1;
+                    continue;
+                }
+                if let Some(addr) = parse_addr(fields[idx + 2]) {
+                    table.insert(fields[idx + 1], addr);
+                }
+                idx += 3;
+            }
+        }
+        table
+    }
+
+    /// Reads a symbol file: one name and address per line, such as
+    /// `CHECKMM 04,2060` or `AZIMUTH E3,1417`. `#` starts a comment.
+    pub fn parse_symbols(text: &str) -> Result<SymbolTable, SymbolTableError> {
+        let mut table = SymbolTable::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(x) => &line[..x],
+                None => line,
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [] => {}
+                [name, addr] => match parse_addr(addr) {
+                    Some(addr) => table.insert(name, addr),
+                    None => return Err(SymbolTableError::InvalidLine(idx + 1)),
+                },
+                _ => return Err(SymbolTableError::InvalidLine(idx + 1)),
+            }
+        }
+        Ok(table)
+    }
+
+    /// Adds a symbol. A location with several names is shown by the first
+    /// one added.
+    pub fn insert(&mut self, name: &str, addr: AgcAddr) {
+        if let Some(old) = self.by_name.insert(name.to_string(), addr) {
+            if self.by_addr.get(&old).map(|x| x.as_str()) == Some(name) {
+                self.by_addr.remove(&old);
+            }
+        }
+        self.by_addr.entry(addr).or_insert_with(|| name.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<AgcAddr> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Symbol defined exactly at `addr`.
+    pub fn name(&self, addr: AgcAddr) -> Option<&str> {
+        self.by_addr.get(&addr).map(|x| x.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, AgcAddr)> {
+        self.by_name.iter().map(|(k, v)| (k.as_str(), *v))
+    }
+}
+
+impl AgcSymbols for SymbolTable {
+    fn symbol_at(&self, addr: AgcAddr) -> Option<(&str, u16)> {
+        let (found, name) = self.by_addr.range(..=addr).next_back()?;
+        let distance = match (*found, addr) {
+            (AgcAddr::Erasable { bank, offset }, AgcAddr::Erasable { bank: b, offset: o })
+                if bank == b =>
+            {
+                o - offset
+            }
+            (AgcAddr::Fixed { bank, offset }, AgcAddr::Fixed { bank: b, offset: o })
+                if bank == b =>
+            {
+                o - offset
+            }
+            _ => return None,
+        };
+        Some((name.as_str(), distance))
+    }
+
+    fn address_of(&self, name: &str) -> Option<AgcAddr> {
+        self.get(name)
+    }
+}
+
+/// Parses an address the way yaYUL prints them: `E3,1417` and `04,2060`
+/// for banked locations, four octal digits for unswitched ones. Longer
+/// plain numbers are the values of constants.
+fn parse_addr(s: &str) -> Option<AgcAddr> {
+    match s.split_once(',') {
+        Some((bank, addr)) => {
+            let addr = u16::from_str_radix(addr, 8).ok()?;
+            match bank.strip_prefix('E') {
+                Some(ebank) => {
+                    let ebank = u8::from_str_radix(ebank, 8).ok()?;
+                    let x = AgcAddr::from_cpu(addr, ebank, 0);
+                    match x {
+                        AgcAddr::Erasable { .. } if (ebank as usize) < RAM_NUM_BANKS => Some(x),
+                        _ => None,
+                    }
+                }
+                None => {
+                    let fbank = u8::from_str_radix(bank, 8).ok()?;
+                    let x = AgcAddr::from_cpu(addr, 0, fbank);
+                    match x {
+                        AgcAddr::Fixed { .. } if (fbank as usize) < ROM_NUM_BANKS => Some(x),
+                        _ => None,
+                    }
+                }
+            }
+        }
+        None if s.len() <= 4 => s.parse().ok(),
+        None => None,
+    }
+}
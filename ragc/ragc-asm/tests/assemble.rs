@@ -0,0 +1,205 @@
+use heapless::spsc::Queue;
+
+use ragc_asm::{assemble, AsmErrorKind, AsmValue};
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::mods::AgcNullPeriph;
+use ragc_core::mem::{rope_word, AgcAddr, MemoryMap};
+
+fn words(source: &str, bank: usize, count: usize) -> Vec<u16> {
+    let asm = assemble(source).unwrap();
+    (0..count).map(|x| rope_word(asm.rope(), bank, x)).collect()
+}
+
+fn error(source: &str) -> (usize, AsmErrorKind) {
+    let err = assemble(source).err().unwrap();
+    (err.line, err.kind)
+}
+
+#[test]
+fn encodes_instructions() {
+    let source = "
+        SETLOC  4000
+START   CA      A
+        TS      L
+        DXCH    ARUPT
+        EXTEND
+        DCA     CONST
+        TCF     START
+        NOOP
+        EXTEND
+        MP      CONST +1
+        EXTEND
+        INDEX   Q
+        DV      100
+        EXTEND
+        WRITE   10
+        RELINT
+        TCF     +0
+CONST   2DEC    5
+";
+    assert_eq!(
+        words(source, 2, 18),
+        vec![
+            0o30000, 0o54001, 0o52011, 0o00006, 0o34021, 0o14000, 0o14007, 0o00006, 0o74021,
+            0o00006, 0o50002, 0o10100, 0o00006, 0o01010, 0o00003, 0o14017, 0o00000, 0o00005,
+        ]
+    );
+}
+
+#[test]
+fn encodes_constants() {
+    let source = "
+        SETLOC  200
+DATA    ERASE   +4
+        SETLOC  4000
+        OCT     12345
+        DEC     100
+        DEC     -1
+        DEC     -0
+        DEC     .5
+        DEC     5 B-3
+        DEC     1 B-14
+        2DEC    -.5
+        ADRES   DATA
+        CADR    FAR
+        BANK    24
+FAR     TC      Q
+";
+    assert_eq!(
+        words(source, 2, 11),
+        vec![
+            0o12345, 0o00144, 0o77776, 0o77777, 0o20000, 0o24000, 0o00001, 0o57777, 0o77777,
+            0o00200, 0o50000,
+        ]
+    );
+}
+
+#[test]
+fn places_erasable_labels_and_banks() {
+    let source = "
+        SETLOC  61
+FIRST   ERASE
+PAIR    ERASE   +1
+LAST    ERASE
+        SETLOC  E3,1400
+SWITCHED ERASE
+MASK3   EQUALS  7
+        BANK    24
+ONE     TC      Q
+        BANK    25
+TWO     TC      Q
+        BANK    24
+THREE   TC      Q
+";
+    let asm = assemble(source).unwrap();
+    let erasable = |offset| AgcAddr::Erasable { bank: 0, offset };
+    assert_eq!(asm.addr("FIRST"), Some(erasable(0o61)));
+    assert_eq!(asm.addr("PAIR"), Some(erasable(0o62)));
+    assert_eq!(asm.addr("LAST"), Some(erasable(0o64)));
+    assert_eq!(
+        asm.addr("SWITCHED"),
+        Some(AgcAddr::Erasable { bank: 3, offset: 0 })
+    );
+    assert_eq!(asm.symbol("MASK3"), Some(AsmValue::Number(7)));
+    assert_eq!(
+        asm.addr("TWO"),
+        Some(AgcAddr::Fixed {
+            bank: 0o25,
+            offset: 0
+        })
+    );
+    assert_eq!(
+        asm.addr("THREE"),
+        Some(AgcAddr::Fixed {
+            bank: 0o24,
+            offset: 1
+        })
+    );
+    assert_eq!(rope_word(asm.rope(), 0o24, 1), 0o00002);
+}
+
+#[test]
+fn rope_words_have_odd_parity() {
+    let asm = assemble("        SETLOC  4000\n        OCT     3\n        OCT     7\n").unwrap();
+    for offset in 0..2 {
+        let raw = u16::from_be(asm.rope()[0][offset]);
+        assert_eq!(raw.count_ones() % 2, 1);
+    }
+}
+
+#[test]
+fn reports_errors_with_line_numbers() {
+    assert_eq!(
+        error("        SETLOC  4000\n        MP      A\n"),
+        (2, AsmErrorKind::MissingExtend("MP".into()))
+    );
+    assert_eq!(
+        error("        SETLOC  4000\n        EXTEND\n        CA      A\n"),
+        (3, AsmErrorKind::UnexpectedExtend("CA".into()))
+    );
+    assert_eq!(
+        error("        SETLOC  4000\n        TC      NOWHERE\n"),
+        (2, AsmErrorKind::UndefinedSymbol("NOWHERE".into()))
+    );
+    assert_eq!(
+        error("        FOO     1\n"),
+        (1, AsmErrorKind::UnknownOpcode("FOO".into()))
+    );
+    assert_eq!(error("        TC      A\n"), (1, AsmErrorKind::NoLocation));
+    assert_eq!(
+        error("        SETLOC  2000\n"),
+        (1, AsmErrorKind::AmbiguousAddress(0o2000))
+    );
+    assert_eq!(
+        error("        SETLOC  4000\n        TS      4000\n"),
+        (2, AsmErrorKind::OperandRange("4000".into()))
+    );
+    assert_eq!(
+        error("        SETLOC  4000\nX       TC      A\nX       TC      A\n"),
+        (3, AsmErrorKind::DuplicateSymbol("X".into()))
+    );
+    assert_eq!(
+        error("        SETLOC  4000\n        TC      A\n        SETLOC  4000\n        TC      A\n"),
+        (
+            4,
+            AsmErrorKind::Overlap(AgcAddr::Fixed { bank: 2, offset: 0 })
+        )
+    );
+}
+
+#[test]
+fn assembled_program_runs() {
+    let source = "
+        SETLOC  100
+PRODUCT ERASE   +1
+        SETLOC  4000
+        CA      SIX
+        EXTEND
+        MP      SEVEN
+        DXCH    PRODUCT
+        TCF     +0
+SIX     DEC     6
+SEVEN   DEC     7
+";
+    let asm = assemble(source).unwrap();
+
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let (mut downrupt, mut dsky) = (AgcNullPeriph, AgcNullPeriph);
+    let mm = MemoryMap::new(asm.rope(), &mut downrupt, &mut dsky, rupt_rx);
+    let mut cpu = AgcCpu::new(mm);
+    cpu.reset();
+    for _ in 0..6 {
+        cpu.step().unwrap();
+    }
+
+    let product = asm.addr("PRODUCT").unwrap();
+    assert_eq!(cpu.peek(product), 0);
+    assert_eq!(
+        cpu.peek(AgcAddr::Erasable {
+            bank: 0,
+            offset: 0o101
+        }),
+        42
+    );
+}
@@ -0,0 +1,98 @@
+use ragc_asm::{assemble, SymbolTable, SymbolTableError};
+use ragc_core::disasm::AgcBankListing;
+use ragc_core::mem::AgcAddr;
+use ragc_core::symbols::{parse_symbolic, AgcSymbolic, AgcSymbols};
+
+fn fixed(bank: u8, offset: u16) -> AgcAddr {
+    AgcAddr::Fixed { bank, offset }
+}
+
+fn erasable(bank: u8, offset: u16) -> AgcAddr {
+    AgcAddr::Erasable { bank, offset }
+}
+
+#[test]
+fn reads_yayul_listings() {
+    let listing = b"
+004061,000012: 04,2060           CHECKMM        MASK     LOW7
+004062,000013: 04,2061                          XCH      RUPTREG4
+
+Symbol Table
+------------
+     1:  AZIMUTH          E3,1417        2:  CHECKMM          04,2060        3:  LOW7             4017
+     4:  NEWJOB           0067           5:  SIXTY            00074
+";
+    let table = SymbolTable::parse(listing).unwrap();
+
+    assert_eq!(table.len(), 4);
+    assert_eq!(table.get("AZIMUTH"), Some(erasable(3, 0o17)));
+    assert_eq!(table.get("CHECKMM"), Some(fixed(4, 0o60)));
+    assert_eq!(table.get("LOW7"), Some(fixed(2, 0o17)));
+    assert_eq!(table.get("NEWJOB"), Some(erasable(0, 0o67)));
+    assert_eq!(table.get("SIXTY"), None);
+}
+
+#[test]
+fn reads_symbol_files() {
+    let text = "# name  address\nCHECKMM 04,2060\n\nAZIMUTH E3,1417  # pad loaded\n";
+    let table = SymbolTable::parse(text.as_bytes()).unwrap();
+    assert_eq!(table.name(fixed(4, 0o60)), Some("CHECKMM"));
+    assert_eq!(table.name(erasable(3, 0o17)), Some("AZIMUTH"));
+
+    assert_eq!(
+        SymbolTable::parse(b"CHECKMM\n").err(),
+        Some(SymbolTableError::InvalidLine(1))
+    );
+    assert_eq!(
+        SymbolTable::parse(b"CHECKMM 04,2060\nLOW7 9999\n").err(),
+        Some(SymbolTableError::InvalidLine(2))
+    );
+    assert_eq!(
+        SymbolTable::parse(b"\x01\x00\x00\x00").err(),
+        Some(SymbolTableError::Binary)
+    );
+}
+
+#[test]
+fn addresses_are_shown_by_symbol() {
+    let table = SymbolTable::parse(b"CHECKMM 04,2060\nREFSMMAT E3,1400\n").unwrap();
+    let symbols = Some(&table as &dyn AgcSymbols);
+    let show = |addr| AgcSymbolic::new(addr, symbols).to_string();
+
+    assert_eq!(show(fixed(4, 0o60)), "CHECKMM");
+    assert_eq!(show(fixed(4, 0o63)), "CHECKMM +3");
+    assert_eq!(show(fixed(4, 0o57)), "04,2057");
+    assert_eq!(show(fixed(5, 0o63)), "05,2063");
+    assert_eq!(show(erasable(3, 0o4)), "REFSMMAT +4");
+
+    assert_eq!(parse_symbolic("CHECKMM+3", symbols), Some(fixed(4, 0o63)));
+    assert_eq!(parse_symbolic("REFSMMAT", symbols), Some(erasable(3, 0)));
+    assert_eq!(parse_symbolic("REFSMMAT+400", symbols), None);
+    assert_eq!(parse_symbolic("REFSMMAT+177777", symbols), None);
+    assert_eq!(parse_symbolic("CHECKMM+177777", symbols), None);
+    assert_eq!(parse_symbolic("E3,1404", symbols), Some(erasable(3, 4)));
+    assert_eq!(parse_symbolic("CHECKMM", None), None);
+}
+
+#[test]
+fn listings_use_assembled_symbols() {
+    let source = "
+        SETLOC  4000
+START   TC      CHECK
+        TCF     START +1
+CHECK   CA      START
+        RETURN
+";
+    let asm = assemble(source).unwrap();
+    let table = asm.symbol_table();
+    assert_eq!(table.get("START"), Some(fixed(2, 0)));
+
+    let lines: Vec<String> = AgcBankListing::new(asm.rope(), 2)
+        .with_symbols(Some(&table))
+        .take(4)
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(lines[0], "4000     04002    START     TC     CHECK");
+    assert_eq!(lines[1], "4001     14001              TCF    START +1");
+    assert_eq!(lines[2], "4002     34000    CHECK     CA     START");
+}
@@ -1,5 +1,11 @@
 #![no_std]
 
+pub mod rope;
+pub mod verify;
+
+pub use rope::{detect_format, load_rope, load_rope_as, AgcRope, AgcRopeError, AgcRopeFormat};
+pub use verify::{bank_checksum, check_bank, verify_rope, AgcBankStatus, AgcRopeReport};
+
 const ROM_BANKS_NUM: usize = 36;
 const ROM_BANK_NUM_WORDS: usize = 1024;
 
@@ -0,0 +1,239 @@
+use core::fmt;
+
+use ragc_core::consts::{ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use ragc_core::mem::set_rope_word;
+
+/// Rope image in the layout `ragc_core::mem::MemoryMap` expects.
+pub type AgcRope = [[u16; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS];
+
+/// Size of a full yaYUL `.bin` image, in bytes.
+pub const ROPE_IMAGE_SIZE: usize = ROM_NUM_BANKS * ROM_BANK_NUM_WORDS * 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcRopeFormat {
+    /// yaYUL binary: big-endian words shifted left by one for the parity
+    /// bit, with banks 02 and 03 first.
+    Bin,
+    /// yaYUL `.binsource`: octal words without parity, grouped by `BANK=`
+    /// lines, with `;` starting a comment.
+    BinSource,
+    /// Whitespace separated octal words without parity, in bank order
+    /// starting at bank 00.
+    Octal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcRopeError {
+    Empty,
+    /// A binary image that is not a whole number of banks, or larger than
+    /// the rope. Holds its size in bytes.
+    InvalidSize(usize),
+    /// An octal dump that is not a whole number of banks, or larger than
+    /// the rope. Holds its number of words.
+    InvalidWordCount(usize),
+    /// Text that is not a 15-bit octal word, at a 1-based line.
+    InvalidWord {
+        line: usize,
+    },
+    /// A `BANK=` line without a valid fixed bank number.
+    InvalidBank {
+        line: usize,
+    },
+    /// Words before the first `BANK=` line.
+    NoBank {
+        line: usize,
+    },
+    /// More words than fit into the bank.
+    BankOverflow {
+        line: usize,
+        bank: usize,
+    },
+}
+
+impl fmt::Display for AgcRopeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgcRopeError::Empty => write!(f, "empty rope image"),
+            AgcRopeError::InvalidSize(x) => write!(
+                f,
+                "binary rope is {} bytes, expected whole banks of {} bytes up to {}",
+                x,
+                ROM_BANK_NUM_WORDS * 2,
+                ROPE_IMAGE_SIZE
+            ),
+            AgcRopeError::InvalidWordCount(x) => write!(
+                f,
+                "octal rope has {} words, expected whole banks of {} words up to {}",
+                x,
+                ROM_BANK_NUM_WORDS,
+                ROM_NUM_BANKS * ROM_BANK_NUM_WORDS
+            ),
+            AgcRopeError::InvalidWord { line } => write!(f, "line {}: invalid octal word", line),
+            AgcRopeError::InvalidBank { line } => write!(f, "line {}: invalid bank", line),
+            AgcRopeError::NoBank { line } => {
+                write!(f, "line {}: words before the first BANK= line", line)
+            }
+            AgcRopeError::BankOverflow { line, bank } => {
+                write!(
+                    f,
+                    "line {}: bank {:02o} has more than {} words",
+                    line, bank, ROM_BANK_NUM_WORDS
+                )
+            }
+        }
+    }
+}
+
+/// Guesses the format of a rope image. Anything that is not plain ASCII
+/// text is taken as a binary image, text with `BANK=` lines as yaYUL
+/// `.binsource` and other text as an octal dump.
+pub fn detect_format(data: &[u8]) -> AgcRopeFormat {
+    let text = data
+        .iter()
+        .all(|x| x.is_ascii_graphic() || x.is_ascii_whitespace());
+    if !text {
+        return AgcRopeFormat::Bin;
+    }
+
+    let has_banks = data
+        .split(|x| *x == b'\n')
+        .any(|line| line.trim_ascii_start().starts_with(b"BANK="));
+    if has_banks {
+        AgcRopeFormat::BinSource
+    } else {
+        AgcRopeFormat::Octal
+    }
+}
+
+/// Loads a rope image of any supported format into `rope` and returns the
+/// format that was detected. Banks the image does not cover are left
+/// blank.
+pub fn load_rope(data: &[u8], rope: &mut AgcRope) -> Result<AgcRopeFormat, AgcRopeError> {
+    let format = detect_format(data);
+    load_rope_as(data, format, rope)?;
+    Ok(format)
+}
+
+/// Loads a rope image of a known format into `rope`.
+pub fn load_rope_as(
+    data: &[u8],
+    format: AgcRopeFormat,
+    rope: &mut AgcRope,
+) -> Result<(), AgcRopeError> {
+    if data.is_empty() {
+        return Err(AgcRopeError::Empty);
+    }
+
+    *rope = [[0; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS];
+    match format {
+        AgcRopeFormat::Bin => load_bin(data, rope),
+        AgcRopeFormat::BinSource => load_binsource(data, rope),
+        AgcRopeFormat::Octal => load_octal(data, rope),
+    }
+}
+
+fn load_bin(data: &[u8], rope: &mut AgcRope) -> Result<(), AgcRopeError> {
+    if !data.len().is_multiple_of(ROM_BANK_NUM_WORDS * 2) || data.len() > ROPE_IMAGE_SIZE {
+        return Err(AgcRopeError::InvalidSize(data.len()));
+    }
+
+    // The image is already in the stored layout, so the words are copied
+    // as they are.
+    for (idx, word) in data.chunks(2).enumerate() {
+        rope[idx / ROM_BANK_NUM_WORDS][idx % ROM_BANK_NUM_WORDS] =
+            u16::from_ne_bytes([word[0], word[1]]);
+    }
+    Ok(())
+}
+
+fn load_binsource(data: &[u8], rope: &mut AgcRope) -> Result<(), AgcRopeError> {
+    let mut bank = None;
+    let mut offset = 0;
+    for (idx, line) in data.split(|x| *x == b'\n').enumerate() {
+        let line_num = idx + 1;
+        let line = match line.iter().position(|x| *x == b';') {
+            Some(x) => &line[..x],
+            None => line,
+        };
+        let line = line.trim_ascii();
+
+        if let Some(value) = line.strip_prefix(b"BANK=") {
+            bank = match parse_octal(value) {
+                Some(x) if (x as usize) < ROM_NUM_BANKS => Some(x as usize),
+                _ => return Err(AgcRopeError::InvalidBank { line: line_num }),
+            };
+            offset = 0;
+            continue;
+        }
+        // Other directives, such as the listing's page numbers or the
+        // expected bugger words, do not hold any rope words.
+        if line.contains(&b'=') {
+            continue;
+        }
+
+        for field in line.split(|x| x.is_ascii_whitespace()) {
+            if field.is_empty() {
+                continue;
+            }
+            let bank = bank.ok_or(AgcRopeError::NoBank { line: line_num })?;
+            if offset >= ROM_BANK_NUM_WORDS {
+                return Err(AgcRopeError::BankOverflow {
+                    line: line_num,
+                    bank,
+                });
+            }
+            // Oct2Bin writes `@` for words the listing leaves unused.
+            let value = match field {
+                b"@" => 0,
+                _ => parse_octal(field).ok_or(AgcRopeError::InvalidWord { line: line_num })?,
+            };
+            set_rope_word(rope, bank, offset, value);
+            offset += 1;
+        }
+    }
+    Ok(())
+}
+
+fn load_octal(data: &[u8], rope: &mut AgcRope) -> Result<(), AgcRopeError> {
+    let mut count = 0;
+    for (idx, line) in data.split(|x| *x == b'\n').enumerate() {
+        for field in line.split(|x| x.is_ascii_whitespace()) {
+            if field.is_empty() {
+                continue;
+            }
+            let value = parse_octal(field).ok_or(AgcRopeError::InvalidWord { line: idx + 1 })?;
+            if count < ROM_NUM_BANKS * ROM_BANK_NUM_WORDS {
+                set_rope_word(
+                    rope,
+                    count / ROM_BANK_NUM_WORDS,
+                    count % ROM_BANK_NUM_WORDS,
+                    value,
+                );
+            }
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return Err(AgcRopeError::Empty);
+    }
+    if !count.is_multiple_of(ROM_BANK_NUM_WORDS) || count > ROM_NUM_BANKS * ROM_BANK_NUM_WORDS {
+        return Err(AgcRopeError::InvalidWordCount(count));
+    }
+    Ok(())
+}
+
+/// Parses an octal number that fits into 15 bits.
+fn parse_octal(field: &[u8]) -> Option<u16> {
+    if field.is_empty() || field.len() > 5 {
+        return None;
+    }
+    let mut value = 0;
+    for x in field {
+        if !(b'0'..=b'7').contains(x) {
+            return None;
+        }
+        value = (value << 3) | (x - b'0') as u16;
+    }
+    Some(value)
+}
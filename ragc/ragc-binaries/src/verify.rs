@@ -0,0 +1,141 @@
+use ragc_core::consts::{ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use ragc_core::mem::rope_word;
+
+use crate::rope::AgcRope;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcBankStatus {
+    /// The bank sums to plus or minus its bank number.
+    Good,
+    /// Every word of the bank is zero.
+    Blank,
+    /// The bank does not sum to its bank number. Holds the sum.
+    Bad(u16),
+}
+
+/// Result of checking a whole rope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcRopeReport {
+    pub banks: [AgcBankStatus; ROM_NUM_BANKS],
+    /// CRC-32 of the image as a yaYUL `.bin` file, parity bits included.
+    pub image_crc32: u32,
+    /// CRC-32 of the 15-bit words in bank order, as big-endian pairs of
+    /// bytes. Unlike `image_crc32` it does not depend on the parity bits,
+    /// so a rope gives the same value whichever format it was loaded from.
+    pub words_crc32: u32,
+}
+
+impl AgcRopeReport {
+    /// Banks that hold words but fail the checksum.
+    pub fn bad_banks(&self) -> impl Iterator<Item = (usize, u16)> + '_ {
+        self.banks
+            .iter()
+            .enumerate()
+            .filter_map(|(bank, status)| match status {
+                AgcBankStatus::Bad(sum) => Some((bank, *sum)),
+                _ => None,
+            })
+    }
+
+    pub fn is_good(&self) -> bool {
+        self.bad_banks().next().is_none()
+    }
+}
+
+/// Adds two words the way the rope self-check does: in ones' complement,
+/// with an overflow carried back into the sum.
+fn checksum_add(a: u16, b: u16) -> u16 {
+    let signed = |x: u16| -> i32 {
+        if x & 0o40000 != 0 {
+            -((!x & 0o77777) as i32)
+        } else {
+            x as i32
+        }
+    };
+
+    let mut sum = signed(a) + signed(b);
+    if sum > 0o37777 {
+        sum -= 0o37777;
+    } else if sum < -0o37777 {
+        sum += 0o37777;
+    }
+
+    if sum < 0 {
+        !(-sum) as u16 & 0o77777
+    } else {
+        sum as u16
+    }
+}
+
+/// Sums every word of a fixed bank. A bank closed by a bugger word sums to
+/// plus or minus its bank number.
+pub fn bank_checksum(rope: &AgcRope, bank: usize) -> u16 {
+    (0..ROM_BANK_NUM_WORDS).fold(0, |sum, offset| {
+        checksum_add(sum, rope_word(rope, bank, offset))
+    })
+}
+
+/// Checks a single fixed bank against the bugger word rule.
+pub fn check_bank(rope: &AgcRope, bank: usize) -> AgcBankStatus {
+    if (0..ROM_BANK_NUM_WORDS).all(|offset| rope_word(rope, bank, offset) == 0) {
+        return AgcBankStatus::Blank;
+    }
+
+    let sum = bank_checksum(rope, bank);
+    if sum == bank as u16 || sum == !(bank as u16) & 0o77777 {
+        AgcBankStatus::Good
+    } else {
+        AgcBankStatus::Bad(sum)
+    }
+}
+
+/// Checks every bank of a rope and hashes the image.
+pub fn verify_rope(rope: &AgcRope) -> AgcRopeReport {
+    let mut banks = [AgcBankStatus::Blank; ROM_NUM_BANKS];
+    for (bank, status) in banks.iter_mut().enumerate() {
+        *status = check_bank(rope, bank);
+    }
+
+    let mut image_crc = Crc32::new();
+    for word in rope.iter().flatten() {
+        image_crc.update(&word.to_ne_bytes());
+    }
+    let mut words_crc = Crc32::new();
+    for bank in 0..ROM_NUM_BANKS {
+        for offset in 0..ROM_BANK_NUM_WORDS {
+            words_crc.update(&rope_word(rope, bank, offset).to_be_bytes());
+        }
+    }
+
+    AgcRopeReport {
+        banks,
+        image_crc32: image_crc.finish(),
+        words_crc32: words_crc.finish(),
+    }
+}
+
+/// CRC-32 as used by zip and `crc32(1)`, so image hashes can be compared
+/// with the usual tools.
+struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    fn new() -> Crc32 {
+        Crc32 { crc: 0xFFFFFFFF }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.crc ^= *byte as u32;
+            for _ in 0..8 {
+                let mask = (self.crc & 1).wrapping_neg();
+                self.crc = (self.crc >> 1) ^ (0xEDB88320 & mask);
+            }
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        !self.crc
+    }
+}
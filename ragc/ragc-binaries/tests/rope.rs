@@ -0,0 +1,111 @@
+use ragc_binaries::{load_rope, AgcRope, AgcRopeError, AgcRopeFormat, RETREAD50_ROPE};
+use ragc_core::consts::{ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use ragc_core::mem::rope_word;
+
+fn blank() -> Box<AgcRope> {
+    Box::new([[0; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS])
+}
+
+/// Writes RETREAD50 as a `.binsource` listing, eight words per line.
+fn retread50_binsource() -> String {
+    let mut text = String::from("; RETREAD50\n");
+    for bank in 0..ROM_NUM_BANKS {
+        text += &format!("\nBANK={:o}\n", bank);
+        for (idx, offset) in (0..ROM_BANK_NUM_WORDS).enumerate() {
+            let sep = if idx % 8 == 7 { "\n" } else { " " };
+            text += &format!("{:05o}{}", rope_word(RETREAD50_ROPE, bank, offset), sep);
+        }
+        text += "BUGGER=00000 ; not checked\n";
+    }
+    text
+}
+
+fn retread50_octal() -> String {
+    let mut text = String::new();
+    for bank in 0..ROM_NUM_BANKS {
+        for offset in 0..ROM_BANK_NUM_WORDS {
+            text += &format!("{:05o}\n", rope_word(RETREAD50_ROPE, bank, offset));
+        }
+    }
+    text
+}
+
+/// Compares word values only, RETREAD50's parity bits are not all set.
+fn same_words(rope: &AgcRope) -> bool {
+    (0..ROM_NUM_BANKS).all(|bank| {
+        (0..ROM_BANK_NUM_WORDS)
+            .all(|offset| rope_word(rope, bank, offset) == rope_word(RETREAD50_ROPE, bank, offset))
+    })
+}
+
+fn load(data: &[u8]) -> Result<(AgcRopeFormat, Box<AgcRope>), AgcRopeError> {
+    let mut rope = blank();
+    let format = load_rope(data, &mut rope)?;
+    Ok((format, rope))
+}
+
+#[test]
+fn bin_image_loads_as_is() {
+    let data = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/RETREAD50.bin")).unwrap();
+    let (format, rope) = load(&data).unwrap();
+    assert_eq!(format, AgcRopeFormat::Bin);
+    assert!(*rope == *RETREAD50_ROPE);
+}
+
+#[test]
+fn binsource_matches_bin() {
+    let (format, rope) = load(retread50_binsource().as_bytes()).unwrap();
+    assert_eq!(format, AgcRopeFormat::BinSource);
+    assert!(same_words(&rope));
+}
+
+#[test]
+fn octal_dump_matches_bin() {
+    let (format, rope) = load(retread50_octal().as_bytes()).unwrap();
+    assert_eq!(format, AgcRopeFormat::Octal);
+    assert!(same_words(&rope));
+}
+
+#[test]
+fn partial_ropes_leave_other_banks_blank() {
+    let (_, rope) = load(b"BANK=2\n30000 @ 00001\n").unwrap();
+    assert_eq!(rope_word(&rope, 2, 0), 0o30000);
+    assert_eq!(rope_word(&rope, 2, 1), 0);
+    assert_eq!(rope_word(&rope, 2, 2), 0o00001);
+    assert_eq!(rope_word(&rope, 3, 0), 0);
+}
+
+#[test]
+fn invalid_images_are_reported() {
+    assert_eq!(load(b"").unwrap_err(), AgcRopeError::Empty);
+    assert_eq!(
+        load(&[0; 2047]).unwrap_err(),
+        AgcRopeError::InvalidSize(2047)
+    );
+    assert_eq!(
+        load(b"12345\n70000\n").unwrap_err(),
+        AgcRopeError::InvalidWordCount(2)
+    );
+    assert_eq!(
+        load(b"BANK=2\n12345 100000\n").unwrap_err(),
+        AgcRopeError::InvalidWord { line: 2 }
+    );
+    assert_eq!(
+        load(b"BANK=44\n").unwrap_err(),
+        AgcRopeError::InvalidBank { line: 1 }
+    );
+    assert_eq!(
+        load(b"00000\nBANK=2\n").unwrap_err(),
+        AgcRopeError::NoBank { line: 1 }
+    );
+
+    let mut text = String::from("BANK=3\n");
+    text += &"00000\n".repeat(ROM_BANK_NUM_WORDS + 1);
+    assert_eq!(
+        load(text.as_bytes()).unwrap_err(),
+        AgcRopeError::BankOverflow {
+            line: ROM_BANK_NUM_WORDS + 2,
+            bank: 3
+        }
+    );
+}
@@ -0,0 +1,79 @@
+use ragc_binaries::{
+    bank_checksum, check_bank, load_rope, verify_rope, AgcBankStatus, AgcRope, RETREAD50_ROPE,
+};
+use ragc_core::consts::{ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use ragc_core::mem::{rope_word, set_rope_word};
+
+fn blank() -> Box<AgcRope> {
+    Box::new([[0; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS])
+}
+
+/// Fills bank 02 with two words whose sum overflows, closed by a bugger
+/// word for a sum of `target`.
+fn bank2(target: i32) -> Box<AgcRope> {
+    let mut rope = blank();
+    set_rope_word(&mut rope, 2, 0, 0o30000);
+    set_rope_word(&mut rope, 2, 1, 0o30000);
+    assert_eq!(bank_checksum(&rope, 2), 0o20001);
+
+    let bugger = target - 0o20001;
+    let bugger = if bugger < 0 {
+        !(-bugger) as u16 & 0o77777
+    } else {
+        bugger as u16
+    };
+    set_rope_word(&mut rope, 2, 2, bugger);
+    rope
+}
+
+#[test]
+fn bugger_words_close_banks() {
+    let rope = bank2(2);
+    assert_eq!(bank_checksum(&rope, 2), 0o00002);
+    assert_eq!(check_bank(&rope, 2), AgcBankStatus::Good);
+
+    let rope = bank2(-2);
+    assert_eq!(bank_checksum(&rope, 2), 0o77775);
+    assert_eq!(check_bank(&rope, 2), AgcBankStatus::Good);
+
+    let rope = bank2(3);
+    assert_eq!(check_bank(&rope, 2), AgcBankStatus::Bad(0o00003));
+    assert_eq!(check_bank(&rope, 3), AgcBankStatus::Blank);
+
+    let report = verify_rope(&rope);
+    assert!(!report.is_good());
+    assert_eq!(report.bad_banks().collect::<Vec<_>>(), vec![(2, 0o00003)]);
+}
+
+#[test]
+fn byte_swapped_images_fail() {
+    let mut rope = bank2(2);
+    for word in rope.iter_mut().flatten() {
+        *word = word.swap_bytes();
+    }
+    assert!(matches!(check_bank(&rope, 2), AgcBankStatus::Bad(_)));
+}
+
+#[test]
+fn retread50_hashes() {
+    let report = verify_rope(RETREAD50_ROPE);
+    assert_eq!(report.image_crc32, 0xC877A38A);
+
+    // RETREAD50 predates bugger words, only its unused banks pass.
+    assert_eq!(report.bad_banks().count(), 0o12);
+    assert!(report.banks[0o12..]
+        .iter()
+        .all(|x| *x == AgcBankStatus::Blank));
+
+    let mut text = String::new();
+    for bank in 0..ROM_NUM_BANKS {
+        for offset in 0..ROM_BANK_NUM_WORDS {
+            text += &format!("{:05o}\n", rope_word(RETREAD50_ROPE, bank, offset));
+        }
+    }
+    let mut rope = blank();
+    load_rope(text.as_bytes(), &mut rope).unwrap();
+    let octal = verify_rope(&rope);
+    assert_eq!(octal.words_crc32, report.words_crc32);
+    assert_ne!(octal.image_crc32, report.image_crc32);
+}
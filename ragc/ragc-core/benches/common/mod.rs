@@ -0,0 +1,12 @@
+use ragc_core::cpu::AgcCpu;
+
+/// Number of MCTs run by each benchmark iteration.
+pub const CYCLES: usize = 100000;
+
+/// Runs the CPU for `CYCLES` MCTs.
+pub fn run(cpu: &mut AgcCpu) {
+    let end = cpu.total_cycles + CYCLES;
+    while cpu.total_cycles < end {
+        cpu.step().unwrap();
+    }
+}
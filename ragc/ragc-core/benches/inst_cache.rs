@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+mod common;
+use common::{run, CYCLES};
+
+use ragc_core::cpu::AgcCpu;
+use ragc_core::inst_cache::AgcInstCache;
+use ragc_core::mem::mods::AgcNullPeriph;
+use ragc_core::mem::MemoryMap;
+
+fn bench_inst_cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inst_cache");
+    group.throughput(Throughput::Elements(CYCLES as u64));
+
+    for &cached in [false, true].iter() {
+        let mut queue = heapless::spsc::Queue::new();
+        let (_rupt_tx, rupt_rx) = queue.split();
+        let (mut downrupt, mut dsky) = (AgcNullPeriph, AgcNullPeriph);
+        let mut cache = Box::new(AgcInstCache::new());
+
+        let mm = MemoryMap::new(
+            ragc_binaries::RETREAD50_ROPE,
+            &mut downrupt,
+            &mut dsky,
+            rupt_rx,
+        );
+        let mut cpu = AgcCpu::new(mm);
+        if cached {
+            cpu.set_inst_cache(&mut cache);
+        }
+        cpu.reset();
+
+        let name = if cached { "cached" } else { "uncached" };
+        group.bench_function(name, |b| b.iter(|| run(&mut cpu)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_inst_cache);
+criterion_main!(benches);
@@ -0,0 +1,97 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+mod common;
+use common::{run, CYCLES};
+
+use ragc_core::consts::cpu::*;
+use ragc_core::consts::io;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::mods::{AgcIoPeriph, AgcNullPeriph};
+use ragc_core::mem::MemoryMap;
+
+/// Requests the interrupts of `mask` every `every` interrupt polls.
+struct RuptPeriph {
+    mask: u16,
+    every: u32,
+    polls: u32,
+}
+
+impl AgcIoPeriph for RuptPeriph {
+    fn read(&self, _channel_idx: usize) -> u16 {
+        0
+    }
+    fn write(&mut self, _channel_idx: usize, _value: u16) {}
+    fn is_interrupt(&mut self) -> u16 {
+        self.polls += 1;
+        if self.polls == self.every {
+            self.polls = 0;
+            self.mask
+        } else {
+            0
+        }
+    }
+}
+
+/// DSKY pressing a new key on every interrupt poll.
+struct KeyStormDsky {
+    keys: &'static [u16],
+    idx: usize,
+}
+
+impl AgcIoPeriph for KeyStormDsky {
+    fn read(&self, channel_idx: usize) -> u16 {
+        match channel_idx {
+            io::CHANNEL_MNKEYIN => self.keys[self.idx],
+            _ => 0,
+        }
+    }
+    fn write(&mut self, _channel_idx: usize, _value: u16) {}
+    fn is_interrupt(&mut self) -> u16 {
+        self.idx = (self.idx + 1) % self.keys.len();
+        1 << RUPT_KEY1
+    }
+}
+
+/// Boots RETREAD50 with the given peripherals and benchmarks its MCT rate.
+fn bench_rope(
+    c: &mut Criterion,
+    name: &str,
+    downrupt: &mut dyn AgcIoPeriph,
+    dsky: &mut dyn AgcIoPeriph,
+) {
+    let mut queue = heapless::spsc::Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mm = MemoryMap::new(ragc_binaries::RETREAD50_ROPE, downrupt, dsky, rupt_rx);
+    let mut cpu = AgcCpu::new(mm);
+    cpu.reset();
+
+    let mut group = c.benchmark_group("throughput");
+    group.throughput(Throughput::Elements(CYCLES as u64));
+    group.bench_function(name, |b| b.iter(|| run(&mut cpu)));
+    group.finish();
+}
+
+fn steady_state(c: &mut Criterion) {
+    bench_rope(c, "steady_state", &mut AgcNullPeriph, &mut AgcNullPeriph);
+}
+
+fn timer_rupts(c: &mut Criterion) {
+    let mut rupts = RuptPeriph {
+        mask: (1 << RUPT_TIME3) | (1 << RUPT_TIME4) | (1 << RUPT_TIME5),
+        every: 16,
+        polls: 0,
+    };
+    bench_rope(c, "timer_rupts", &mut rupts, &mut AgcNullPeriph);
+}
+
+fn key_storm(c: &mut Criterion) {
+    // VERB 35 ENTR, the lamp test, over and over.
+    let mut dsky = KeyStormDsky {
+        keys: &[0o21, 0o3, 0o5, 0o34],
+        idx: 0,
+    };
+    bench_rope(c, "key_storm", &mut AgcNullPeriph, &mut dsky);
+}
+
+criterion_group!(benches, steady_state, timer_rupts, key_storm);
+criterion_main!(benches);
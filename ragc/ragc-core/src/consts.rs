@@ -16,6 +16,8 @@ pub mod memmap {
 }
 
 pub mod io {
+    pub const NUM_CHANNELS: usize = 0o400;
+
     pub const CHANNEL_L: usize = 0o01;
     pub const CHANNEL_Q: usize = 0o02;
     pub const CHANNEL_HISCALAR: usize = 0o03;
@@ -37,6 +39,23 @@ pub mod io {
     pub const CHANNEL_CHAN33: usize = 0o33;
     pub const CHANNEL_CHAN34: usize = 0o34; // DOWNLIST WORD1
     pub const CHANNEL_CHAN35: usize = 0o35; // DOWNLIST WORD2
+    pub const CHANNEL_CHAN77: usize = 0o77; // RESTART MONITOR
+
+    // Channel 77 restart monitor bits
+    pub const CHAN77_PARITY_FAIL: u16 = 0o00001;
+    pub const CHAN77_ERASABLE_PARITY_FAIL: u16 = 0o00002;
+    pub const CHAN77_TC_TRAP: u16 = 0o00004;
+    pub const CHAN77_RUPT_LOCK: u16 = 0o00010;
+    pub const CHAN77_NIGHT_WATCHMAN: u16 = 0o00020;
+    pub const CHAN77_VOLTAGE_FAIL: u16 = 0o00040;
+    pub const CHAN77_COUNTER_FAIL: u16 = 0o00100;
+
+    // Channel 33 discretes, active low
+    pub const CHAN33_AGC_WARNING: u16 = 0o40000;
+
+    // DSKY lamp bits of the fictitious channel 163
+    pub const CHAN163_AGC_WARNING: u16 = 0o00001;
+    pub const CHAN163_RESTART: u16 = 0o00200;
 }
 
 pub mod cpu {
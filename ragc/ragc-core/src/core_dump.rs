@@ -0,0 +1,105 @@
+use core::fmt;
+
+use crate::consts::{RAM_BANK_NUM_WORDS, RAM_NUM_BANKS};
+
+/// Number of IO channels in a yaAGC core dump.
+pub const CORE_DUMP_CHANNELS: usize = 512;
+
+/// yaAGC's CPU state after a reset, in the order it reads the fields back:
+/// CycleCounter, ExtraCode, AllowInterrupt, PendFlag, PendDelay and
+/// ExtraDelay.
+const RESET_CPU_STATE: [u16; 6] = [0; 6];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcCoreDumpError {
+    /// The dump ends after this many words, before the last erasable bank.
+    Truncated(usize),
+    /// A field that is not an octal word, at a 1-based line.
+    InvalidWord { line: usize },
+}
+
+impl fmt::Display for AgcCoreDumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgcCoreDumpError::Truncated(x) => write!(
+                f,
+                "core dump ends after {} of {} words",
+                x,
+                CORE_DUMP_CHANNELS + RAM_NUM_BANKS * RAM_BANK_NUM_WORDS
+            ),
+            AgcCoreDumpError::InvalidWord { line } => {
+                write!(f, "line {}: invalid octal word", line)
+            }
+        }
+    }
+}
+
+/// Contents of erasable memory and the IO channels in the text format of
+/// yaAGC's core dumps: one octal word per line, first every channel, then
+/// erasable banks 0 to 7. Erasable bank 0 starts with the registers.
+///
+/// yaAGC follows the words with a line of its own CPU state, and refuses a
+/// dump without it. That line is skipped when parsing and written as the
+/// state after a reset, since the CPU always starts from one.
+#[derive(Clone)]
+pub struct AgcCoreDump {
+    pub channels: [u16; CORE_DUMP_CHANNELS],
+    pub erasable: [[u16; RAM_BANK_NUM_WORDS]; RAM_NUM_BANKS],
+}
+
+impl AgcCoreDump {
+    pub fn new() -> AgcCoreDump {
+        AgcCoreDump {
+            channels: [0; CORE_DUMP_CHANNELS],
+            erasable: [[0; RAM_BANK_NUM_WORDS]; RAM_NUM_BANKS],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<AgcCoreDump, AgcCoreDumpError> {
+        let mut dump = AgcCoreDump::new();
+        let total = CORE_DUMP_CHANNELS + RAM_NUM_BANKS * RAM_BANK_NUM_WORDS;
+
+        let mut count = 0;
+        for (idx, line) in text.lines().enumerate() {
+            for field in line.split_whitespace() {
+                if count == total {
+                    return Ok(dump);
+                }
+
+                let word = u16::from_str_radix(field, 8)
+                    .map_err(|_| AgcCoreDumpError::InvalidWord { line: idx + 1 })?;
+                if count < CORE_DUMP_CHANNELS {
+                    dump.channels[count] = word;
+                } else {
+                    let x = count - CORE_DUMP_CHANNELS;
+                    dump.erasable[x / RAM_BANK_NUM_WORDS][x % RAM_BANK_NUM_WORDS] = word;
+                }
+                count += 1;
+            }
+        }
+
+        if count < total {
+            return Err(AgcCoreDumpError::Truncated(count));
+        }
+        Ok(dump)
+    }
+}
+
+impl Default for AgcCoreDump {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for AgcCoreDump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for word in self.channels.iter().chain(self.erasable.iter().flatten()) {
+            writeln!(f, "{:06o}", word)?;
+        }
+        for (idx, field) in RESET_CPU_STATE.iter().enumerate() {
+            let sep = if idx == 0 { "" } else { " " };
+            write!(f, "{}{:o}", sep, field)?;
+        }
+        writeln!(f)
+    }
+}
@@ -0,0 +1,72 @@
+use crate::cpu::AgcOverflow;
+
+/// Output pulse generated by a DINC sequence. POUT and MOUT are produced
+/// when a positive or negative counter steps towards zero, ZOUT when the
+/// counter is already at +0 or -0.
+#[derive(Debug, PartialEq)]
+pub enum AgcDincPulse {
+    POUT,
+    MOUT,
+    ZOUT,
+}
+
+/// PINC: Ones' complement increment of a 15-bit counter. Incrementing
+/// +37777 overflows back to +0.
+pub fn pinc(val: u16) -> (u16, AgcOverflow) {
+    match val & 0o77777 {
+        0o37777 => (0o00000, AgcOverflow::Positive),
+        0o77777 => (0o00001, AgcOverflow::None),
+        v => ((v + 1) & 0o77777, AgcOverflow::None),
+    }
+}
+
+/// MINC: Ones' complement decrement of a 15-bit counter. Decrementing
+/// -37777 overflows back to -0.
+pub fn minc(val: u16) -> (u16, AgcOverflow) {
+    match val & 0o77777 {
+        0o40000 => (0o77777, AgcOverflow::Negative),
+        0o00000 => (0o77776, AgcOverflow::None),
+        v => ((v - 1) & 0o77777, AgcOverflow::None),
+    }
+}
+
+/// PCDU: The CDU counters count in two's complement, so they simply wrap
+/// around without overflowing.
+pub fn pcdu(val: u16) -> (u16, AgcOverflow) {
+    ((val + 1) & 0o77777, AgcOverflow::None)
+}
+
+/// MCDU: Two's complement decrement of a CDU counter.
+pub fn mcdu(val: u16) -> (u16, AgcOverflow) {
+    ((val + 0o77777) & 0o77777, AgcOverflow::None)
+}
+
+/// DINC: Steps the counter one count towards zero, emitting POUT or MOUT
+/// depending on its sign, or ZOUT if it is already zero.
+pub fn dinc(val: u16) -> (u16, AgcDincPulse) {
+    match val & 0o77777 {
+        0o00000 | 0o77777 => (val & 0o77777, AgcDincPulse::ZOUT),
+        v if v & 0o40000 == 0o40000 => (v + 1, AgcDincPulse::MOUT),
+        v => (v - 1, AgcDincPulse::POUT),
+    }
+}
+
+/// SHINC: Shifts a zero into the counter. The bit shifted out of the top
+/// of the word is reported as an overflow.
+pub fn shinc(val: u16) -> (u16, AgcOverflow) {
+    shift(val, 0)
+}
+
+/// SHANC: Shifts a one into the counter.
+pub fn shanc(val: u16) -> (u16, AgcOverflow) {
+    shift(val, 1)
+}
+
+fn shift(val: u16, bit: u16) -> (u16, AgcOverflow) {
+    let res = ((val << 1) | bit) & 0o77777;
+    if val & 0o40000 == 0o40000 {
+        (res, AgcOverflow::Positive)
+    } else {
+        (res, AgcOverflow::None)
+    }
+}
@@ -1,22 +1,36 @@
 use log::{debug, error, trace, warn};
 
 use crate::consts::cpu::*;
+use crate::consts::{io, special, timer};
+use crate::core_dump::AgcCoreDump;
+use crate::counters;
+use crate::counters::AgcDincPulse;
 use crate::decoder::decode;
+use crate::disasm::AgcDisasm;
+use crate::fault::{AgcFault, AgcFaultError, AgcFaultKind, AgcFaults};
+use crate::inst_cache::AgcInstCache;
 use crate::instructions::Instructions;
 use crate::instructions::{AgcInst, AgcMnem};
-use crate::mem::MemoryMap;
+use crate::mem::{AgcAddr, AgcBankContext, MemoryMap};
+use crate::observer::{AgcMemAccess, AgcRegisters, AgcStepInfo, CpuObserver, MAX_STEP_ACCESSES};
+use crate::state::{AgcStateError, MachineState};
+use crate::symbols::AgcSymbols;
 use crate::utils::{overflow_correction, s15_add, sign_extend};
 
+/// Unprogrammed sequences that the CPU executes between instructions. The
+/// counter sequences carry the address of the counter register they operate
+/// on, so peripherals can drive counters by queueing them through
+/// `AgcCpu::set_unprog_seq`.
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum AgcUnprogSeq {
-    PINC,
-    PCDU,
-    MINC,
-    MCDU,
-    DINC,
-    SHINC,
-    SHANC,
+    PINC(usize),
+    PCDU(usize),
+    MINC(usize),
+    MCDU(usize),
+    DINC(usize),
+    SHINC(usize),
+    SHANC(usize),
     INOTRD,
     INOTLD,
     FETCH,
@@ -26,6 +40,51 @@ pub enum AgcUnprogSeq {
     RUPT,
 }
 
+/// Errors raised by the CPU when it is asked to execute something that has no
+/// defined Block II behavior. Every fetched word decodes to an instruction,
+/// so only an `AgcInst` built by hand, rather than by the decoder, can be
+/// invalid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcCpuError {
+    InvalidInstruction { pc: u16, inst_data: u16 },
+}
+
+/// Hardware check that forced the CPU through a GOJ restart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcRestartCause {
+    /// NEWJOB was not tested within 1.92 seconds.
+    NightWatchman,
+    /// The CPU looped on TC, or went too long without one.
+    TcTrap,
+    /// The CPU stayed in, or out of, an interrupt for too long.
+    RuptLock,
+    /// A memory word failed its parity check.
+    Parity,
+    /// Restart forced by an injected fault.
+    Forced,
+}
+
+impl AgcRestartCause {
+    /// Channel 77 bits latched when a restart with this cause happens.
+    pub fn channel77_bits(&self) -> u16 {
+        match self {
+            AgcRestartCause::NightWatchman => io::CHAN77_NIGHT_WATCHMAN,
+            AgcRestartCause::TcTrap => io::CHAN77_TC_TRAP,
+            AgcRestartCause::RuptLock => io::CHAN77_RUPT_LOCK,
+            AgcRestartCause::Parity => io::CHAN77_PARITY_FAIL,
+            AgcRestartCause::Forced => 0,
+        }
+    }
+}
+
+/// A restart, along with the cycle count and Z when it was triggered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcRestart {
+    pub cause: AgcRestartCause,
+    pub total_cycles: usize,
+    pub z: u16,
+}
+
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum AgcOverflow {
@@ -61,6 +120,15 @@ pub struct AgcCpu<'a> {
     non_tc_count: u32,
 
     ruptlock_count: i32,
+
+    last_restart: Option<AgcRestart>,
+    restart_count: u32,
+
+    faults: AgcFaults,
+    inst_cache: Option<&'a mut AgcInstCache>,
+
+    recording: bool,
+    accesses: heapless::Vec<AgcMemAccess, MAX_STEP_ACCESSES>,
 }
 
 impl<'a> AgcUnprogInstr for AgcCpu<'a> {
@@ -89,7 +157,7 @@ impl<'a> AgcUnprogInstr for AgcCpu<'a> {
 }
 
 impl<'a> AgcCpu<'a> {
-    fn calculate_instr_data(&self) -> u16 {
+    pub(crate) fn calculate_instr_data(&self) -> u16 {
         let mut inst_data = s15_add(self.ir, self.idx_val);
         if self.ec_flag {
             inst_data = inst_data | 0x8000;
@@ -118,6 +186,15 @@ impl<'a> AgcCpu<'a> {
             tc_count: 0,
             non_tc_count: 0,
             ruptlock_count: 0,
+
+            last_restart: None,
+            restart_count: 0,
+
+            faults: AgcFaults::new(),
+            inst_cache: None,
+
+            recording: false,
+            accesses: heapless::Vec::new(),
         };
 
         cpu.reset();
@@ -133,7 +210,7 @@ impl<'a> AgcCpu<'a> {
         self.update_pc(0x800);
         self.gint = false;
         let io_val = self.read_io(0o163);
-        self.write_io(0o163, 0o200 | io_val);
+        self.write_io(0o163, io::CHAN163_RESTART | io_val);
     }
 
     pub fn update_pc(&mut self, val: u16) {
@@ -165,7 +242,18 @@ impl<'a> AgcCpu<'a> {
         if idx == 0o067 {
             self.nightwatch += 1;
         }
-        self.mem.read(idx)
+        let val = self.mem.read(idx);
+        if self.recording {
+            let addr = self.mem.resolve(idx);
+            let _ = self.accesses.push(AgcMemAccess::Read(addr, val));
+        }
+        if self.faults.has_address_triggers() {
+            let addr = self.mem.resolve(idx);
+            if let Some(kind) = self.faults.take_address(addr) {
+                self.apply_fault(kind);
+            }
+        }
+        val
     }
     pub fn read_s16(&mut self, idx: usize) -> u16 {
         match idx {
@@ -209,6 +297,15 @@ impl<'a> AgcCpu<'a> {
         if idx == 0o067 {
             self.nightwatch += 1;
         }
+        if idx >= 0o2000 {
+            if let Some(x) = &mut self.inst_cache {
+                x.invalidate(self.mem.resolve(idx));
+            }
+        }
+        if self.recording {
+            let addr = self.mem.resolve(idx);
+            let _ = self.accesses.push(AgcMemAccess::Write(addr, val));
+        }
         self.mem.write(idx, val)
     }
 
@@ -246,13 +343,237 @@ impl<'a> AgcCpu<'a> {
     }
 
     pub fn read_io(&mut self, idx: usize) -> u16 {
-        self.mem.read_io(idx)
+        let val = self.mem.read_io(idx);
+        if self.recording {
+            let _ = self.accesses.push(AgcMemAccess::IoRead(idx, val));
+        }
+        val
     }
 
     pub fn write_io(&mut self, idx: usize, val: u16) {
+        if self.recording {
+            let _ = self.accesses.push(AgcMemAccess::IoWrite(idx, val));
+        }
         self.mem.write_io(idx, val);
     }
 
+    /// Returns the current value of the central and bank registers without
+    /// going through the CPU's access bookkeeping.
+    pub fn registers(&self) -> AgcRegisters {
+        AgcRegisters {
+            z: self.mem.read(REG_Z),
+            a: self.mem.read(REG_A),
+            l: self.mem.read(REG_L),
+            q: self.mem.read(REG_Q),
+            eb: self.mem.read(REG_EB),
+            fb: self.mem.read(REG_FB),
+            bb: self.mem.read(REG_BB),
+        }
+    }
+
+    /// Resolves a 12-bit CPU address through the current bank registers.
+    pub fn resolve(&self, addr: u16) -> AgcAddr {
+        self.mem.resolve(addr as usize)
+    }
+
+    /// Bank context currently selected by EB, FB and the superbank bit.
+    pub fn bank_context(&self) -> AgcBankContext {
+        self.mem.bank_context()
+    }
+
+    /// Reads a word by its physical location without recording the access.
+    pub fn peek(&self, addr: AgcAddr) -> u16 {
+        self.mem.read_physical(addr)
+    }
+
+    /// Writes a word by its physical location without recording the access.
+    pub fn poke(&mut self, addr: AgcAddr, val: u16) {
+        if let Some(x) = &mut self.inst_cache {
+            x.invalidate(addr);
+        }
+        self.mem.write_physical(addr, val)
+    }
+
+    /// Reads an IO channel without recording the access.
+    pub fn peek_io(&mut self, idx: usize) -> u16 {
+        self.mem.read_io(idx)
+    }
+
+    /// Returns true when the next step will run an unprogrammed sequence
+    /// rather than an instruction.
+    pub fn unprog_pending(&self) -> bool {
+        !self.unprog.is_empty()
+    }
+
+    /// Decodes the instruction the CPU will execute next, taking a pending
+    /// INDEX and EXTEND into account.
+    pub fn next_instruction(&self) -> AgcInst {
+        let addr = self.mem.read(REG_PC) & 0o7777;
+        decode(addr, self.calculate_instr_data())
+    }
+
+    /// Returns the most recent hardware restart, if any happened since the
+    /// CPU was created.
+    pub fn last_restart(&self) -> Option<AgcRestart> {
+        self.last_restart
+    }
+
+    /// Latches alarm bits (`consts::io::CHAN77_*`) into the channel 77
+    /// restart monitor, the way the hardware alarm circuits do. This does
+    /// not restart the CPU by itself.
+    pub fn raise_alarm(&mut self, bits: u16) {
+        self.mem.raise_alarm(bits);
+    }
+
+    /// Names locations in log messages after `symbols`.
+    pub fn set_symbols(&mut self, symbols: &'a dyn AgcSymbols) {
+        self.mem.set_symbols(symbols);
+    }
+
+    pub fn symbols(&self) -> Option<&'a dyn AgcSymbols> {
+        self.mem.symbols()
+    }
+
+    /// Lends the CPU a cache of decoded fixed memory instructions. The
+    /// cache should be empty or have been used with the same rope.
+    pub fn set_inst_cache(&mut self, cache: &'a mut AgcInstCache) {
+        self.inst_cache = Some(cache);
+    }
+
+    /// Arms a fault that fires once its trigger is met. Faults are test
+    /// harness configuration and are not part of machine snapshots.
+    pub fn inject_fault(&mut self, fault: AgcFault) -> Result<(), AgcFaultError> {
+        self.faults.add(fault)
+    }
+
+    /// Disarms every pending fault and stops the active ones, including
+    /// stuck channel bits.
+    pub fn clear_faults(&mut self) {
+        self.faults.clear();
+        self.mem.clear_stuck_bits();
+    }
+
+    fn apply_fault(&mut self, kind: AgcFaultKind) {
+        debug!("Injecting fault: {:?}", kind);
+        match kind {
+            AgcFaultKind::ErasableParity => {
+                self.mem.raise_alarm(io::CHAN77_ERASABLE_PARITY_FAIL);
+                self.request_restart(AgcRestartCause::Parity);
+            }
+            AgcFaultKind::FixedParity => self.request_restart(AgcRestartCause::Parity),
+            AgcFaultKind::StuckBits {
+                channel,
+                mask,
+                value,
+            } => {
+                if !self.mem.set_stuck_bits(channel, mask, value) {
+                    error!("Unable to stick bits of channel {:o}", channel);
+                }
+            }
+            AgcFaultKind::DropCounter { counter, count } => {
+                if count > 0 && self.faults.add_drop(counter, count).is_err() {
+                    error!("Unable to drop increments of counter {:o}", counter);
+                }
+            }
+            AgcFaultKind::SpuriousRupt(x) => self.request_rupt(x),
+            AgcFaultKind::ForcedGoj => self.request_restart(AgcRestartCause::Forced),
+        }
+    }
+
+    /// Requests the interrupt with the given `RUPT_*` number. It is taken
+    /// once interrupts are enabled and no higher priority one is pending.
+    pub fn request_rupt(&mut self, rupt: u8) {
+        if rupt <= RUPT_HANDRUPT {
+            self.rupt |= 1 << rupt;
+        }
+    }
+
+    /// Number of hardware restarts since the CPU was created.
+    pub fn restart_count(&self) -> u32 {
+        self.restart_count
+    }
+
+    /// Copies erasable memory and the IO channels into a yaAGC style core
+    /// dump.
+    pub fn save_core(&self) -> AgcCoreDump {
+        let mut dump = AgcCoreDump::new();
+        self.mem.save_core(&mut dump);
+        dump
+    }
+
+    /// Restores erasable memory and the output channels from a core dump.
+    /// Meant to be used right after a reset, like powering up a machine
+    /// whose core kept its contents.
+    pub fn load_core(&mut self, dump: &AgcCoreDump) {
+        self.mem.load_core(dump);
+    }
+
+    /// Captures the whole machine: CPU, memory map and peripherals. Only
+    /// valid between steps.
+    pub fn save_state(&self) -> Result<MachineState, AgcStateError> {
+        let mut state = MachineState::new();
+        let mut w = state.writer();
+
+        w.u16(self.ir)?;
+        w.u16(self.idx_val)?;
+        w.bool(self.ec_flag)?;
+        w.u64(self.total_cycles as u64)?;
+        w.u64(self.mct_counter.to_bits())?;
+        w.u8(self.timer_counter)?;
+        w.bool(self.gint)?;
+        w.bool(self.is_irupt)?;
+        w.u16(self.rupt)?;
+        w.u8(self.unprog.len() as u8)?;
+        for seq in self.unprog.iter() {
+            w.unprog_seq(seq)?;
+        }
+        w.u16(self.nightwatch)?;
+        w.u32(self.nightwatch_cycles)?;
+        w.u32(self.tc_count)?;
+        w.u32(self.non_tc_count)?;
+        w.u32(self.ruptlock_count as u32)?;
+        w.restart(&self.last_restart)?;
+        w.u32(self.restart_count)?;
+        self.mem.save_state(&mut w)?;
+
+        let len = w.written();
+        state.finish(len);
+        Ok(state)
+    }
+
+    /// Restores a snapshot taken by `save_state`. The CPU must have been
+    /// created with the same rope and peripherals. If the snapshot turns out
+    /// to be corrupt half way through, the machine is left partially
+    /// restored and should be reset.
+    pub fn restore_state(&mut self, state: &MachineState) -> Result<(), AgcStateError> {
+        let mut r = state.reader();
+
+        self.ir = r.u16()?;
+        self.idx_val = r.u16()?;
+        self.ec_flag = r.bool()?;
+        self.total_cycles = r.u64()? as usize;
+        self.mct_counter = f64::from_bits(r.u64()?);
+        self.timer_counter = r.u8()?;
+        self.gint = r.bool()?;
+        self.is_irupt = r.bool()?;
+        self.rupt = r.u16()?;
+        self.unprog.clear();
+        for _ in 0..r.u8()? {
+            let seq = r.unprog_seq()?;
+            if self.unprog.push_back(seq).is_err() {
+                return Err(AgcStateError::InvalidData);
+            }
+        }
+        self.nightwatch = r.u16()?;
+        self.nightwatch_cycles = r.u32()?;
+        self.tc_count = r.u32()?;
+        self.non_tc_count = r.u32()?;
+        self.ruptlock_count = r.u32()? as i32;
+        self.last_restart = r.restart()?;
+        self.restart_count = r.u32()?;
+        self.mem.restore_state(&mut r)
+    }
+
     fn is_overflow(&mut self) -> bool {
         let a = self.read(REG_A);
         match a & 0xC000 {
@@ -277,9 +598,11 @@ impl<'a> AgcCpu<'a> {
         return false;
     }
 
+    /// Vectors to the highest priority pending interrupt. Priority follows
+    /// the `RUPT_*` numbering, and interrupt n lands at 04000 + 4n.
     fn handle_rupt(&mut self) {
         debug!("Interrupt Mask: {:x}", self.rupt);
-        for i in 0..10 {
+        for i in 0..=RUPT_HANDRUPT as u16 {
             let mask = 1 << i;
             if self.rupt & mask != 0 {
                 // Set the interrupt flag to pending
@@ -301,7 +624,7 @@ impl<'a> AgcCpu<'a> {
         }
     }
 
-    pub fn execute(&mut self, inst: &AgcInst) -> u16 {
+    pub fn execute(&mut self, inst: &AgcInst) -> Result<u16, AgcCpuError> {
         match inst.mnem {
             AgcMnem::TC | AgcMnem::TCF => {
                 self.non_tc_count = 0;
@@ -328,6 +651,7 @@ impl<'a> AgcCpu<'a> {
             AgcMnem::DIM => self.dim(&inst),
             AgcMnem::DXCH => self.dxch(&inst),
             AgcMnem::DV => self.dv(&inst),
+            AgcMnem::EDRUPT => self.edrupt(inst),
             AgcMnem::EXTEND => {
                 self.ec_flag = true;
                 self.idx_val = 0x0;
@@ -364,14 +688,21 @@ impl<'a> AgcCpu<'a> {
             AgcMnem::WOR => self.wor(&inst),
             AgcMnem::WRITE => self.write_instr(&inst),
             AgcMnem::XCH => self.xch(&inst),
-            _ => {
-                warn!("Unimplemented Execution of Instruction: {:?}", inst.mnem);
+            AgcMnem::INVALID => {
+                warn!(
+                    "Invalid Instruction: {:05o} @ {}",
+                    inst.inst_data,
+                    self.mem.symbolic(inst.pc as usize)
+                );
                 self.ec_flag = false;
                 self.idx_val = 0x0;
-                0
+                return Err(AgcCpuError::InvalidInstruction {
+                    pc: inst.pc,
+                    inst_data: inst.inst_data,
+                });
             }
         };
-        cycles
+        Ok(cycles)
     }
 
     fn handle_ruptlock(&mut self, cycles: u16) {
@@ -384,7 +715,7 @@ impl<'a> AgcCpu<'a> {
                 self.ruptlock_count += cycles as i32;
                 if self.ruptlock_count > RUPT_LOCK_COUNT {
                     debug!("RUPTLOCK Restart. Sending GOJ");
-                    self.set_unprog_seq(AgcUnprogSeq::GOJ);
+                    self.request_restart(AgcRestartCause::RuptLock);
                 }
             }
             false => {
@@ -395,7 +726,7 @@ impl<'a> AgcCpu<'a> {
                 self.ruptlock_count -= cycles as i32;
                 if self.ruptlock_count < -RUPT_LOCK_COUNT {
                     debug!("RUPTLOCK Restart. Sending GOJ");
-                    self.set_unprog_seq(AgcUnprogSeq::GOJ);
+                    self.request_restart(AgcRestartCause::RuptLock);
                 }
             }
         }
@@ -408,7 +739,7 @@ impl<'a> AgcCpu<'a> {
             self.nightwatch_cycles = 0;
             if self.nightwatch == 0 {
                 debug!("NIGHT WATCHMAN Restart. Sending GOJ");
-                self.set_unprog_seq(AgcUnprogSeq::GOJ);
+                self.request_restart(AgcRestartCause::NightWatchman);
             }
 
             self.nightwatch = 0;
@@ -419,20 +750,40 @@ impl<'a> AgcCpu<'a> {
         if self.tc_count >= TCMONITOR_COUNT {
             self.tc_count = 0;
 
-            self.set_unprog_seq(AgcUnprogSeq::GOJ);
+            debug!("TC TRAP Restart. Sending GOJ");
+            self.request_restart(AgcRestartCause::TcTrap);
         } else if self.non_tc_count >= TCMONITOR_COUNT {
             self.non_tc_count = 0;
 
-            self.set_unprog_seq(AgcUnprogSeq::GOJ);
+            debug!("TC TRAP Restart. Sending GOJ");
+            self.request_restart(AgcRestartCause::TcTrap);
         }
     }
 
+    /// Records the cause of a hardware restart, latches it into channel 77
+    /// and queues the GOJ sequence.
+    fn request_restart(&mut self, cause: AgcRestartCause) {
+        self.last_restart = Some(AgcRestart {
+            cause,
+            total_cycles: self.total_cycles,
+            z: self.mem.read(REG_PC),
+        });
+        self.restart_count += 1;
+        self.mem.raise_alarm(cause.channel77_bits());
+        self.set_unprog_seq(AgcUnprogSeq::GOJ);
+    }
+
     fn update_cycles(&mut self, cycles: u16) {
         self.mct_counter += cycles as f64 * 12.0;
 
         self.total_cycles += cycles as usize;
+        self.mem.tick(self.total_cycles);
         debug!("TotalCyles: {:?}", self.total_cycles * 12);
 
+        while let Some(kind) = self.faults.take_due(self.total_cycles) {
+            self.apply_fault(kind);
+        }
+
         self.handle_nightwatch(cycles);
         self.handle_tc_trap();
         self.handle_ruptlock(cycles);
@@ -441,7 +792,65 @@ impl<'a> AgcCpu<'a> {
         self.rupt |= timers.pump_mcts(cycles, &mut self.unprog);
     }
 
-    fn step_unprogrammed(&mut self) -> u16 {
+    fn handle_counter(&mut self, seq: &AgcUnprogSeq) {
+        if let AgcUnprogSeq::PINC(addr)
+        | AgcUnprogSeq::MINC(addr)
+        | AgcUnprogSeq::PCDU(addr)
+        | AgcUnprogSeq::MCDU(addr)
+        | AgcUnprogSeq::SHINC(addr)
+        | AgcUnprogSeq::SHANC(addr)
+        | AgcUnprogSeq::DINC(addr) = *seq
+        {
+            if self.faults.drop_increment(addr) {
+                debug!("Dropping counter increment {:?}", seq);
+                self.mem.raise_alarm(io::CHAN77_COUNTER_FAIL);
+                return;
+            }
+        }
+
+        let (addr, (val, overflow)) = match *seq {
+            AgcUnprogSeq::PINC(addr) => (addr, counters::pinc(self.read(addr))),
+            AgcUnprogSeq::MINC(addr) => (addr, counters::minc(self.read(addr))),
+            AgcUnprogSeq::PCDU(addr) => (addr, counters::pcdu(self.read(addr))),
+            AgcUnprogSeq::MCDU(addr) => (addr, counters::mcdu(self.read(addr))),
+            AgcUnprogSeq::SHINC(addr) => (addr, counters::shinc(self.read(addr))),
+            AgcUnprogSeq::SHANC(addr) => (addr, counters::shanc(self.read(addr))),
+            AgcUnprogSeq::DINC(addr) => {
+                let (val, pulse) = counters::dinc(self.read(addr));
+                self.write(addr, val);
+                if pulse == AgcDincPulse::ZOUT && addr == timer::MM_TIME6 {
+                    self.mem.fetch_timers().set_time6_enable(false);
+                    self.rupt |= 1 << RUPT_TIME6;
+                }
+                return;
+            }
+            _ => {
+                return;
+            }
+        };
+
+        trace!("Counter {:o}: {:o} ({:?})", addr, val, overflow);
+        self.write(addr, val);
+        match overflow {
+            AgcOverflow::None => {}
+            _ => self.handle_counter_overflow(addr),
+        }
+    }
+
+    fn handle_counter_overflow(&mut self, addr: usize) {
+        match addr {
+            timer::MM_TIME1 => self.set_unprog_seq(AgcUnprogSeq::PINC(timer::MM_TIME2)),
+            timer::MM_TIME3 => self.rupt |= 1 << RUPT_TIME3,
+            timer::MM_TIME4 => self.rupt |= 1 << RUPT_TIME4,
+            timer::MM_TIME5 => self.rupt |= 1 << RUPT_TIME5,
+            special::SG_INLINK => self.rupt |= 1 << RUPT_UPRUPT,
+            special::SG_RNRAD => self.rupt |= 1 << RUPT_RADAR,
+            _ => {}
+        }
+    }
+
+    fn step_unprogrammed(&mut self) -> (AgcUnprogSeq, u16) {
+        self.rupt |= self.mem.take_rupt_requests();
         let instr = self.unprog.pop_front().unwrap();
         let cycles = match instr {
             AgcUnprogSeq::GOJ => 2,
@@ -458,7 +867,16 @@ impl<'a> AgcCpu<'a> {
         match instr {
             AgcUnprogSeq::GOJ => {
                 self.handle_goj();
-                return cycles;
+                return (instr, cycles);
+            }
+            AgcUnprogSeq::PINC(_)
+            | AgcUnprogSeq::MINC(_)
+            | AgcUnprogSeq::PCDU(_)
+            | AgcUnprogSeq::MCDU(_)
+            | AgcUnprogSeq::DINC(_)
+            | AgcUnprogSeq::SHINC(_)
+            | AgcUnprogSeq::SHANC(_) => {
+                self.handle_counter(&instr);
             }
             _ => {}
         };
@@ -473,15 +891,15 @@ impl<'a> AgcCpu<'a> {
                 self.set_unprog_seq(AgcUnprogSeq::RUPT);
                 let inst_data = self.calculate_instr_data();
                 let addr: usize = (self.read(REG_PC) & 0xFFFF) as usize;
-                let i = decode(addr as u16, inst_data).unwrap();
-                debug!("{:x?}++++", i);
+                debug!("{:x?}++++", decode(addr as u16, inst_data));
             }
         }
 
-        cycles
+        (instr, cycles)
     }
 
-    fn step_programmed(&mut self) -> u16 {
+    fn step_programmed(&mut self) -> Result<(Option<AgcInst>, u16), AgcCpuError> {
+        self.rupt |= self.mem.take_rupt_requests();
         if !self.rupt_disabled() {
             if self.rupt_pending() == true {
                 debug!("Handling Interrupt: {:?} {:x}", self.gint, self.rupt);
@@ -492,17 +910,21 @@ impl<'a> AgcCpu<'a> {
                 let inst_data = self.calculate_instr_data();
 
                 let addr: usize = (self.read(REG_PC) & 0xFFFF) as usize;
-                let i = decode(addr as u16, inst_data).unwrap();
-                debug!("{:x?}++++", i);
+                debug!("{:x?}++++", decode(addr as u16, inst_data));
 
-                return 0;
+                return Ok((None, 0));
             }
         }
 
         let inst_data = self.calculate_instr_data();
 
         let addr: usize = (self.read(REG_PC) & 0xFFFF) as usize;
-        let i = decode(addr as u16, inst_data).unwrap();
+        let i = self.decode_cached(addr as u16, inst_data);
+        trace!(
+            "{}: {}",
+            self.mem.symbolic(addr),
+            AgcDisasm::new(&i, self.mem.bank_context()).with_symbols(self.mem.symbols())
+        );
 
         let next_pc = ((addr + 1) & 0xFFFF) as u16;
         self.update_pc(next_pc);
@@ -517,17 +939,83 @@ impl<'a> AgcCpu<'a> {
             }
         }
 
-        let cycles = self.execute(&i);
+        let cycles = self.execute(&i)?;
         self.update_cycles(cycles);
-        cycles
+        Ok((Some(i), cycles))
+    }
+
+    /// Decodes the instruction at `addr`, through the instruction cache
+    /// when there is one and the word was not modified by INDEX.
+    fn decode_cached(&mut self, addr: u16, inst_data: u16) -> AgcInst {
+        let cache = match &mut self.inst_cache {
+            Some(x) if self.idx_val == 0 && addr >= 0o2000 => x,
+            _ => return decode(addr, inst_data),
+        };
+
+        let phys = self.mem.resolve(addr as usize);
+        if let Some(mut i) = cache.get(phys, inst_data) {
+            i.pc = addr;
+            return i;
+        }
+
+        let i = decode(addr, inst_data);
+        cache.insert(phys, i);
+        i
     }
 
-    pub fn step(&mut self) -> u16 {
+    pub fn step(&mut self) -> Result<u16, AgcCpuError> {
         if self.unprog.len() > 0 {
-            self.step_unprogrammed()
+            Ok(self.step_unprogrammed().1)
         } else {
             // on instruction MCTs
-            self.step_programmed()
+            Ok(self.step_programmed()?.1)
         }
     }
+
+    /// Same as `step`, but reports the executed sequence to `observer`
+    /// together with the register state around it and the memory accesses
+    /// it performed. Steps that only take an interrupt are not reported;
+    /// the RUPT sequence they queue is reported by the following step.
+    pub fn step_observed(&mut self, observer: &mut dyn CpuObserver) -> Result<u16, AgcCpuError> {
+        let before = self.registers();
+        let banks = self.mem.bank_context();
+        self.accesses.clear();
+        self.recording = true;
+
+        let res = if !self.unprog.is_empty() {
+            let (seq, cycles) = self.step_unprogrammed();
+            self.recording = false;
+            let info = AgcStepInfo {
+                before,
+                banks,
+                after: self.registers(),
+                accesses: &self.accesses,
+                cycles,
+                total_cycles: self.total_cycles,
+            };
+            observer.unprogrammed(&seq, &info);
+            Ok(cycles)
+        } else {
+            match self.step_programmed() {
+                Ok((Some(inst), cycles)) => {
+                    self.recording = false;
+                    let info = AgcStepInfo {
+                        before,
+                        banks,
+                        after: self.registers(),
+                        accesses: &self.accesses,
+                        cycles,
+                        total_cycles: self.total_cycles,
+                    };
+                    observer.programmed(&inst, &info);
+                    Ok(cycles)
+                }
+                Ok((None, cycles)) => Ok(cycles),
+                Err(x) => Err(x),
+            }
+        };
+
+        self.recording = false;
+        res
+    }
 }
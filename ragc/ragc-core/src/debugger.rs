@@ -0,0 +1,273 @@
+use crate::cpu::{AgcCpu, AgcCpuError, AgcUnprogSeq};
+use crate::instructions::{AgcInst, AgcMnem};
+use crate::mem::AgcAddr;
+use crate::observer::{AgcMemAccess, AgcStepInfo, CpuObserver};
+
+pub const MAX_BREAKPOINTS: usize = 32;
+pub const MAX_WATCHPOINTS: usize = 32;
+
+/// Kind of access a watchpoint triggers on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcWatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+/// Location watched by a watchpoint. Memory watchpoints are only supported
+/// on erasable memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcWatchTarget {
+    Memory(AgcAddr),
+    Channel(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcWatchpoint {
+    pub target: AgcWatchTarget,
+    pub kind: AgcWatchKind,
+}
+
+impl AgcWatchpoint {
+    fn matches(&self, access: &AgcMemAccess) -> bool {
+        let (is_write, hit) = match (*access, self.target) {
+            (AgcMemAccess::Read(a, _), AgcWatchTarget::Memory(t)) => (false, a == t),
+            (AgcMemAccess::Write(a, _), AgcWatchTarget::Memory(t)) => (true, a == t),
+            (AgcMemAccess::IoRead(c, _), AgcWatchTarget::Channel(t)) => (false, c == t),
+            (AgcMemAccess::IoWrite(c, _), AgcWatchTarget::Channel(t)) => (true, c == t),
+            _ => return false,
+        };
+
+        hit && match self.kind {
+            AgcWatchKind::Read => !is_write,
+            AgcWatchKind::Write => is_write,
+            AgcWatchKind::Access => true,
+        }
+    }
+}
+
+/// Reason the debugger handed control back to the caller.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcStopReason {
+    Step,
+    Breakpoint(AgcAddr),
+    Watchpoint(AgcWatchpoint, AgcMemAccess),
+    CycleLimit,
+    Error(AgcCpuError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcDebugError {
+    TooManyBreakpoints,
+    TooManyWatchpoints,
+    FixedWatchpoint,
+}
+
+/// Breakpoint and watchpoint bookkeeping around an `AgcCpu`. Breakpoints
+/// are physical addresses, so a breakpoint in a switched fixed bank only
+/// hits when FB and the superbank bit select that bank.
+pub struct AgcDebugger {
+    breakpoints: heapless::Vec<AgcAddr, MAX_BREAKPOINTS>,
+    watchpoints: heapless::Vec<AgcWatchpoint, MAX_WATCHPOINTS>,
+    hit: Option<(AgcWatchpoint, AgcMemAccess)>,
+    executed: bool,
+}
+
+impl AgcDebugger {
+    pub fn new() -> AgcDebugger {
+        AgcDebugger {
+            breakpoints: heapless::Vec::new(),
+            watchpoints: heapless::Vec::new(),
+            hit: None,
+            executed: false,
+        }
+    }
+
+    pub fn breakpoints(&self) -> &[AgcAddr] {
+        &self.breakpoints
+    }
+
+    pub fn watchpoints(&self) -> &[AgcWatchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn add_breakpoint(&mut self, addr: AgcAddr) -> Result<(), AgcDebugError> {
+        if self.breakpoints.contains(&addr) {
+            return Ok(());
+        }
+        self.breakpoints
+            .push(addr)
+            .map_err(|_| AgcDebugError::TooManyBreakpoints)
+    }
+
+    /// Removes a breakpoint. Returns false if there was none at `addr`.
+    pub fn remove_breakpoint(&mut self, addr: AgcAddr) -> bool {
+        match self.breakpoints.iter().position(|x| *x == addr) {
+            Some(idx) => {
+                self.breakpoints.swap_remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn add_watchpoint(&mut self, wp: AgcWatchpoint) -> Result<(), AgcDebugError> {
+        if let AgcWatchTarget::Memory(AgcAddr::Fixed { .. }) = wp.target {
+            return Err(AgcDebugError::FixedWatchpoint);
+        }
+        if self.watchpoints.contains(&wp) {
+            return Ok(());
+        }
+        self.watchpoints
+            .push(wp)
+            .map_err(|_| AgcDebugError::TooManyWatchpoints)
+    }
+
+    /// Removes every watchpoint on `target`. Returns false if there was none.
+    pub fn remove_watchpoint(&mut self, target: AgcWatchTarget) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|x| x.target != target);
+        self.watchpoints.len() != len
+    }
+
+    fn breakpoint_at(&self, cpu: &AgcCpu) -> Option<AgcAddr> {
+        if cpu.unprog_pending() {
+            return None;
+        }
+        let addr = cpu.resolve(cpu.registers().z);
+        match self.breakpoints.contains(&addr) {
+            true => Some(addr),
+            false => None,
+        }
+    }
+
+    /// Executes a single instruction, along with any unprogrammed sequences
+    /// queued in front of it. Taking an interrupt counts as a step.
+    pub fn step(&mut self, cpu: &mut AgcCpu) -> AgcStopReason {
+        self.executed = false;
+        loop {
+            let rupt_only = match cpu.step_observed(self) {
+                Ok(0) => !cpu.unprog_pending() && !self.executed,
+                Ok(_) => false,
+                Err(x) => return AgcStopReason::Error(x),
+            };
+            if let Some((wp, access)) = self.hit.take() {
+                return AgcStopReason::Watchpoint(wp, access);
+            }
+            if self.executed || rupt_only {
+                return AgcStopReason::Step;
+            }
+        }
+    }
+
+    /// Returns the address a subroutine call about to be executed returns
+    /// to, or `None` if the next instruction is not a TC call.
+    pub fn return_address(&self, cpu: &AgcCpu) -> Option<AgcAddr> {
+        if cpu.unprog_pending() {
+            return None;
+        }
+        if !is_call(&cpu.next_instruction()) {
+            return None;
+        }
+        Some(cpu.resolve(cpu.registers().z.wrapping_add(1)))
+    }
+
+    /// Like `step`, but runs a subroutine called by TC until it returns to
+    /// the word after the call.
+    pub fn step_over(&mut self, cpu: &mut AgcCpu, cycle_limit: usize) -> AgcStopReason {
+        match self.return_address(cpu) {
+            Some(ret) => self.run_until(cpu, cycle_limit, ret),
+            None => self.step(cpu),
+        }
+    }
+
+    /// Runs until a breakpoint or watchpoint hits, or until the CPU has run
+    /// for `cycle_limit` total cycles.
+    pub fn run(&mut self, cpu: &mut AgcCpu, cycle_limit: usize) -> AgcStopReason {
+        self.run_to(cpu, cycle_limit, None)
+    }
+
+    /// Same as `run`, but also stops with `AgcStopReason::Step` once the CPU
+    /// is about to execute the instruction at `addr`.
+    pub fn run_until(
+        &mut self,
+        cpu: &mut AgcCpu,
+        cycle_limit: usize,
+        addr: AgcAddr,
+    ) -> AgcStopReason {
+        self.run_to(cpu, cycle_limit, Some(addr))
+    }
+
+    fn run_to(
+        &mut self,
+        cpu: &mut AgcCpu,
+        cycle_limit: usize,
+        target: Option<AgcAddr>,
+    ) -> AgcStopReason {
+        // The first step always executes, so that resuming from a breakpoint
+        // does not stop on it again.
+        let mut first = true;
+        loop {
+            if !first {
+                if target.is_some()
+                    && !cpu.unprog_pending()
+                    && target == Some(cpu.resolve(cpu.registers().z))
+                {
+                    return AgcStopReason::Step;
+                }
+                if let Some(addr) = self.breakpoint_at(cpu) {
+                    return AgcStopReason::Breakpoint(addr);
+                }
+            }
+            if cpu.total_cycles >= cycle_limit {
+                return AgcStopReason::CycleLimit;
+            }
+            first = false;
+
+            if let Err(x) = cpu.step_observed(self) {
+                return AgcStopReason::Error(x);
+            }
+            if let Some((wp, access)) = self.hit.take() {
+                return AgcStopReason::Watchpoint(wp, access);
+            }
+        }
+    }
+
+    fn check_watchpoints(&mut self, info: &AgcStepInfo) {
+        if self.hit.is_some() {
+            return;
+        }
+        for access in info.accesses {
+            if let Some(wp) = self.watchpoints.iter().find(|wp| wp.matches(access)) {
+                self.hit = Some((*wp, *access));
+                return;
+            }
+        }
+    }
+}
+
+impl Default for AgcDebugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// TC to anything other than A, L or Q is a subroutine call returning to the
+/// following word.
+fn is_call(inst: &AgcInst) -> bool {
+    match inst.mnem {
+        AgcMnem::TC if !inst.is_extended() => inst.get_data_bits() > 2,
+        _ => false,
+    }
+}
+
+impl CpuObserver for AgcDebugger {
+    fn programmed(&mut self, _inst: &AgcInst, info: &AgcStepInfo) {
+        self.executed = true;
+        self.check_watchpoints(info);
+    }
+
+    fn unprogrammed(&mut self, _seq: &AgcUnprogSeq, info: &AgcStepInfo) {
+        self.check_watchpoints(info);
+    }
+}
@@ -1,7 +1,6 @@
 use crate::instructions::{AgcInst, AgcMnem};
-use log::error;
 
-fn decode_extended(mut i: AgcInst) -> Result<AgcInst, &'static str> {
+fn decode_extended(mut i: AgcInst) -> AgcInst {
     let opbits = i.get_opcode_bits();
     match opbits {
         0 => {
@@ -30,19 +29,11 @@ fn decode_extended(mut i: AgcInst) -> Result<AgcInst, &'static str> {
                 Some(6) => {
                     i.mnem = AgcMnem::RXOR;
                 }
-                Some(7) => {
-                    i.mnem = AgcMnem::EDRUPT;
-                }
                 _ => {
-                    error!(
-                        "Invalid Extrabits Encoding for {}: {:?}",
-                        opbits, i.extrabits
-                    );
-                    i.extrabits = None;
-                    return Err("Invalid Extrabits Encoding");
+                    i.mnem = AgcMnem::EDRUPT;
                 }
             }
-            return Ok(i);
+            return i;
         }
         1 => {
             let exb: u8 = ((i.inst_data & 0x0C00) >> 10) as u8;
@@ -69,19 +60,11 @@ fn decode_extended(mut i: AgcInst) -> Result<AgcInst, &'static str> {
                 Some(2) => {
                     i.mnem = AgcMnem::AUG;
                 }
-                Some(3) => {
-                    i.mnem = AgcMnem::DIM;
-                }
                 _ => {
-                    error!(
-                        "Invalid Extrabits Encoding for {}: {:?}",
-                        opbits, i.extrabits
-                    );
-                    i.extrabits = None;
-                    return Err("Invalid Extrabits Encoding");
+                    i.mnem = AgcMnem::DIM;
                 }
             }
-            return Ok(i);
+            return i;
         }
         3 => {
             i.mnem = AgcMnem::DCA;
@@ -104,17 +87,14 @@ fn decode_extended(mut i: AgcInst) -> Result<AgcInst, &'static str> {
                 }
             }
         }
-        7 => {
-            i.mnem = AgcMnem::MP;
-        }
         _ => {
-            return Err("Invalid Opcode Size");
+            i.mnem = AgcMnem::MP;
         }
     }
-    Ok(i)
+    i
 }
 
-fn decode_simple(mut i: AgcInst) -> Result<AgcInst, &'static str> {
+fn decode_simple(mut i: AgcInst) -> AgcInst {
     let opbits = i.get_opcode_bits();
     match opbits {
         0 => {
@@ -138,16 +118,8 @@ fn decode_simple(mut i: AgcInst) -> Result<AgcInst, &'static str> {
                 Some(2) => {
                     i.mnem = AgcMnem::TCF;
                 }
-                Some(3) => {
-                    i.mnem = AgcMnem::TCF;
-                }
                 _ => {
-                    error!(
-                        "Invalid Extrabits Encoding for {}: {:?}",
-                        opbits, i.extrabits
-                    );
-                    i.extrabits = None;
-                    return Err("Invalid Extrabits Encoding");
+                    i.mnem = AgcMnem::TCF;
                 }
             }
         }
@@ -164,16 +136,8 @@ fn decode_simple(mut i: AgcInst) -> Result<AgcInst, &'static str> {
                 Some(2) => {
                     i.mnem = AgcMnem::INCR;
                 }
-                Some(3) => {
-                    i.mnem = AgcMnem::ADS;
-                }
                 _ => {
-                    error!(
-                        "Invalid Extrabits Encoding for {}: {:?}",
-                        opbits, i.extrabits
-                    );
-                    i.extrabits = None;
-                    return Err("Invalid Extrabits Encoding");
+                    i.mnem = AgcMnem::ADS;
                 }
             }
         }
@@ -203,16 +167,8 @@ fn decode_simple(mut i: AgcInst) -> Result<AgcInst, &'static str> {
                     i.mnem = AgcMnem::TS;
                     i.mct = 2;
                 }
-                Some(3) => {
-                    i.mnem = AgcMnem::XCH;
-                }
                 _ => {
-                    error!(
-                        "Invalid Extrabits Encoding for {}: {:?}",
-                        opbits, i.extrabits
-                    );
-                    i.extrabits = None;
-                    return Err("Invaid Extrabits Encoding");
+                    i.mnem = AgcMnem::XCH;
                 }
             }
         }
@@ -220,22 +176,18 @@ fn decode_simple(mut i: AgcInst) -> Result<AgcInst, &'static str> {
             i.mnem = AgcMnem::AD;
             i.mct = 2;
         }
-        7 => {
-            i.mnem = AgcMnem::MASK;
-        }
         _ => {
-            error!(
-                "Invalid value found. We didn't properly mask the opcode bits. {}",
-                opbits
-            );
-            return Err("Invalid Opcode Size");
+            i.mnem = AgcMnem::MASK;
         }
     }
 
-    Ok(i)
+    i
 }
 
-pub fn decode(pc: u16, inst_data: u16) -> Result<AgcInst, &'static str> {
+/// Decodes the word fetched from `pc`. Block II assigns an instruction to
+/// every opcode and extrabits pattern, so any word, corrupt or not, decodes
+/// to something and the CPU executes it as that.
+pub fn decode(pc: u16, inst_data: u16) -> AgcInst {
     let i = AgcInst {
         pc: pc,
         inst_data: inst_data,
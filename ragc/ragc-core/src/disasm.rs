@@ -0,0 +1,289 @@
+use core::fmt;
+
+use crate::consts;
+use crate::decoder::decode;
+use crate::instructions::{AgcInst, AgcMnem};
+use crate::mem::{rope_word, AgcAddr, AgcBankContext};
+use crate::symbols::{AgcSymbolic, AgcSymbols};
+
+const EXTEND_WORD: u16 = 0o00006;
+
+/// Kind of operand carried by an instruction's address field.
+enum AgcOperand {
+    None,
+    // 12-bit address anywhere in memory
+    Memory(u16),
+    // 10-bit erasable address
+    Erasable(u16),
+    // IO channel number
+    Channel(u16),
+}
+
+fn mnem_name(mnem: &AgcMnem) -> &'static str {
+    match mnem {
+        AgcMnem::AD => "AD",
+        AgcMnem::ADS => "ADS",
+        AgcMnem::AUG => "AUG",
+        AgcMnem::BZF => "BZF",
+        AgcMnem::BZMF => "BZMF",
+        AgcMnem::CA => "CA",
+        AgcMnem::CS => "CS",
+        AgcMnem::CCS => "CCS",
+        AgcMnem::DAS => "DAS",
+        AgcMnem::DCA => "DCA",
+        AgcMnem::DCS => "DCS",
+        AgcMnem::DIM => "DIM",
+        AgcMnem::DV => "DV",
+        AgcMnem::DXCH => "DXCH",
+        AgcMnem::EDRUPT => "EDRUPT",
+        AgcMnem::EXTEND => "EXTEND",
+        AgcMnem::INCR => "INCR",
+        AgcMnem::INDEX => "INDEX",
+        AgcMnem::INHINT => "INHINT",
+        AgcMnem::LXCH => "LXCH",
+        AgcMnem::MASK => "MASK",
+        AgcMnem::MP => "MP",
+        AgcMnem::MSU => "MSU",
+        AgcMnem::QXCH => "QXCH",
+        AgcMnem::RAND => "RAND",
+        AgcMnem::READ => "READ",
+        AgcMnem::RELINT => "RELINT",
+        AgcMnem::RESUME => "RESUME",
+        AgcMnem::ROR => "ROR",
+        AgcMnem::RXOR => "RXOR",
+        AgcMnem::SU => "SU",
+        AgcMnem::TC => "TC",
+        AgcMnem::TCF => "TCF",
+        AgcMnem::TS => "TS",
+        AgcMnem::WAND => "WAND",
+        AgcMnem::WOR => "WOR",
+        AgcMnem::WRITE => "WRITE",
+        AgcMnem::XCH => "XCH",
+        AgcMnem::INVALID => "INVALID",
+    }
+}
+
+/// yaYUL implied-address mnemonics, which assemble to a fixed instruction
+/// word. NOOP is handled separately since in fixed memory it is a TCF to
+/// the next location.
+fn implied_alias(inst: &AgcInst) -> Option<&'static str> {
+    let word = inst.inst_data & 0o77777;
+    if inst.is_extended() {
+        match word {
+            0o22007 => Some("ZQ"),
+            0o40001 => Some("DCOM"),
+            0o70000 => Some("SQUARE"),
+            _ => None,
+        }
+    } else {
+        match word {
+            0o00000 => Some("XXALQ"),
+            0o00001 => Some("XLQ"),
+            0o00002 => Some("RETURN"),
+            0o20001 => Some("DDOUBL"),
+            0o22007 => Some("ZL"),
+            0o30000 => Some("NOOP"),
+            0o40000 => Some("COM"),
+            0o52005 => Some("DTCF"),
+            0o52006 => Some("DTCB"),
+            0o54000 => Some("OVSK"),
+            0o54005 => Some("TCAA"),
+            0o60000 => Some("DOUBLE"),
+            _ => None,
+        }
+    }
+}
+
+fn operand(inst: &AgcInst) -> AgcOperand {
+    let k = inst.get_data_bits();
+    match inst.mnem {
+        AgcMnem::RELINT | AgcMnem::INHINT | AgcMnem::EXTEND | AgcMnem::RESUME => AgcOperand::None,
+        AgcMnem::INVALID => AgcOperand::None,
+
+        AgcMnem::READ
+        | AgcMnem::WRITE
+        | AgcMnem::RAND
+        | AgcMnem::WAND
+        | AgcMnem::ROR
+        | AgcMnem::WOR
+        | AgcMnem::RXOR => AgcOperand::Channel(k & 0o777),
+        // EDRUPT is extrabits 07 of the channel opcode, which are not part
+        // of K.
+        AgcMnem::EDRUPT => AgcOperand::Memory(k & 0o777),
+
+        // The double precision instructions are encoded with K+1.
+        AgcMnem::DCA | AgcMnem::DCS => AgcOperand::Memory(k.wrapping_sub(1) & 0o7777),
+        AgcMnem::DAS | AgcMnem::DXCH => AgcOperand::Erasable(k.wrapping_sub(1) & 0o1777),
+
+        AgcMnem::INDEX if !inst.is_extended() => AgcOperand::Erasable(k & 0o1777),
+        AgcMnem::CCS
+        | AgcMnem::LXCH
+        | AgcMnem::INCR
+        | AgcMnem::ADS
+        | AgcMnem::TS
+        | AgcMnem::XCH
+        | AgcMnem::DV
+        | AgcMnem::MSU
+        | AgcMnem::QXCH
+        | AgcMnem::AUG
+        | AgcMnem::DIM
+        | AgcMnem::SU => AgcOperand::Erasable(k & 0o1777),
+
+        _ => AgcOperand::Memory(k),
+    }
+}
+
+/// Formats a decoded instruction in yaYUL syntax, e.g. `CA     E3,1400` or
+/// `TC     24,2057`. Addresses inside the switched windows are qualified
+/// with the banks in `banks`.
+pub struct AgcDisasm<'i> {
+    inst: &'i AgcInst,
+    banks: AgcBankContext,
+    symbols: Option<&'i dyn AgcSymbols>,
+}
+
+impl<'i> AgcDisasm<'i> {
+    pub fn new(inst: &'i AgcInst, banks: AgcBankContext) -> AgcDisasm<'i> {
+        AgcDisasm {
+            inst,
+            banks,
+            symbols: None,
+        }
+    }
+
+    /// Shows operands by symbol where `symbols` knows one, e.g.
+    /// `TC     CHECKMM +3`.
+    pub fn with_symbols(mut self, symbols: Option<&'i dyn AgcSymbols>) -> AgcDisasm<'i> {
+        self.symbols = symbols;
+        self
+    }
+
+    fn fmt_address(&self, f: &mut fmt::Formatter, addr: u16) -> fmt::Result {
+        match self.banks.resolve(addr) {
+            Some(x) => write!(f, "{}", AgcSymbolic::new(x, self.symbols)),
+            None => write!(f, "{:04o}", addr),
+        }
+    }
+}
+
+impl<'i> fmt::Display for AgcDisasm<'i> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(alias) = implied_alias(self.inst) {
+            return write!(f, "{}", alias);
+        }
+
+        // In fixed memory yaYUL assembles NOOP as a TCF to the next word.
+        if let AgcMnem::TCF = self.inst.mnem {
+            if self.inst.get_data_bits() == (self.inst.pc + 1) & 0o7777 {
+                return write!(f, "NOOP");
+            }
+        }
+
+        let name = mnem_name(&self.inst.mnem);
+        match operand(self.inst) {
+            AgcOperand::None => write!(f, "{}", name),
+            AgcOperand::Channel(k) => write!(f, "{:<7}{:02o}", name, k),
+            AgcOperand::Memory(k) | AgcOperand::Erasable(k) => {
+                write!(f, "{:<7}", name)?;
+                self.fmt_address(f, k)
+            }
+        }
+    }
+}
+
+/// One word of a rope bank listing. With symbols, a label column holds the
+/// symbol defined at the word.
+pub struct AgcListingLine<'s> {
+    pub addr: AgcAddr,
+    pub word: u16,
+    pub inst: AgcInst,
+    symbols: Option<&'s dyn AgcSymbols>,
+}
+
+impl<'s> fmt::Display for AgcListingLine<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bank = match self.addr {
+            AgcAddr::Fixed { bank, .. } => bank,
+            _ => 0,
+        };
+        let banks = AgcBankContext { ebank: None, fbank: bank };
+        let disasm = AgcDisasm::new(&self.inst, banks).with_symbols(self.symbols);
+        match self.symbols {
+            Some(symbols) => {
+                let label = match symbols.symbol_at(self.addr) {
+                    Some((name, 0)) => name,
+                    _ => "",
+                };
+                write!(
+                    f,
+                    "{:<9}{:05o}    {:<10}{}",
+                    self.addr, self.word, label, disasm
+                )
+            }
+            None => write!(f, "{:<9}{:05o}    {}", self.addr, self.word, disasm),
+        }
+    }
+}
+
+/// Iterator disassembling one fixed bank of a rope. Words following an
+/// EXTEND are decoded as extracodes.
+pub struct AgcBankListing<'r> {
+    program: &'r [[u16; consts::ROM_BANK_NUM_WORDS]; consts::ROM_NUM_BANKS],
+    bank: usize,
+    offset: usize,
+    extended: bool,
+    symbols: Option<&'r dyn AgcSymbols>,
+}
+
+impl<'r> AgcBankListing<'r> {
+    pub fn new(
+        program: &'r [[u16; consts::ROM_BANK_NUM_WORDS]; consts::ROM_NUM_BANKS],
+        bank: usize,
+    ) -> AgcBankListing<'r> {
+        AgcBankListing {
+            program,
+            bank,
+            offset: 0,
+            extended: false,
+            symbols: None,
+        }
+    }
+
+    /// Labels the listing and its operands with `symbols`.
+    pub fn with_symbols(mut self, symbols: Option<&'r dyn AgcSymbols>) -> AgcBankListing<'r> {
+        self.symbols = symbols;
+        self
+    }
+}
+
+impl<'r> Iterator for AgcBankListing<'r> {
+    type Item = AgcListingLine<'r>;
+
+    fn next(&mut self) -> Option<AgcListingLine<'r>> {
+        if self.bank >= consts::ROM_NUM_BANKS || self.offset >= consts::ROM_BANK_NUM_WORDS {
+            return None;
+        }
+
+        let addr = AgcAddr::Fixed {
+            bank: self.bank as u8,
+            offset: self.offset as u16,
+        };
+        let word = rope_word(self.program, self.bank, self.offset);
+        let inst_data = if self.extended { word | 0x8000 } else { word };
+        let inst = decode(addr.cpu_addr(), inst_data);
+
+        // An extended INDEX keeps the extend state for the word it indexes.
+        self.extended = match (self.extended, inst.mnem) {
+            (true, AgcMnem::INDEX) => true,
+            (true, _) => false,
+            (false, _) => word == EXTEND_WORD,
+        };
+        self.offset += 1;
+        Some(AgcListingLine {
+            addr,
+            word,
+            inst,
+            symbols: self.symbols,
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use crate::consts::cpu::RUPT_HANDRUPT;
+use crate::mem::AgcAddr;
+
+/// Maximum number of faults that can be armed at the same time.
+pub const MAX_FAULTS: usize = 16;
+
+/// Condition that fires an injected fault.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcFaultTrigger {
+    /// Once the CPU has run at least this many MCTs.
+    Cycle(usize),
+    /// The first time the CPU reads the word, instruction fetches included.
+    Address(AgcAddr),
+}
+
+/// What happens when an injected fault fires.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcFaultKind {
+    /// Erasable memory parity failure, which restarts the CPU.
+    ErasableParity,
+    /// Fixed memory parity failure, which restarts the CPU.
+    FixedParity,
+    /// From then on, the bits of `mask` read from `channel` are stuck at
+    /// the matching bits of `value`.
+    StuckBits { channel: usize, mask: u16, value: u16 },
+    /// The next `count` increments of the counter register at `counter`
+    /// are lost, and the counter fail alarm is raised.
+    DropCounter { counter: usize, count: u16 },
+    /// Requests the interrupt with the given `RUPT_*` number.
+    SpuriousRupt(u8),
+    /// Restarts the CPU through GOJ without any alarm.
+    ForcedGoj,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcFault {
+    pub trigger: AgcFaultTrigger,
+    pub kind: AgcFaultKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcFaultError {
+    TooManyFaults,
+    InvalidChannel,
+    InvalidRupt,
+}
+
+/// Faults waiting for their trigger, and the counter drops that are
+/// currently active.
+pub(crate) struct AgcFaults {
+    pending: heapless::Vec<AgcFault, MAX_FAULTS>,
+    drops: heapless::Vec<(usize, u16), MAX_FAULTS>,
+    address_triggers: bool,
+}
+
+impl AgcFaults {
+    pub fn new() -> AgcFaults {
+        AgcFaults {
+            pending: heapless::Vec::new(),
+            drops: heapless::Vec::new(),
+            address_triggers: false,
+        }
+    }
+
+    pub fn add(&mut self, fault: AgcFault) -> Result<(), AgcFaultError> {
+        match fault.kind {
+            AgcFaultKind::StuckBits { channel, .. } if channel > 0o377 => {
+                return Err(AgcFaultError::InvalidChannel);
+            }
+            AgcFaultKind::SpuriousRupt(x) if x > RUPT_HANDRUPT => {
+                return Err(AgcFaultError::InvalidRupt);
+            }
+            _ => {}
+        }
+
+        self.pending
+            .push(fault)
+            .map_err(|_| AgcFaultError::TooManyFaults)?;
+        self.update_address_triggers();
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.drops.clear();
+        self.address_triggers = false;
+    }
+
+    /// True when some pending fault waits for a memory read, so reads
+    /// need to be checked at all.
+    pub fn has_address_triggers(&self) -> bool {
+        self.address_triggers
+    }
+
+    /// Removes and returns the first fault whose cycle trigger is due.
+    pub fn take_due(&mut self, total_cycles: usize) -> Option<AgcFaultKind> {
+        self.take(|x| match x {
+            AgcFaultTrigger::Cycle(cycle) => *cycle <= total_cycles,
+            _ => false,
+        })
+    }
+
+    /// Removes and returns the first fault armed on a read of `addr`.
+    pub fn take_address(&mut self, addr: AgcAddr) -> Option<AgcFaultKind> {
+        self.take(|x| *x == AgcFaultTrigger::Address(addr))
+    }
+
+    /// Starts dropping increments of a counter register.
+    pub fn add_drop(&mut self, counter: usize, count: u16) -> Result<(), AgcFaultError> {
+        self.drops
+            .push((counter, count))
+            .map_err(|_| AgcFaultError::TooManyFaults)
+    }
+
+    /// Returns true if the next increment of `counter` has to be dropped,
+    /// and accounts for it.
+    pub fn drop_increment(&mut self, counter: usize) -> bool {
+        let idx = match self.drops.iter().position(|x| x.0 == counter) {
+            Some(x) => x,
+            None => return false,
+        };
+
+        self.drops[idx].1 -= 1;
+        if self.drops[idx].1 == 0 {
+            self.drops.swap_remove(idx);
+        }
+        true
+    }
+
+    fn take<F: Fn(&AgcFaultTrigger) -> bool>(&mut self, due: F) -> Option<AgcFaultKind> {
+        let idx = self.pending.iter().position(|x| due(&x.trigger))?;
+        let fault = self.pending.remove(idx);
+        self.update_address_triggers();
+        Some(fault.kind)
+    }
+
+    fn update_address_triggers(&mut self) {
+        self.address_triggers = self
+            .pending
+            .iter()
+            .any(|x| matches!(x.trigger, AgcFaultTrigger::Address(_)));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::consts::{ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use crate::instructions::AgcInst;
+use crate::mem::AgcAddr;
+
+/// Decoded instructions of fixed memory, indexed by physical bank and
+/// offset. An entry only hits when the word being executed, including the
+/// extend bit, is the one it was decoded from, so words modified by INDEX
+/// never use the cache.
+///
+/// The cache is large, so it is allocated by the caller and lent to the CPU
+/// with `AgcCpu::set_inst_cache`.
+pub struct AgcInstCache {
+    banks: [[Option<AgcInst>; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS],
+}
+
+impl AgcInstCache {
+    pub fn new() -> AgcInstCache {
+        AgcInstCache {
+            banks: [[None; ROM_BANK_NUM_WORDS]; ROM_NUM_BANKS],
+        }
+    }
+
+    /// Returns the cached instruction for the word at `addr`, if it was
+    /// decoded from `inst_data`.
+    pub fn get(&self, addr: AgcAddr, inst_data: u16) -> Option<AgcInst> {
+        match self.entry(addr) {
+            Some(Some(x)) if x.inst_data == inst_data => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, addr: AgcAddr, inst: AgcInst) {
+        if let Some(x) = self.entry_mut(addr) {
+            *x = Some(inst);
+        }
+    }
+
+    /// Drops the entry for a fixed memory word that was written.
+    pub fn invalidate(&mut self, addr: AgcAddr) {
+        if let Some(x) = self.entry_mut(addr) {
+            *x = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for bank in self.banks.iter_mut() {
+            for x in bank.iter_mut() {
+                *x = None;
+            }
+        }
+    }
+
+    fn entry(&self, addr: AgcAddr) -> Option<&Option<AgcInst>> {
+        match addr {
+            AgcAddr::Fixed { bank, offset } => self
+                .banks
+                .get(bank as usize)
+                .and_then(|x| x.get(offset as usize)),
+            AgcAddr::Erasable { .. } => None,
+        }
+    }
+
+    fn entry_mut(&mut self, addr: AgcAddr) -> Option<&mut Option<AgcInst>> {
+        match addr {
+            AgcAddr::Fixed { bank, offset } => self
+                .banks
+                .get_mut(bank as usize)
+                .and_then(|x| x.get_mut(offset as usize)),
+            AgcAddr::Erasable { .. } => None,
+        }
+    }
+}
+
+impl Default for AgcInstCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
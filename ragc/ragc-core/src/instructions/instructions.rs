@@ -366,6 +366,19 @@ impl<'a> Instructions for AgcCpu<'a> {
             return 6;
         }
 
+        if zero_list.contains(&divisor) {
+            // DV is undefined unless the divisor is larger in magnitude than
+            // the dividend. Saturate with the sign rule, as for equal
+            // magnitudes above, rather than dividing by zero.
+            log::warn!("Undefined behavior for DV by zero!");
+            if dividend_sign ^ divisor_sign == 0o00000 {
+                self.write_s15(REG_A, 0o37777);
+            } else {
+                self.write_s15(REG_A, 0o40000);
+            }
+            return 6;
+        }
+
         let dividend = convert_to_dp(dividend_upper, dividend_lower);
         let cpu_dividend = crate::utils::agc_dp_to_cpu(dividend);
         let cpu_divisor = crate::utils::agc_sp_to_cpu(divisor);
@@ -373,7 +386,13 @@ impl<'a> Instructions for AgcCpu<'a> {
         let cpu_quotent = cpu_dividend / (cpu_divisor as i32);
         let cpu_remainder = cpu_dividend % (cpu_divisor as i32);
 
-        self.write_s16(REG_A, crate::utils::cpu_to_agc_sp(cpu_quotent as i16));
+        // The quotient follows the sign rule even when it is zero, as for a
+        // zero dividend above. cpu_to_agc_sp would turn every zero into -0.
+        let quotient = match cpu_quotent {
+            0 if dividend_sign ^ divisor_sign == 0o00000 => 0o00000,
+            _ => crate::utils::cpu_to_agc_sp(cpu_quotent as i16),
+        };
+        self.write_s16(REG_A, quotient);
         match cpu_remainder {
             0 => {
                 if dividend_sign == 0o40000 {
@@ -418,7 +437,7 @@ impl<'a> Instructions for AgcCpu<'a> {
     }
 
     fn dcs(&mut self, inst: &AgcInst) -> u16 {
-        let k = inst.get_kaddr() - 1;
+        let k = inst.get_kaddr().saturating_sub(1);
 
         let val_l = (!self.read_s16(k + 1)) & 0xFFFF;
         self.write(REG_L, val_l);
@@ -433,7 +452,7 @@ impl<'a> Instructions for AgcCpu<'a> {
     }
 
     fn dca(&mut self, inst: &AgcInst) -> u16 {
-        let k = inst.get_kaddr() - 1;
+        let k = inst.get_kaddr().saturating_sub(1);
 
         let val_l = self.read_s16(k + 1);
         self.write_s16(REG_L, val_l);
@@ -448,7 +467,7 @@ impl<'a> Instructions for AgcCpu<'a> {
     }
 
     fn dxch(&mut self, inst: &AgcInst) -> u16 {
-        let kaddr = inst.get_kaddr_ram() - 1;
+        let kaddr = inst.get_kaddr_ram().saturating_sub(1);
 
         let l = self.read_s16(REG_L);
         let k2 = self.read_s16(kaddr + 1);
@@ -662,12 +681,22 @@ impl<'a> Instructions for AgcCpu<'a> {
     }
 
     fn edrupt(&mut self, _inst: &AgcInst) -> u16 {
+        // EDRUPT runs the RUPT sequence without a priority bit set, so the
+        // return state lands in ZRUPT/BRUPT and control goes to location 0.
         self.gint = false;
+        self.is_irupt = true;
+
+        let val = self.read(REG_PC) + 1;
+        self.write(REG_PC_SHADOW, val);
+        self.write(REG_IR, self.calculate_instr_data());
+        self.idx_val = 0;
+
+        self.update_pc(0o0000);
         3
     }
 
     fn resume(&mut self, _inst: &AgcInst) -> u16 {
-        let val = self.read(REG_PC_SHADOW) - 1;
+        let val = self.read(REG_PC_SHADOW).wrapping_sub(1) & 0o7777;
         self.write(REG_PC, val);
         self.ir = self.read(REG_IR);
         self.idx_val = 0;
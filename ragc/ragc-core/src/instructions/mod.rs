@@ -8,7 +8,7 @@ const OPCODE_MASK: u16 = 0o7;
 const OPCODE_OFFSET: u16 = 12;
 const OPCODE_EXTEND_MASK: u16 = 0o100000;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AgcMnem {
     AD,
     ADS,
@@ -51,7 +51,7 @@ pub enum AgcMnem {
     INVALID,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct AgcInst {
     pub pc: u16,
     pub mnem: AgcMnem,
@@ -1,8 +1,19 @@
 #![no_std]
 
 pub mod consts;
+pub mod core_dump;
+pub mod counters;
 pub mod cpu;
+pub mod debugger;
 pub mod decoder;
+pub mod disasm;
+pub mod fault;
+pub mod inst_cache;
 pub mod instructions;
 pub mod mem;
+pub mod observer;
+pub mod pacer;
+pub mod padload;
+pub mod state;
+pub mod symbols;
 pub mod utils;
@@ -0,0 +1,161 @@
+use core::fmt;
+use core::fmt::Write;
+use core::str::FromStr;
+
+use crate::consts;
+
+/// Physical location of a memory word, independent of the bank registers
+/// that were used to reach it.
+///
+/// Erasable words are identified by their E-bank and offset inside the bank
+/// (0-377), fixed words by their F-bank and offset inside the bank
+/// (0-1777). Superbank selection is already applied to the fixed bank
+/// number, so fixed banks range over 00-43.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AgcAddr {
+    Erasable { bank: u8, offset: u16 },
+    Fixed { bank: u8, offset: u16 },
+}
+
+/// Error returned when an address string cannot be parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcAddrParseError;
+
+/// Bank register state used to resolve the switched memory windows. The
+/// E-bank is optional so that listings of a rope, where it is not known, can
+/// still be resolved for fixed memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcBankContext {
+    pub ebank: Option<u8>,
+    pub fbank: u8,
+}
+
+impl AgcBankContext {
+    /// Resolves a 12-bit CPU address. Returns `None` for the switched
+    /// erasable window when the E-bank is unknown.
+    pub fn resolve(&self, addr: u16) -> Option<AgcAddr> {
+        match (addr & 0o7777, self.ebank) {
+            (0o1400..=0o1777, None) => None,
+            (_, ebank) => Some(AgcAddr::from_cpu(addr, ebank.unwrap_or(0), self.fbank)),
+        }
+    }
+}
+
+/// Returns the physical fixed bank seen through the 2000-3777 window for a
+/// given FB value and superbank bit.
+pub fn physical_fbank(fbank: usize, superbank: bool) -> u8 {
+    match fbank {
+        0o30..=0o37 if superbank => (fbank + 0o10) as u8,
+        _ => fbank as u8,
+    }
+}
+
+impl AgcAddr {
+    /// Resolves a 12-bit CPU address using the given E-bank and physical
+    /// F-bank.
+    pub fn from_cpu(addr: u16, ebank: u8, fbank: u8) -> AgcAddr {
+        let addr = addr & 0o7777;
+        match addr {
+            0o0000..=0o1377 => AgcAddr::Erasable {
+                bank: (addr >> 8) as u8,
+                offset: addr & 0o377,
+            },
+            0o1400..=0o1777 => AgcAddr::Erasable {
+                bank: ebank,
+                offset: addr & 0o377,
+            },
+            0o2000..=0o3777 => AgcAddr::Fixed {
+                bank: fbank,
+                offset: addr & 0o1777,
+            },
+            _ => AgcAddr::Fixed {
+                bank: (addr >> 10) as u8,
+                offset: addr & 0o1777,
+            },
+        }
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        matches!(self, AgcAddr::Fixed { .. })
+    }
+
+    /// CPU address under which this word is visible once the bank
+    /// registers select its bank.
+    pub fn cpu_addr(&self) -> u16 {
+        match *self {
+            AgcAddr::Erasable {
+                bank: bank @ 0..=2,
+                offset,
+            } => ((bank as u16) << 8) | (offset & 0o377),
+            AgcAddr::Erasable { offset, .. } => 0o1400 | (offset & 0o377),
+            AgcAddr::Fixed {
+                bank: bank @ 2..=3,
+                offset,
+            } => ((bank as u16) << 10) | (offset & 0o1777),
+            AgcAddr::Fixed { offset, .. } => 0o2000 | (offset & 0o1777),
+        }
+    }
+}
+
+impl fmt::Display for AgcAddr {
+    /// Formats the address the way yaYUL listings do: `E3,1400` and
+    /// `24,2000` for switched memory, a plain octal address for unswitched
+    /// erasable and fixed-fixed memory.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s: heapless::String<8> = heapless::String::new();
+        match *self {
+            AgcAddr::Erasable { bank: 0..=2, .. } | AgcAddr::Fixed { bank: 2..=3, .. } => {
+                write!(s, "{:04o}", self.cpu_addr())?
+            }
+            AgcAddr::Erasable { bank, .. } => write!(s, "E{:o},{:04o}", bank, self.cpu_addr())?,
+            AgcAddr::Fixed { bank, .. } => write!(s, "{:02o},{:04o}", bank, self.cpu_addr())?,
+        };
+        f.pad(&s)
+    }
+}
+
+fn parse_octal(s: &str) -> Result<u16, AgcAddrParseError> {
+    u16::from_str_radix(s.trim(), 8).map_err(|_| AgcAddrParseError)
+}
+
+impl FromStr for AgcAddr {
+    type Err = AgcAddrParseError;
+
+    /// Parses an address in the syntax used by `Display`: `E3,1400` for
+    /// switched erasable, `24,2000` for switched fixed and plain octal for
+    /// unswitched memory. Plain addresses inside a switched window are
+    /// rejected since they do not name a single word.
+    fn from_str(s: &str) -> Result<AgcAddr, AgcAddrParseError> {
+        match s.split_once(',') {
+            Some((bank, addr)) => {
+                let addr = parse_octal(addr)?;
+                let bank = bank.trim();
+                if let Some(ebank) = bank.strip_prefix('E').or_else(|| bank.strip_prefix('e')) {
+                    let ebank = parse_octal(ebank)?;
+                    if ebank as usize >= consts::RAM_NUM_BANKS || !(0o1400..=0o1777).contains(&addr)
+                    {
+                        return Err(AgcAddrParseError);
+                    }
+                    Ok(AgcAddr::Erasable {
+                        bank: ebank as u8,
+                        offset: addr & 0o377,
+                    })
+                } else {
+                    let fbank = parse_octal(bank)?;
+                    if fbank as usize >= consts::ROM_NUM_BANKS || !(0o2000..=0o3777).contains(&addr)
+                    {
+                        return Err(AgcAddrParseError);
+                    }
+                    Ok(AgcAddr::Fixed {
+                        bank: fbank as u8,
+                        offset: addr & 0o1777,
+                    })
+                }
+            }
+            None => match parse_octal(s)? {
+                addr @ (0o0000..=0o1377 | 0o4000..=0o7777) => Ok(AgcAddr::from_cpu(addr, 0, 0)),
+                _ => Err(AgcAddrParseError),
+            },
+        }
+    }
+}
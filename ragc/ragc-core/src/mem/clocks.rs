@@ -1,6 +1,7 @@
 use crate::consts;
 use crate::cpu::AgcUnprogSeq;
 use crate::mem::MemoryType;
+use crate::state::{AgcStateError, StateReader, StateWriter};
 
 use heapless::Deque;
 
@@ -17,7 +18,8 @@ pub struct Timers {
     downrupt_flags: u8,
 
     // Timer Values
-    timer1: u32,
+    timer1: u16,
+    timer2: u16,
     timer3: u16,
     timer4: u16,
     timer5: u16,
@@ -55,6 +57,7 @@ impl Timers {
 
             // Timer values
             timer1: 0,
+            timer2: 0,
             timer3: 0,
             timer4: 0,
             timer5: 0,
@@ -69,43 +72,27 @@ impl Timers {
         }
     }
 
-    fn increment_scaler(&mut self, unprog: &mut Deque<AgcUnprogSeq, 8>) -> u16 {
-        let mut interrupt_mask = 0;
-
+    fn increment_scaler(&mut self, unprog: &mut Deque<AgcUnprogSeq, 8>) {
         self.scaler += 1;
-        interrupt_mask |= match self.scaler & 0o37 {
+        match self.scaler & 0o37 {
             0 => {
-                push_unprog_seq(unprog, AgcUnprogSeq::PINC);
-                self.handle_timer5()
+                push_unprog_seq(unprog, AgcUnprogSeq::PINC(consts::timer::MM_TIME5));
             }
             8 => {
-                push_unprog_seq(unprog, AgcUnprogSeq::PINC);
-                self.handle_timer4()
+                push_unprog_seq(unprog, AgcUnprogSeq::PINC(consts::timer::MM_TIME4));
             }
             16 => {
-                push_unprog_seq(unprog, AgcUnprogSeq::PINC);
-                push_unprog_seq(unprog, AgcUnprogSeq::PINC);
-                self.handle_timer1_timer3(unprog)
+                push_unprog_seq(unprog, AgcUnprogSeq::PINC(consts::timer::MM_TIME1));
+                push_unprog_seq(unprog, AgcUnprogSeq::PINC(consts::timer::MM_TIME3));
             }
-            _ => 0,
+            _ => {}
         };
 
-        if self.time6_enable {
-            if self.scaler % 2 == 0o00000 {
-                if self.timer6 == 0o77777 || self.timer6 == 0o00000 {
-                    self.time6_enable = false;
-                    interrupt_mask |= 1 << consts::cpu::RUPT_TIME6;
-                } else {
-                    push_unprog_seq(unprog, AgcUnprogSeq::DINC);
-                    if self.timer6 & 0o40000 == 0o40000 {
-                        self.timer6 += 1;
-                    } else {
-                        self.timer6 -= 1;
-                    }
-                }
-            }
-        };
-        interrupt_mask
+        // TIME6 only counts while enabled through channel 13. The T6RUPT
+        // itself is raised by the CPU once the DINC sequence reports ZOUT.
+        if self.time6_enable && self.scaler % 2 == 0o00000 {
+            push_unprog_seq(unprog, AgcUnprogSeq::DINC(consts::timer::MM_TIME6));
+        }
     }
 
     pub fn pump_mcts(&mut self, mcts: u16, unprog: &mut Deque<AgcUnprogSeq, 8>) -> u16 {
@@ -118,33 +105,11 @@ impl Timers {
             rupt |= self.handle_downrupt();
         }
 
-        rupt |= if self.scaler_mcts >= 80 {
+        if self.scaler_mcts >= 80 {
             self.scaler_mcts -= 80;
-            self.increment_scaler(unprog)
-        } else {
-            0
-        };
-        rupt
-    }
-
-    pub fn handle_timer4(&mut self) -> u16 {
-        self.timer4 = (self.timer4 + 1) & 0o77777;
-        if self.timer4 == 0o40000 {
-            self.timer4 = 0;
-            return 1 << consts::cpu::RUPT_TIME4;
+            self.increment_scaler(unprog);
         }
-
-        0
-    }
-
-    pub fn handle_timer5(&mut self) -> u16 {
-        self.timer5 = (self.timer5 + 1) & 0o77777;
-        if self.timer5 == 0o40000 {
-            self.timer5 = 0;
-            return 1 << consts::cpu::RUPT_TIME5;
-        }
-
-        0
+        rupt
     }
 
     pub fn set_time6_enable(&mut self, val: bool) {
@@ -159,30 +124,13 @@ impl Timers {
         return 1 << consts::cpu::RUPT_DOWNRUPT;
     }
 
-    pub fn handle_timer1_timer3(&mut self, unprog: &mut Deque<AgcUnprogSeq, 8>) -> u16 {
-        self.timer1 += 1;
-        if self.timer1 & 0o37777 == 0o00000 {
-            push_unprog_seq(unprog, AgcUnprogSeq::PINC);
-        }
-
-        self.timer3 = (self.timer3 + 1) & 0o77777;
-        debug!("New TIMER3: {:o}", self.timer3);
-        if self.timer3 == 0o40000 {
-            self.timer3 = 0;
-            debug!("New TIMER3 interrupt!");
-            return 1 << consts::cpu::RUPT_TIME3;
-        }
-
-        0
-    }
-
     pub fn set_time_value(&mut self, timer_id: TimerType, value: u16) {
         match timer_id {
             TimerType::TIME1 => {
-                self.timer1 = value as u32;
+                self.timer1 = value & 0o77777;
             }
             TimerType::TIME2 => {
-                self.timer1 = value as u32;
+                self.timer2 = value & 0o77777;
             }
             TimerType::TIME3 => {
                 self.timer3 = value & 0o77777;
@@ -205,18 +153,52 @@ impl Timers {
     #[allow(dead_code)]
     pub fn reset(&mut self) {
         self.timer1 = 0;
+        self.timer2 = 0;
         self.timer3 = 0;
         self.timer4 = 0;
         self.timer5 = 0;
         self.timer6 = 0;
     }
+
+    pub(crate) fn save_state(&self, w: &mut StateWriter) -> Result<(), AgcStateError> {
+        w.bool(self.time6_enable)?;
+        w.u32(self.scaler)?;
+        w.u16(self.scaler_mcts)?;
+        w.u32(self.downrupt)?;
+        w.u8(self.downrupt_flags)?;
+        w.words(&[
+            self.timer1,
+            self.timer2,
+            self.timer3,
+            self.timer4,
+            self.timer5,
+            self.timer6,
+        ])
+    }
+
+    pub(crate) fn restore_state(&mut self, r: &mut StateReader) -> Result<(), AgcStateError> {
+        self.time6_enable = r.bool()?;
+        self.scaler = r.u32()?;
+        self.scaler_mcts = r.u16()?;
+        self.downrupt = r.u32()?;
+        self.downrupt_flags = r.u8()?;
+        let mut t = [0; 6];
+        r.words(&mut t)?;
+        self.timer1 = t[0];
+        self.timer2 = t[1];
+        self.timer3 = t[2];
+        self.timer4 = t[3];
+        self.timer5 = t[4];
+        self.timer6 = t[5];
+        Ok(())
+    }
 }
 
 impl MemoryType for Timers {
     fn read(&self, _bank_idx: usize, bank_offset: usize) -> u16 {
         let res = match bank_offset {
-            consts::timer::MM_TIME2 => ((self.timer1 >> 14) & 0o37777) as u16,
-            consts::timer::MM_TIME1 => (self.timer1 & 0o37777) as u16,
+            consts::timer::MM_TIME2 => self.timer2,
+            consts::timer::MM_TIME1 => self.timer1,
             consts::timer::MM_TIME3 => self.timer3,
             consts::timer::MM_TIME4 => self.timer4,
             consts::timer::MM_TIME5 => self.timer5,
@@ -234,7 +216,7 @@ impl MemoryType for Timers {
         );
         match bank_offset {
             consts::timer::MM_TIME2 => {
-                self.set_time_value(TimerType::TIME1, value);
+                self.set_time_value(TimerType::TIME2, value);
             }
             consts::timer::MM_TIME1 => {
                 self.set_time_value(TimerType::TIME1, value);
@@ -1,5 +1,6 @@
 use crate::consts::edit::*;
 use crate::mem::MemoryType;
+use crate::state::{AgcStateError, StateReader, StateWriter};
 use log::{error, trace};
 
 #[derive(Clone)]
@@ -27,6 +28,32 @@ impl EditRegisters {
         self.sr = 0;
         self.edop = 0;
     }
+
+    /// Stores a value as is, without the editing a write applies.
+    pub(crate) fn store(&mut self, bank_offset: usize, value: u16) {
+        let value = value & 0x7FFF;
+        match bank_offset {
+            SG_CYL => self.cyl = value,
+            SG_CYR => self.cyr = value,
+            SG_SR => self.sr = value,
+            SG_EDOP => self.edop = value,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn save_state(&self, w: &mut StateWriter) -> Result<(), AgcStateError> {
+        w.words(&[self.cyr, self.sr, self.cyl, self.edop])
+    }
+
+    pub(crate) fn restore_state(&mut self, r: &mut StateReader) -> Result<(), AgcStateError> {
+        let mut vals = [0; 4];
+        r.words(&mut vals)?;
+        self.cyr = vals[0];
+        self.sr = vals[1];
+        self.cyl = vals[2];
+        self.edop = vals[3];
+        Ok(())
+    }
 }
 
 impl MemoryType for EditRegisters {
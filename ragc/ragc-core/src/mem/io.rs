@@ -1,5 +1,6 @@
 use super::mods::AgcIoPeriph;
 use crate::consts::io;
+use crate::state::{AgcStateError, StateReader, StateWriter};
 use crate::utils::Option;
 
 use log::{debug, error, warn};
@@ -66,18 +67,24 @@ impl<'a> Io<'a> {
                 //println!("CHAN32: {:5o}", val);
                 val | (self.io_mem[0o32] & 0o57777)
             }
-            io::CHANNEL_CHAN33 => 0o77777,
+            io::CHANNEL_CHAN33 => {
+                // The warning flip-flop is reset by reading the channel.
+                let val = self.io_mem[io::CHANNEL_CHAN33];
+                self.io_mem[io::CHANNEL_CHAN33] |= io::CHAN33_AGC_WARNING;
+                val
+            }
             io::CHANNEL_CHAN34 | io::CHANNEL_CHAN35 => match &self.downrupt {
                 Option::Some(x) => x.read(channel_idx),
                 Option::None => 0o77777,
             },
+            io::CHANNEL_CHAN77 => self.io_mem[io::CHANNEL_CHAN77],
             0o163 => match &self.dsky {
                 Option::Some(x) => x.read(channel_idx),
                 Option::None => 0o77777,
             },
             _ => {
                 error!("Unknown IO Channel: {:o}", channel_idx);
-                self.io_mem[channel_idx]
+                self.channel(channel_idx)
             }
         }
     }
@@ -107,10 +114,79 @@ impl<'a> Io<'a> {
             io::CHANNEL_CHAN13 => {
                 self.io_mem[io::CHANNEL_CHAN13] = val;
             }
-            io::CHANNEL_CHAN32 => {}
-            _ => {
-                self.io_mem[channel_idx] = val;
+            io::CHANNEL_CHAN32 | io::CHANNEL_CHAN33 => {}
+            io::CHANNEL_CHAN77 => {
+                // Any write resets the restart monitor.
+                self.io_mem[io::CHANNEL_CHAN77] = 0;
+                self.set_dsky_lamp(io::CHAN163_AGC_WARNING, false);
             }
+            // Channel instructions address 512 channels, but nothing is
+            // wired past the ones kept here.
+            _ => match self.io_mem.get_mut(channel_idx) {
+                Some(x) => *x = val,
+                None => error!("Unknown IO Channel: {:o}", channel_idx),
+            },
+        }
+    }
+
+    /// Value last latched into a channel, without the side effects of a
+    /// read.
+    pub fn channel(&self, channel_idx: usize) -> u16 {
+        if channel_idx >= self.io_mem.len() {
+            return 0;
+        }
+        self.io_mem[channel_idx]
+    }
+
+    /// Latches alarm bits into the channel 77 restart monitor. Every alarm
+    /// also pulls the AGC WARNING discrete of channel 33 low and lights the
+    /// AGC WARNING lamp until software resets channel 77.
+    pub fn raise_alarm(&mut self, bits: u16) {
+        self.io_mem[io::CHANNEL_CHAN77] |= bits;
+        self.io_mem[io::CHANNEL_CHAN33] &= !io::CHAN33_AGC_WARNING;
+        self.set_dsky_lamp(io::CHAN163_AGC_WARNING, true);
+    }
+
+    fn set_dsky_lamp(&mut self, lamp: u16, lit: bool) {
+        if let Option::Some(x) = &mut self.dsky {
+            let val = x.read(0o163);
+            x.write(0o163, if lit { val | lamp } else { val & !lamp });
+        }
+    }
+
+    /// Saves the channel values followed by the state of the DSKY and the
+    /// downrupt peripherals.
+    pub(crate) fn save_state(&self, w: &mut StateWriter) -> Result<(), AgcStateError> {
+        w.words(&self.io_mem)?;
+        w.periph(|buf| match &self.dsky {
+            Option::Some(x) => x.save_state(buf),
+            Option::None => 0,
+        })?;
+        w.periph(|buf| match &self.downrupt {
+            Option::Some(x) => x.save_state(buf),
+            Option::None => 0,
+        })
+    }
+
+    pub(crate) fn restore_state(&mut self, r: &mut StateReader) -> Result<(), AgcStateError> {
+        r.words(&mut self.io_mem)?;
+        let dsky = r.periph()?;
+        if let Option::Some(x) = &mut self.dsky {
+            x.restore_state(dsky);
+        }
+        let downrupt = r.periph()?;
+        if let Option::Some(x) = &mut self.downrupt {
+            x.restore_state(downrupt);
+        }
+        Ok(())
+    }
+
+    pub fn tick(&mut self, total_cycles: usize) {
+        if let Option::Some(x) = &mut self.dsky {
+            x.tick(total_cycles);
+        }
+        if let Option::Some(x) = &mut self.downrupt {
+            x.tick(total_cycles);
         }
     }
 
@@ -1,5 +1,6 @@
 use crate::consts;
 use crate::mem::MemoryType;
+use crate::state::{AgcStateError, StateReader, StateWriter};
 
 #[derive(Clone)]
 pub struct Memory {
@@ -16,6 +17,20 @@ impl Memory {
     pub fn reset(&mut self) {
         self.banks = [[0; consts::RAM_BANK_NUM_WORDS]; consts::RAM_NUM_BANKS];
     }
+
+    pub(crate) fn save_state(&self, w: &mut StateWriter) -> Result<(), AgcStateError> {
+        for bank in self.banks.iter() {
+            w.words(bank)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn restore_state(&mut self, r: &mut StateReader) -> Result<(), AgcStateError> {
+        for bank in self.banks.iter_mut() {
+            r.words(bank)?;
+        }
+        Ok(())
+    }
 }
 
 impl MemoryType for Memory {
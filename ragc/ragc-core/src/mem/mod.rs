@@ -1,3 +1,4 @@
+pub mod addr;
 mod clocks;
 mod edit_registers;
 pub mod io;
@@ -7,16 +8,35 @@ mod registers;
 mod rom;
 mod special_registers;
 
+pub use addr::{AgcAddr, AgcAddrParseError, AgcBankContext};
 pub use io::Io;
+pub use rom::{rope_word, set_rope_word};
 
-use heapless::spsc::Producer;
+use heapless::spsc::Consumer;
 
-use log::{error, trace};
+use log::{error, trace, warn};
 
 use self::mods::AgcIoPeriph;
 
 use crate::consts;
 use crate::consts::memmap;
+use crate::core_dump::AgcCoreDump;
+use crate::fault::MAX_FAULTS;
+use crate::state::{AgcStateError, StateReader, StateWriter};
+use crate::symbols::{AgcSymbolic, AgcSymbols};
+
+/// Channels restored from a core dump. These are the output latches the
+/// software sets up; inputs come from the hardware and channel 10 only holds
+/// the last DSKY relay row written.
+const CORE_DUMP_OUTPUT_CHANNELS: [usize; 7] = [
+    consts::io::CHANNEL_PYJETS,
+    consts::io::CHANNEL_ROLLJETS,
+    consts::io::CHANNEL_SUPERBNK,
+    consts::io::CHANNEL_DSALMOUT,
+    consts::io::CHANNEL_CHAN12,
+    consts::io::CHANNEL_CHAN13,
+    consts::io::CHANNEL_CHAN14,
+];
 
 trait MemoryType {
     fn read(&self, bank_idx: usize, bank_offset: usize) -> u16;
@@ -33,20 +53,29 @@ pub struct MemoryMap<'a> {
     regs: registers::Registers,
     rom_debug: bool,
     superbank: bool,
+    rupt_rx: Consumer<'a, u8, 8>,
+    stuck_bits: heapless::Vec<(usize, u16, u16), MAX_FAULTS>,
+    symbols: Option<&'a dyn AgcSymbols>,
 }
 
 impl<'a> MemoryMap<'a> {
-    pub fn new_blank(rupt_tx: Producer<u8, 8>) -> MemoryMap {
+    /// Creates a memory map with a blank rope and no peripherals. Interrupts
+    /// are requested by pushing `RUPT_*` numbers to the producer side of
+    /// `rupt_rx`.
+    pub fn new_blank(rupt_rx: Consumer<'a, u8, 8>) -> MemoryMap<'a> {
         MemoryMap {
             ram: memory::Memory::new(),
             rom: rom::Rom::blank(),
             io: io::Io::blank(),
             edit: edit_registers::EditRegisters::new(),
-            special: special_registers::SpecialRegisters::new(rupt_tx),
+            special: special_registers::SpecialRegisters::new(),
             timers: clocks::Timers::new(),
             regs: registers::Registers::new(),
             superbank: false,
             rom_debug: false,
+            rupt_rx,
+            stuck_bits: heapless::Vec::new(),
+            symbols: None,
         }
     }
 
@@ -54,18 +83,21 @@ impl<'a> MemoryMap<'a> {
         program: &'a [[u16; consts::ROM_BANK_NUM_WORDS]; consts::ROM_NUM_BANKS],
         downrupt: &'a mut dyn AgcIoPeriph,
         dsky: &'a mut dyn AgcIoPeriph,
-        rupt_tx: Producer<u8, 8>,
+        rupt_rx: Consumer<'a, u8, 8>,
     ) -> MemoryMap<'a> {
         MemoryMap {
             ram: memory::Memory::new(),
             rom: rom::Rom::new(program),
             edit: edit_registers::EditRegisters::new(),
             io: io::Io::new(downrupt, dsky),
-            special: special_registers::SpecialRegisters::new(rupt_tx),
+            special: special_registers::SpecialRegisters::new(),
             timers: clocks::Timers::new(),
             regs: registers::Registers::new(),
             superbank: false,
             rom_debug: false,
+            rupt_rx,
+            stuck_bits: heapless::Vec::new(),
+            symbols: None,
         }
     }
 
@@ -76,6 +108,20 @@ impl<'a> MemoryMap<'a> {
         //self.io.reset();     // TODO: Implement a reset for IO Space
     }
 
+    /// Names locations in log messages after `symbols`.
+    pub fn set_symbols(&mut self, symbols: &'a dyn AgcSymbols) {
+        self.symbols = Some(symbols);
+    }
+
+    pub fn symbols(&self) -> Option<&'a dyn AgcSymbols> {
+        self.symbols
+    }
+
+    /// Location a CPU address currently selects, by symbol if one is known.
+    pub fn symbolic(&self, idx: usize) -> AgcSymbolic<'a> {
+        AgcSymbolic::new(self.resolve(idx), self.symbols)
+    }
+
     pub fn enable_rom_write(&mut self) {
         self.rom_debug = true;
     }
@@ -126,6 +172,25 @@ impl<'a> MemoryMap<'a> {
     }
 
     pub fn read_io(&mut self, idx: usize) -> u16 {
+        let val = self.read_io_unstuck(idx);
+        self.stuck_bits
+            .iter()
+            .filter(|x| x.0 == idx)
+            .fold(val, |val, x| (val & !x.1) | (x.2 & x.1))
+    }
+
+    /// Forces the bits of `mask` read from a channel to the matching bits of
+    /// `value`, as if the input lines were stuck. Returns false if too many
+    /// channels already have stuck bits.
+    pub fn set_stuck_bits(&mut self, channel: usize, mask: u16, value: u16) -> bool {
+        self.stuck_bits.push((channel, mask, value)).is_ok()
+    }
+
+    pub fn clear_stuck_bits(&mut self) {
+        self.stuck_bits.clear();
+    }
+
+    fn read_io_unstuck(&mut self, idx: usize) -> u16 {
         match idx {
             consts::io::CHANNEL_L => self.regs.read(0, consts::cpu::REG_L),
             consts::io::CHANNEL_Q => self.regs.read(0, consts::cpu::REG_Q),
@@ -172,7 +237,7 @@ impl<'a> MemoryMap<'a> {
             }
             memmap::AGC_MM_FIXED_START..=memmap::AGC_MM_FIXED_END => {
                 if self.rom_debug == false {
-                    error!("Writing to ROM location: {:x}", idx);
+                    error!("Writing to ROM location: {}", self.symbolic(idx));
                     return;
                 }
 
@@ -240,6 +305,163 @@ impl<'a> MemoryMap<'a> {
         val
     }
 
+    /// Bank context currently selected by EB, FB and the superbank bit.
+    pub fn bank_context(&self) -> AgcBankContext {
+        AgcBankContext {
+            ebank: Some(self.regs.ebank as u8),
+            fbank: addr::physical_fbank(self.regs.fbank, self.superbank),
+        }
+    }
+
+    /// Resolves a 12-bit CPU address to the physical word it currently
+    /// selects.
+    pub fn resolve(&self, idx: usize) -> AgcAddr {
+        AgcAddr::from_cpu(
+            idx as u16,
+            self.regs.ebank as u8,
+            addr::physical_fbank(self.regs.fbank, self.superbank),
+        )
+    }
+
+    /// Reads a word by its physical location, independent of the bank
+    /// registers. Erasable bank 0 below 061 is the register space.
+    pub fn read_physical(&self, addr: AgcAddr) -> u16 {
+        match addr {
+            AgcAddr::Erasable { bank: 0, offset } if offset <= 0o60 => self.read(offset as usize),
+            AgcAddr::Erasable { bank, offset } => {
+                if bank as usize >= consts::RAM_NUM_BANKS {
+                    return 0;
+                }
+                self.ram.read(bank as usize, (offset & 0o377) as usize)
+            }
+            AgcAddr::Fixed { bank, offset } => self.rom.read(bank as usize, offset as usize),
+        }
+    }
+
+    /// Writes a word by its physical location. Fixed memory is only written
+    /// when ROM writes are enabled.
+    pub fn write_physical(&mut self, addr: AgcAddr, val: u16) {
+        match addr {
+            AgcAddr::Erasable { bank: 0, offset } if offset <= 0o60 => {
+                self.write(offset as usize, val)
+            }
+            AgcAddr::Erasable { bank, offset } => {
+                if bank as usize >= consts::RAM_NUM_BANKS {
+                    error!("Invalid erasable bank: {:o}", bank);
+                    return;
+                }
+                self.ram
+                    .write(bank as usize, (offset & 0o377) as usize, val)
+            }
+            AgcAddr::Fixed { bank, offset } => {
+                if !self.rom_debug {
+                    error!(
+                        "Writing to ROM location: {}",
+                        AgcSymbolic::new(addr, self.symbols)
+                    );
+                    return;
+                }
+                self.rom.write(bank as usize, offset as usize, val)
+            }
+        }
+    }
+
+    /// Value of a channel as a read would return it, without the side
+    /// effects of a read.
+    fn peek_channel(&self, idx: usize) -> u16 {
+        match idx {
+            consts::io::CHANNEL_L => self.regs.read(0, consts::cpu::REG_L),
+            consts::io::CHANNEL_Q => self.regs.read(0, consts::cpu::REG_Q),
+            consts::io::CHANNEL_HISCALAR => ((self.timers.read_scalar() >> 14) & 0o37777) as u16,
+            consts::io::CHANNEL_LOSCALAR => (self.timers.read_scalar() & 0o37777) as u16,
+            consts::io::CHANNEL_CHAN13 if self.timers.get_time6_enable() => {
+                self.io.channel(idx) | 0o40000
+            }
+            _ => self.io.channel(idx),
+        }
+    }
+
+    /// Copies erasable memory, registers included, and the channels into a
+    /// core dump.
+    pub fn save_core(&self, dump: &mut AgcCoreDump) {
+        for (bank, words) in dump.erasable.iter_mut().enumerate() {
+            for (offset, word) in words.iter_mut().enumerate() {
+                *word = self.read_physical(AgcAddr::Erasable {
+                    bank: bank as u8,
+                    offset: offset as u16,
+                });
+            }
+        }
+        for (idx, word) in dump.channels.iter_mut().enumerate() {
+            *word = self.peek_channel(idx);
+        }
+    }
+
+    /// Restores erasable memory and the output channels from a core dump,
+    /// as a power cycle would leave them. The central registers at 0 to 7
+    /// are flip-flops rather than core and are not restored.
+    pub fn load_core(&mut self, dump: &AgcCoreDump) {
+        for (bank, words) in dump.erasable.iter().enumerate() {
+            for (offset, word) in words.iter().enumerate() {
+                let addr = AgcAddr::Erasable {
+                    bank: bank as u8,
+                    offset: offset as u16,
+                };
+                match (bank, offset) {
+                    (0, 0o00..=0o07) => {}
+                    (0, 0o20..=0o23) => self.edit.store(offset, *word),
+                    _ => self.write_physical(addr, *word),
+                }
+            }
+        }
+        for idx in CORE_DUMP_OUTPUT_CHANNELS.iter() {
+            self.write_io(*idx, dump.channels[*idx]);
+        }
+    }
+
+    pub(crate) fn save_state(&self, w: &mut StateWriter) -> Result<(), AgcStateError> {
+        self.ram.save_state(w)?;
+        self.regs.save_state(w)?;
+        self.edit.save_state(w)?;
+        self.special.save_state(w)?;
+        self.timers.save_state(w)?;
+        self.io.save_state(w)?;
+        w.bool(self.superbank)
+    }
+
+    pub(crate) fn restore_state(&mut self, r: &mut StateReader) -> Result<(), AgcStateError> {
+        self.ram.restore_state(r)?;
+        self.regs.restore_state(r)?;
+        self.edit.restore_state(r)?;
+        self.special.restore_state(r)?;
+        self.timers.restore_state(r)?;
+        self.io.restore_state(r)?;
+        self.superbank = r.bool()?;
+        Ok(())
+    }
+
+    pub fn tick(&mut self, total_cycles: usize) {
+        self.io.tick(total_cycles);
+    }
+
+    pub fn raise_alarm(&mut self, bits: u16) {
+        self.io.raise_alarm(bits);
+    }
+
+    /// Drains the interrupt request line and returns the requested
+    /// interrupts as a mask of `1 << RUPT_*` bits.
+    pub fn take_rupt_requests(&mut self) -> u16 {
+        let mut val = 0;
+        while let Some(x) = self.rupt_rx.dequeue() {
+            if x > consts::cpu::RUPT_HANDRUPT {
+                warn!("Ignoring request for unknown interrupt {}", x);
+                continue;
+            }
+            val |= 1 << x;
+        }
+        val
+    }
+
     pub fn check_interrupts(&mut self) -> u16 {
         self.io.check_interrupt()
     }
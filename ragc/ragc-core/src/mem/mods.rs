@@ -5,4 +5,34 @@ pub trait AgcIoPeriph {
     fn read(&self, _channel_idx: usize) -> u16;
     fn write(&mut self, channel_idx: usize, value: u16);
     fn is_interrupt(&mut self) -> u16;
+
+    /// Called after every executed sequence with the number of MCTs the CPU
+    /// has run so far. Peripherals that schedule their events in AGC time,
+    /// rather than on the host clock, use it as their time base.
+    fn tick(&mut self, _total_cycles: usize) {}
+
+    /// Serializes the peripheral's state into `buf` for a machine snapshot
+    /// and returns the number of bytes used, or a length above `buf.len()`
+    /// if the state does not fit. Stateless peripherals can keep the
+    /// default.
+    fn save_state(&self, _buf: &mut [u8]) -> usize {
+        0
+    }
+
+    /// Restores state previously produced by `save_state`.
+    fn restore_state(&mut self, _buf: &[u8]) {}
+}
+
+/// Peripheral with nothing attached: channels read as 0, writes are
+/// dropped and it never interrupts.
+pub struct AgcNullPeriph;
+
+impl AgcIoPeriph for AgcNullPeriph {
+    fn read(&self, _channel_idx: usize) -> u16 {
+        0
+    }
+    fn write(&mut self, _channel_idx: usize, _value: u16) {}
+    fn is_interrupt(&mut self) -> u16 {
+        0
+    }
 }
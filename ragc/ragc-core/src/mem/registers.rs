@@ -1,5 +1,6 @@
 use crate::consts;
 use crate::mem::MemoryType;
+use crate::state::{AgcStateError, StateReader, StateWriter};
 use log::debug;
 
 #[derive(Clone)]
@@ -25,6 +26,19 @@ impl Registers {
         self.ebank = 0;
     }
 
+    pub(crate) fn save_state(&self, w: &mut StateWriter) -> Result<(), AgcStateError> {
+        w.words(&self.regs)?;
+        w.u8(self.fbank as u8)?;
+        w.u8(self.ebank as u8)
+    }
+
+    pub(crate) fn restore_state(&mut self, r: &mut StateReader) -> Result<(), AgcStateError> {
+        r.words(&mut self.regs)?;
+        self.fbank = (r.u8()? & 0x1F) as usize;
+        self.ebank = (r.u8()? & 0x7) as usize;
+        Ok(())
+    }
+
     fn update_bank_registers(&mut self) {
         let evalue: u16 = ((self.ebank & 0x7) << 8) as u16;
         let fvalue: u16 = ((self.fbank & 0x1F) << 10) as u16;
@@ -7,22 +7,48 @@ const DATA_LINE_NUM_PARTS: usize = 8;
 #[allow(dead_code)]
 const DATA_LINE_PART_LEN: usize = 6;
 
+const BANK_IDX_REF: [usize; 36] = [
+    2, 3, 0, 1, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31, 32, 33, 34, 35,
+];
+
+/// Reads a word out of a rope image. Ropes are stored the way yaYUL writes
+/// them: big-endian words shifted left by one to make room for the parity
+/// bit, with fixed-fixed banks 02 and 03 stored first.
+pub fn rope_word(
+    program: &[[u16; consts::ROM_BANK_NUM_WORDS]; consts::ROM_NUM_BANKS],
+    bank_idx: usize,
+    bank_offset: usize,
+) -> u16 {
+    if bank_idx >= consts::ROM_NUM_BANKS || bank_offset >= consts::ROM_BANK_NUM_WORDS {
+        return 0x0;
+    }
+    (u16::from_be(program[BANK_IDX_REF[bank_idx]][bank_offset]) >> 1) & 0x7FFF
+}
+
+/// Stores a word into a rope image in the layout `rope_word` reads, with
+/// odd parity in the low bit. Words outside the rope are ignored.
+pub fn set_rope_word(
+    program: &mut [[u16; consts::ROM_BANK_NUM_WORDS]; consts::ROM_NUM_BANKS],
+    bank_idx: usize,
+    bank_offset: usize,
+    value: u16,
+) {
+    if bank_idx >= consts::ROM_NUM_BANKS || bank_offset >= consts::ROM_BANK_NUM_WORDS {
+        return;
+    }
+    let value = value & 0x7FFF;
+    let parity = (value.count_ones() & 1) ^ 1;
+    program[BANK_IDX_REF[bank_idx]][bank_offset] = ((value << 1) | parity as u16).to_be();
+}
+
 pub struct Rom<'a> {
     program: Option<&'a [[u16; consts::ROM_BANK_NUM_WORDS]; consts::ROM_NUM_BANKS]>,
 }
 impl<'a> MemoryType for Rom<'a> {
     fn read(&self, bank_idx: usize, bank_offset: usize) -> u16 {
-        if bank_idx >= consts::ROM_NUM_BANKS || bank_offset >= consts::ROM_BANK_NUM_WORDS {
-            return 0x0;
-        }
         match self.program {
-            Option::Some(program) => {
-                const BANK_IDX_REF: [usize; 36] = [
-                    2, 3, 0, 1, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
-                    22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35,
-                ];
-                (u16::from_be(program[BANK_IDX_REF[bank_idx]][bank_offset]) >> 1) & 0x7FFF
-            }
+            Option::Some(program) => rope_word(program, bank_idx, bank_offset),
             _ => 0,
         }
     }
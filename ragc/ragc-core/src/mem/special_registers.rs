@@ -1,26 +1,62 @@
 use crate::consts::special::*;
 use crate::mem::MemoryType;
-use heapless::spsc::Producer;
+use crate::state::{AgcStateError, StateReader, StateWriter};
 #[derive(Clone)]
 pub struct SpecialRegisters {
     pub cdu: (u16, u16, u16),
     pub opt: (u16, u16),
     pub pipa: (u16, u16, u16),
     pub inlink: u16,
+    pub rnrad: u16,
+    pub gyroctr: u16,
+    pub outlink: u16,
 }
 
 impl SpecialRegisters {
-    pub fn new(_rupt_tx: Producer<u8, 8>) -> Self {
+    pub fn new() -> Self {
         Self {
             cdu: (0, 0, 0),
             inlink: 0,
             opt: (0, 0),
             pipa: (0, 0, 0),
+            rnrad: 0,
+            gyroctr: 0,
+            outlink: 0,
         }
     }
 
     #[allow(dead_code)]
     pub fn reset(&mut self) {}
+
+    pub(crate) fn save_state(&self, w: &mut StateWriter) -> Result<(), AgcStateError> {
+        w.words(&[
+            self.cdu.0,
+            self.cdu.1,
+            self.cdu.2,
+            self.opt.0,
+            self.opt.1,
+            self.pipa.0,
+            self.pipa.1,
+            self.pipa.2,
+            self.inlink,
+            self.rnrad,
+            self.gyroctr,
+            self.outlink,
+        ])
+    }
+
+    pub(crate) fn restore_state(&mut self, r: &mut StateReader) -> Result<(), AgcStateError> {
+        let mut v = [0; 12];
+        r.words(&mut v)?;
+        self.cdu = (v[0], v[1], v[2]);
+        self.opt = (v[3], v[4]);
+        self.pipa = (v[5], v[6], v[7]);
+        self.inlink = v[8];
+        self.rnrad = v[9];
+        self.gyroctr = v[10];
+        self.outlink = v[11];
+        Ok(())
+    }
 }
 
 impl MemoryType for SpecialRegisters {
@@ -39,23 +75,38 @@ impl MemoryType for SpecialRegisters {
             SG_PIPAY => self.pipa.1,
             SG_PIPAZ => self.pipa.2,
 
+            SG_RNRAD => self.rnrad,
+            SG_GYROCTR => self.gyroctr,
+
             // Inlink and Outlink Registers
             SG_INLINK => self.inlink,
-            SG_OUTLINK => 0,
+            SG_OUTLINK => self.outlink,
             SG_CDUXCMD | SG_CDUYCMD | SG_CDUZCMD => 0,
             _ => 0,
         }
     }
 
     fn write(&mut self, _bank_idx: usize, bank_offset: usize, value: u16) {
+        let value = value & 0x7FFF;
         match bank_offset {
-            SG_CDUX | SG_CDUY | SG_CDUZ | SG_OPTX | SG_OPTY | SG_PIPAX | SG_PIPAY | SG_PIPAZ => {}
+            SG_CDUX => self.cdu.0 = value,
+            SG_CDUY => self.cdu.1 = value,
+            SG_CDUZ => self.cdu.2 = value,
+            SG_OPTX => self.opt.0 = value,
+            SG_OPTY => self.opt.1 = value,
+            SG_PIPAX => self.pipa.0 = value,
+            SG_PIPAY => self.pipa.1 = value,
+            SG_PIPAZ => self.pipa.2 = value,
+            SG_RNRAD => self.rnrad = value,
+            SG_GYROCTR => self.gyroctr = value,
 
             // Inlink and Outlink Registers
             SG_INLINK => {
-                self.inlink = value & 0x7FFF;
+                self.inlink = value;
+            }
+            SG_OUTLINK => {
+                self.outlink = value;
             }
-            SG_OUTLINK => {}
 
             _ => {}
         }
@@ -0,0 +1,51 @@
+use crate::cpu::AgcUnprogSeq;
+use crate::instructions::AgcInst;
+use crate::mem::{AgcAddr, AgcBankContext};
+
+/// Maximum number of memory accesses recorded for a single step. Accesses
+/// past this limit are dropped from the record.
+pub const MAX_STEP_ACCESSES: usize = 32;
+
+/// Snapshot of the central and bank registers.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AgcRegisters {
+    pub z: u16,
+    pub a: u16,
+    pub l: u16,
+    pub q: u16,
+    pub eb: u16,
+    pub fb: u16,
+    pub bb: u16,
+}
+
+/// A single memory or IO channel access performed by the CPU. Memory
+/// accesses are resolved through the bank registers selected at the time of
+/// the access.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcMemAccess {
+    Read(AgcAddr, u16),
+    Write(AgcAddr, u16),
+    IoRead(usize, u16),
+    IoWrite(usize, u16),
+}
+
+/// Everything the CPU did while executing one sequence.
+#[derive(Debug)]
+pub struct AgcStepInfo<'s> {
+    pub before: AgcRegisters,
+    /// Banks selected when the sequence started, used to resolve the
+    /// instruction's address.
+    pub banks: AgcBankContext,
+    pub after: AgcRegisters,
+    pub accesses: &'s [AgcMemAccess],
+    pub cycles: u16,
+    pub total_cycles: usize,
+}
+
+/// Hook invoked by `AgcCpu::step_observed` for every executed sequence.
+/// Both callbacks default to doing nothing, so an observer only needs to
+/// implement the ones it is interested in.
+pub trait CpuObserver {
+    fn programmed(&mut self, _inst: &AgcInst, _info: &AgcStepInfo) {}
+    fn unprogrammed(&mut self, _seq: &AgcUnprogSeq, _info: &AgcStepInfo) {}
+}
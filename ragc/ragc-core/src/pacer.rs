@@ -0,0 +1,152 @@
+/// Length of one memory cycle time (MCT), in nanoseconds.
+pub const MCT_NANOS: u64 = 11700;
+
+/// MCTs in one second of AGC time.
+pub const MCTS_PER_SECOND: u64 = 1_000_000_000 / MCT_NANOS;
+
+/// Default bound on the host time a single `Pacer::budget` call makes up,
+/// 100ms.
+pub const DEFAULT_MAX_CATCHUP_NANOS: u64 = 100_000_000;
+
+/// Cycles handed out by each budget at max speed, about 100ms of AGC time.
+pub const MAX_SPEED_SLICE: u64 = 8547;
+
+/// How fast AGC time runs relative to the host clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaceMode {
+    /// One MCT every 11.7us of host time.
+    RealTime,
+    /// Real time multiplied by the given factor. Factors below 1 slow the
+    /// AGC down.
+    Accelerated(f64),
+    /// No pacing, the CPU runs as fast as the host allows.
+    MaxSpeed,
+    /// AGC time stands still.
+    Paused,
+}
+
+impl PaceMode {
+    /// Target rate in MCTs per host second, if the mode has one.
+    pub fn target_rate(&self) -> Option<f64> {
+        let real_time = 1_000_000_000.0 / MCT_NANOS as f64;
+        match *self {
+            PaceMode::RealTime => Some(real_time),
+            PaceMode::Accelerated(x) => Some(real_time * x),
+            PaceMode::MaxSpeed => None,
+            PaceMode::Paused => Some(0.0),
+        }
+    }
+}
+
+/// Rates measured since the statistics were last reset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaceStats {
+    /// MCTs per host second the mode asks for, none at max speed.
+    pub target_rate: Option<f64>,
+    /// MCTs per host second actually executed.
+    pub achieved_rate: f64,
+    /// Cycles given up because the host fell further behind than the
+    /// catch-up bound.
+    pub dropped_cycles: u64,
+}
+
+/// Turns elapsed host time into a cycle budget for the CPU. The pacer has
+/// no clock of its own: the caller measures host time, asks for a budget,
+/// runs the CPU for about that many MCTs and reports how many it ran.
+///
+/// The cycles owed to the CPU never exceed what the mode runs in the
+/// catch-up bound of host time, so a host stall is dropped instead of
+/// being made up in one long burst. The bound scales with the mode, so
+/// accelerated modes are not capped by it.
+pub struct Pacer {
+    mode: PaceMode,
+    max_catchup_nanos: u64,
+    owed: f64,
+
+    stats_nanos: u64,
+    stats_cycles: u64,
+    dropped_cycles: u64,
+}
+
+impl Pacer {
+    pub fn new(mode: PaceMode) -> Pacer {
+        Pacer {
+            mode,
+            max_catchup_nanos: DEFAULT_MAX_CATCHUP_NANOS,
+            owed: 0.0,
+            stats_nanos: 0,
+            stats_cycles: 0,
+            dropped_cycles: 0,
+        }
+    }
+
+    pub fn mode(&self) -> PaceMode {
+        self.mode
+    }
+
+    /// Switches the mode. Cycles owed under the previous mode are dropped,
+    /// as are the statistics.
+    pub fn set_mode(&mut self, mode: PaceMode) {
+        self.mode = mode;
+        self.owed = 0.0;
+        self.reset_stats();
+    }
+
+    /// Sets the largest amount of host time a single budget may make up.
+    pub fn set_max_catchup(&mut self, nanos: u64) {
+        self.max_catchup_nanos = nanos;
+    }
+
+    /// Accounts for `elapsed_nanos` of host time and returns the number of
+    /// MCTs the CPU should run now.
+    pub fn budget(&mut self, elapsed_nanos: u64) -> u64 {
+        self.stats_nanos += elapsed_nanos;
+
+        let rate = match self.mode.target_rate() {
+            Some(x) => x,
+            None => return MAX_SPEED_SLICE,
+        };
+
+        let max_owed = self.max_catchup_nanos as f64 * rate / 1_000_000_000.0;
+        self.owed += elapsed_nanos as f64 * rate / 1_000_000_000.0;
+        if self.owed > max_owed {
+            self.dropped_cycles += (self.owed - max_owed) as u64;
+            self.owed = max_owed;
+        }
+
+        if self.owed > 0.0 {
+            self.owed as u64
+        } else {
+            0
+        }
+    }
+
+    /// Records the MCTs the CPU ran. Instructions don't end on the budget
+    /// boundary, so anything run past it is taken from the next budget.
+    pub fn ran(&mut self, cycles: u64) {
+        self.stats_cycles += cycles;
+        if self.mode.target_rate().is_some() {
+            self.owed -= cycles as f64;
+        }
+    }
+
+    pub fn stats(&self) -> PaceStats {
+        let achieved_rate = if self.stats_nanos == 0 {
+            0.0
+        } else {
+            self.stats_cycles as f64 * 1_000_000_000.0 / self.stats_nanos as f64
+        };
+
+        PaceStats {
+            target_rate: self.mode.target_rate(),
+            achieved_rate,
+            dropped_cycles: self.dropped_cycles,
+        }
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats_nanos = 0;
+        self.stats_cycles = 0;
+        self.dropped_cycles = 0;
+    }
+}
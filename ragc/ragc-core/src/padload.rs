@@ -0,0 +1,169 @@
+use core::fmt;
+use core::iter::Enumerate;
+use core::str::{FromStr, Lines};
+
+use crate::consts::{RAM_BANK_NUM_WORDS, RAM_NUM_BANKS};
+use crate::mem::{AgcAddr, MemoryMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcPadLoadError {
+    /// A line that does not hold exactly an address and a value.
+    InvalidLine {
+        line: usize,
+    },
+    InvalidAddress {
+        line: usize,
+    },
+    /// A symbol the symbol table does not know, or any symbol when no
+    /// table is loaded.
+    UnknownSymbol {
+        line: usize,
+    },
+    /// An address outside of erasable memory.
+    NotErasable {
+        line: usize,
+    },
+    /// A value that is not a 15-bit octal word.
+    InvalidValue {
+        line: usize,
+    },
+}
+
+impl fmt::Display for AgcPadLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgcPadLoadError::InvalidLine { line } => {
+                write!(f, "line {}: expected an address and a value", line)
+            }
+            AgcPadLoadError::InvalidAddress { line } => write!(f, "line {}: invalid address", line),
+            AgcPadLoadError::UnknownSymbol { line } => write!(f, "line {}: unknown symbol", line),
+            AgcPadLoadError::NotErasable { line } => {
+                write!(f, "line {}: address is not in erasable memory", line)
+            }
+            AgcPadLoadError::InvalidValue { line } => write!(f, "line {}: invalid value", line),
+        }
+    }
+}
+
+/// One word of a pad load.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcPadWord {
+    pub line: usize,
+    pub addr: AgcAddr,
+    pub value: u16,
+}
+
+/// Pad-load file: erasable values to set before the AGC is powered up.
+///
+/// Each line holds an address and an octal value, `#` starts a comment.
+/// Addresses are given the way `AgcAddr` parses them, `E3,1400` or `0061`,
+/// or as a symbol with an optional octal offset such as `REFSMMAT+4`.
+/// Symbols are looked up through `symbols`.
+pub struct AgcPadLoad<'a, F> {
+    lines: Enumerate<Lines<'a>>,
+    symbols: F,
+}
+
+impl<'a, F: Fn(&str) -> Option<AgcAddr>> AgcPadLoad<'a, F> {
+    pub fn new(text: &'a str, symbols: F) -> Self {
+        AgcPadLoad {
+            lines: text.lines().enumerate(),
+            symbols,
+        }
+    }
+
+    /// Checks every line, then writes the values into erasable memory.
+    /// Nothing is written if any line is invalid. Returns the number of
+    /// words written.
+    pub fn apply(self, mm: &mut MemoryMap) -> Result<usize, AgcPadLoadError> {
+        for (idx, line) in self.lines.clone() {
+            if let Some(Err(x)) = self.parse_line(idx + 1, line) {
+                return Err(x);
+            }
+        }
+
+        let mut count = 0;
+        for word in self {
+            let word = word?;
+            mm.write_physical(word.addr, word.value);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn parse_line(&self, line: usize, text: &str) -> Option<Result<AgcPadWord, AgcPadLoadError>> {
+        let text = match text.find('#') {
+            Some(x) => &text[..x],
+            None => text,
+        };
+
+        let mut fields = text.split_whitespace();
+        let (addr, value) = match (fields.next(), fields.next(), fields.next()) {
+            (None, _, _) => return None,
+            (Some(addr), Some(value), None) => (addr, value),
+            _ => return Some(Err(AgcPadLoadError::InvalidLine { line })),
+        };
+
+        Some(
+            self.parse_addr(line, addr)
+                .and_then(|addr| match u16::from_str_radix(value, 8) {
+                    Ok(value) if value <= 0o77777 => Ok(AgcPadWord { line, addr, value }),
+                    _ => Err(AgcPadLoadError::InvalidValue { line }),
+                }),
+        )
+    }
+
+    fn parse_addr(&self, line: usize, text: &str) -> Result<AgcAddr, AgcPadLoadError> {
+        let starts_numeric = text.starts_with(|x: char| x.is_ascii_digit());
+        let is_bank = text.contains(',');
+        let addr = if starts_numeric || is_bank {
+            AgcAddr::from_str(text).map_err(|_| AgcPadLoadError::InvalidAddress { line })?
+        } else {
+            let (name, offset) = match text.split_once('+') {
+                Some((name, offset)) => match u16::from_str_radix(offset, 8) {
+                    Ok(x) => (name, x as usize),
+                    Err(_) => return Err(AgcPadLoadError::InvalidAddress { line }),
+                },
+                None => (text, 0),
+            };
+            let addr = (self.symbols)(name).ok_or(AgcPadLoadError::UnknownSymbol { line })?;
+            offset_erasable(addr, offset).ok_or(AgcPadLoadError::NotErasable { line })?
+        };
+
+        match addr {
+            AgcAddr::Erasable { bank, .. } if (bank as usize) < RAM_NUM_BANKS => Ok(addr),
+            _ => Err(AgcPadLoadError::NotErasable { line }),
+        }
+    }
+}
+
+impl<'a, F: Fn(&str) -> Option<AgcAddr>> Iterator for AgcPadLoad<'a, F> {
+    type Item = Result<AgcPadWord, AgcPadLoadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (idx, line) = self.lines.next()?;
+            if let Some(x) = self.parse_line(idx + 1, line) {
+                return Some(x);
+            }
+        }
+    }
+}
+
+/// Moves an erasable address by `n` words, treating erasable memory as one
+/// contiguous range.
+fn offset_erasable(addr: AgcAddr, n: usize) -> Option<AgcAddr> {
+    match addr {
+        AgcAddr::Erasable { bank, offset } => {
+            let x = bank as usize * RAM_BANK_NUM_WORDS + offset as usize + n;
+            if x >= RAM_NUM_BANKS * RAM_BANK_NUM_WORDS {
+                return None;
+            }
+            Some(AgcAddr::Erasable {
+                bank: (x / RAM_BANK_NUM_WORDS) as u8,
+                offset: (x % RAM_BANK_NUM_WORDS) as u16,
+            })
+        }
+        AgcAddr::Fixed { .. } => None,
+    }
+}
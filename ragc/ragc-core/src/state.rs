@@ -0,0 +1,298 @@
+use crate::cpu::{AgcRestart, AgcRestartCause, AgcUnprogSeq};
+
+/// Version of the snapshot layout. Bump it whenever a component changes
+/// what it saves.
+pub const MACHINE_STATE_VERSION: u16 = 2;
+
+/// Upper bound of an encoded snapshot, in bytes. Peripherals share the
+/// space the CPU and memory map leave, and saving fails with
+/// `BufferTooSmall` once they run out of it.
+pub const MACHINE_STATE_SIZE: usize = 8192;
+
+const MACHINE_STATE_MAGIC: [u8; 4] = *b"RAGC";
+const HEADER_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgcStateError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    BufferTooSmall,
+    InvalidData,
+}
+
+/// Serialized snapshot of the whole machine: CPU, memory map, timers, IO
+/// channels and peripherals. The encoding is little-endian, starts with a
+/// magic and the layout version, followed by the length of the payload.
+pub struct MachineState {
+    data: [u8; MACHINE_STATE_SIZE],
+    len: usize,
+}
+
+impl MachineState {
+    pub(crate) fn new() -> MachineState {
+        MachineState {
+            data: [0; MACHINE_STATE_SIZE],
+            len: 0,
+        }
+    }
+
+    /// Validates the header of an encoded snapshot and copies it.
+    pub fn from_bytes(bytes: &[u8]) -> Result<MachineState, AgcStateError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(AgcStateError::Truncated);
+        }
+        if bytes[0..4] != MACHINE_STATE_MAGIC {
+            return Err(AgcStateError::BadMagic);
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != MACHINE_STATE_VERSION {
+            return Err(AgcStateError::UnsupportedVersion(version));
+        }
+
+        let len = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]) as usize;
+        if len > MACHINE_STATE_SIZE - HEADER_SIZE {
+            return Err(AgcStateError::InvalidData);
+        }
+        if bytes.len() < HEADER_SIZE + len {
+            return Err(AgcStateError::Truncated);
+        }
+
+        let mut state = MachineState::new();
+        state.data[..HEADER_SIZE + len].copy_from_slice(&bytes[..HEADER_SIZE + len]);
+        state.len = HEADER_SIZE + len;
+        Ok(state)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    pub fn version(&self) -> u16 {
+        u16::from_le_bytes([self.data[4], self.data[5]])
+    }
+
+    pub(crate) fn writer(&mut self) -> StateWriter<'_> {
+        StateWriter {
+            buf: &mut self.data[HEADER_SIZE..],
+            pos: 0,
+        }
+    }
+
+    pub(crate) fn finish(&mut self, payload_len: usize) {
+        self.data[0..4].copy_from_slice(&MACHINE_STATE_MAGIC);
+        self.data[4..6].copy_from_slice(&MACHINE_STATE_VERSION.to_le_bytes());
+        self.data[6..10].copy_from_slice(&(payload_len as u32).to_le_bytes());
+        self.len = HEADER_SIZE + payload_len;
+    }
+
+    pub(crate) fn reader(&self) -> StateReader<'_> {
+        StateReader {
+            buf: &self.data[HEADER_SIZE..self.len],
+            pos: 0,
+        }
+    }
+}
+
+pub(crate) struct StateWriter<'b> {
+    buf: &'b mut [u8],
+    pos: usize,
+}
+
+impl<'b> StateWriter<'b> {
+    pub fn written(&self) -> usize {
+        self.pos
+    }
+
+    pub fn bytes(&mut self, data: &[u8]) -> Result<(), AgcStateError> {
+        let end = self.pos + data.len();
+        if end > self.buf.len() {
+            return Err(AgcStateError::BufferTooSmall);
+        }
+        self.buf[self.pos..end].copy_from_slice(data);
+        self.pos = end;
+        Ok(())
+    }
+
+    pub fn u8(&mut self, val: u8) -> Result<(), AgcStateError> {
+        self.bytes(&[val])
+    }
+
+    pub fn bool(&mut self, val: bool) -> Result<(), AgcStateError> {
+        self.u8(val as u8)
+    }
+
+    pub fn u16(&mut self, val: u16) -> Result<(), AgcStateError> {
+        self.bytes(&val.to_le_bytes())
+    }
+
+    pub fn u32(&mut self, val: u32) -> Result<(), AgcStateError> {
+        self.bytes(&val.to_le_bytes())
+    }
+
+    pub fn u64(&mut self, val: u64) -> Result<(), AgcStateError> {
+        self.bytes(&val.to_le_bytes())
+    }
+
+    pub fn words(&mut self, vals: &[u16]) -> Result<(), AgcStateError> {
+        for val in vals {
+            self.u16(*val)?;
+        }
+        Ok(())
+    }
+
+    /// Lets a peripheral serialize itself into the space left after a
+    /// length prefix.
+    pub fn periph(&mut self, save: impl FnOnce(&mut [u8]) -> usize) -> Result<(), AgcStateError> {
+        let start = self.pos + 2;
+        if start > self.buf.len() {
+            return Err(AgcStateError::BufferTooSmall);
+        }
+        let end = self.buf.len().min(start + u16::MAX as usize);
+        let len = save(&mut self.buf[start..end]);
+        if len > end - start {
+            return Err(AgcStateError::BufferTooSmall);
+        }
+        self.u16(len as u16)?;
+        self.pos += len;
+        Ok(())
+    }
+
+    pub fn unprog_seq(&mut self, seq: &AgcUnprogSeq) -> Result<(), AgcStateError> {
+        let (tag, addr) = match *seq {
+            AgcUnprogSeq::PINC(x) => (0, x),
+            AgcUnprogSeq::PCDU(x) => (1, x),
+            AgcUnprogSeq::MINC(x) => (2, x),
+            AgcUnprogSeq::MCDU(x) => (3, x),
+            AgcUnprogSeq::DINC(x) => (4, x),
+            AgcUnprogSeq::SHINC(x) => (5, x),
+            AgcUnprogSeq::SHANC(x) => (6, x),
+            AgcUnprogSeq::INOTRD => (7, 0),
+            AgcUnprogSeq::INOTLD => (8, 0),
+            AgcUnprogSeq::FETCH => (9, 0),
+            AgcUnprogSeq::STORE => (10, 0),
+            AgcUnprogSeq::GOJ => (11, 0),
+            AgcUnprogSeq::TCSAJ => (12, 0),
+            AgcUnprogSeq::RUPT => (13, 0),
+        };
+        self.u8(tag)?;
+        self.u16(addr as u16)
+    }
+
+    pub fn restart(&mut self, restart: &Option<AgcRestart>) -> Result<(), AgcStateError> {
+        let x = match restart {
+            Some(x) => x,
+            None => return self.u8(0),
+        };
+        let tag = match x.cause {
+            AgcRestartCause::NightWatchman => 1,
+            AgcRestartCause::TcTrap => 2,
+            AgcRestartCause::RuptLock => 3,
+            AgcRestartCause::Parity => 4,
+            AgcRestartCause::Forced => 5,
+        };
+        self.u8(tag)?;
+        self.u64(x.total_cycles as u64)?;
+        self.u16(x.z)
+    }
+}
+
+pub(crate) struct StateReader<'b> {
+    buf: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> StateReader<'b> {
+    pub fn bytes(&mut self, len: usize) -> Result<&'b [u8], AgcStateError> {
+        let end = self.pos + len;
+        if end > self.buf.len() {
+            return Err(AgcStateError::Truncated);
+        }
+        let res = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(res)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, AgcStateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, AgcStateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(AgcStateError::InvalidData),
+        }
+    }
+
+    pub fn u16(&mut self) -> Result<u16, AgcStateError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, AgcStateError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, AgcStateError> {
+        let b = self.bytes(8)?;
+        let mut v = [0u8; 8];
+        v.copy_from_slice(b);
+        Ok(u64::from_le_bytes(v))
+    }
+
+    pub fn words(&mut self, vals: &mut [u16]) -> Result<(), AgcStateError> {
+        for val in vals.iter_mut() {
+            *val = self.u16()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the state a peripheral saved through `StateWriter::periph`.
+    pub fn periph(&mut self) -> Result<&'b [u8], AgcStateError> {
+        let len = self.u16()? as usize;
+        self.bytes(len)
+    }
+
+    pub fn unprog_seq(&mut self) -> Result<AgcUnprogSeq, AgcStateError> {
+        let tag = self.u8()?;
+        let addr = self.u16()? as usize;
+        let seq = match tag {
+            0 => AgcUnprogSeq::PINC(addr),
+            1 => AgcUnprogSeq::PCDU(addr),
+            2 => AgcUnprogSeq::MINC(addr),
+            3 => AgcUnprogSeq::MCDU(addr),
+            4 => AgcUnprogSeq::DINC(addr),
+            5 => AgcUnprogSeq::SHINC(addr),
+            6 => AgcUnprogSeq::SHANC(addr),
+            7 => AgcUnprogSeq::INOTRD,
+            8 => AgcUnprogSeq::INOTLD,
+            9 => AgcUnprogSeq::FETCH,
+            10 => AgcUnprogSeq::STORE,
+            11 => AgcUnprogSeq::GOJ,
+            12 => AgcUnprogSeq::TCSAJ,
+            13 => AgcUnprogSeq::RUPT,
+            _ => return Err(AgcStateError::InvalidData),
+        };
+        Ok(seq)
+    }
+
+    pub fn restart(&mut self) -> Result<Option<AgcRestart>, AgcStateError> {
+        let cause = match self.u8()? {
+            0 => return Ok(None),
+            1 => AgcRestartCause::NightWatchman,
+            2 => AgcRestartCause::TcTrap,
+            3 => AgcRestartCause::RuptLock,
+            4 => AgcRestartCause::Parity,
+            5 => AgcRestartCause::Forced,
+            _ => return Err(AgcStateError::InvalidData),
+        };
+        Ok(Some(AgcRestart {
+            cause,
+            total_cycles: self.u64()? as usize,
+            z: self.u16()?,
+        }))
+    }
+}
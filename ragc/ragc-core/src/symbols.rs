@@ -0,0 +1,69 @@
+use core::fmt;
+
+use crate::mem::AgcAddr;
+
+/// Names for memory locations, such as the symbol table of a yaYUL
+/// listing.
+pub trait AgcSymbols {
+    /// Closest symbol at or below `addr` in the same bank, with the number
+    /// of words from the symbol to `addr`.
+    fn symbol_at(&self, addr: AgcAddr) -> Option<(&str, u16)>;
+
+    /// Location of a symbol.
+    fn address_of(&self, name: &str) -> Option<AgcAddr>;
+}
+
+/// Shows an address the way a yaYUL listing refers to it, `CHECKMM` or
+/// `CHECKMM +3`, when a symbol is known for it, and as `AgcAddr` does
+/// otherwise.
+pub struct AgcSymbolic<'s> {
+    addr: AgcAddr,
+    symbols: Option<&'s dyn AgcSymbols>,
+}
+
+impl<'s> AgcSymbolic<'s> {
+    pub fn new(addr: AgcAddr, symbols: Option<&'s dyn AgcSymbols>) -> AgcSymbolic<'s> {
+        AgcSymbolic { addr, symbols }
+    }
+}
+
+impl<'s> fmt::Display for AgcSymbolic<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.symbols.and_then(|x| x.symbol_at(self.addr)) {
+            Some((name, 0)) => write!(f, "{}", name),
+            Some((name, offset)) => write!(f, "{} +{:o}", name, offset),
+            None => write!(f, "{}", self.addr),
+        }
+    }
+}
+
+/// Resolves an address typed by a user: either in the syntax `AgcAddr`
+/// parses, or a symbol with an optional octal offset such as `REFSMMAT+4`.
+/// Offsets stay inside the symbol's bank.
+pub fn parse_symbolic(s: &str, symbols: Option<&dyn AgcSymbols>) -> Option<AgcAddr> {
+    if let Ok(x) = s.parse::<AgcAddr>() {
+        return Some(x);
+    }
+
+    let (name, offset) = match s.split_once('+') {
+        Some((name, offset)) => (name.trim(), u16::from_str_radix(offset.trim(), 8).ok()?),
+        None => (s.trim(), 0),
+    };
+    match symbols?.address_of(name)? {
+        AgcAddr::Erasable { bank, offset: x } if in_bank(x, offset, 0o377) => {
+            Some(AgcAddr::Erasable {
+                bank,
+                offset: x + offset,
+            })
+        }
+        AgcAddr::Fixed { bank, offset: x } if in_bank(x, offset, 0o1777) => Some(AgcAddr::Fixed {
+            bank,
+            offset: x + offset,
+        }),
+        _ => None,
+    }
+}
+
+fn in_bank(x: u16, offset: u16, last: u16) -> bool {
+    matches!(x.checked_add(offset), Some(x) if x <= last)
+}
@@ -0,0 +1,31 @@
+// Each test binary uses its own subset of these helpers.
+#![allow(dead_code)]
+
+use heapless::spsc::{Producer, Queue};
+
+use ragc_asm::AgcRope;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::mods::AgcNullPeriph;
+use ragc_core::mem::MemoryMap;
+
+/// Powers up a CPU on `rope`, with no peripherals and no interrupts
+/// pending, and hands it to `f`.
+pub fn with_cpu<R, F: FnOnce(&mut AgcCpu) -> R>(rope: &AgcRope, f: F) -> R {
+    with_rupts(rope, |cpu, _| f(cpu))
+}
+
+/// Like `with_cpu`, but also hands `f` the queue interrupt requests are
+/// sent on.
+pub fn with_rupts<R, F>(rope: &AgcRope, f: F) -> R
+where
+    F: FnOnce(&mut AgcCpu, &mut Producer<u8, 8>) -> R,
+{
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (mut rupt_tx, rupt_rx) = queue.split();
+    let (mut downrupt, mut dsky) = (AgcNullPeriph, AgcNullPeriph);
+    let mm = MemoryMap::new(rope, &mut downrupt, &mut dsky, rupt_rx);
+    let mut cpu = AgcCpu::new(mm);
+    cpu.reset();
+    cpu.rupt = 0;
+    f(&mut cpu, &mut rupt_tx)
+}
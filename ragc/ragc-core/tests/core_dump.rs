@@ -0,0 +1,94 @@
+use heapless::spsc::Queue;
+
+use ragc_core::consts::cpu::*;
+use ragc_core::consts::io::*;
+use ragc_core::core_dump::{AgcCoreDump, AgcCoreDumpError, CORE_DUMP_CHANNELS};
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::{AgcAddr, MemoryMap};
+
+fn erasable(bank: u8, offset: u16) -> AgcAddr {
+    AgcAddr::Erasable { bank, offset }
+}
+
+#[test]
+fn core_survives_a_power_cycle() {
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mut cpu = AgcCpu::new(MemoryMap::new_blank(rupt_rx));
+
+    cpu.poke(erasable(0, REG_A as u16), 0o12345);
+    cpu.poke(erasable(0, 0o10), 0o00123);
+    cpu.poke(erasable(0, 0o20), 0o00003);
+    cpu.poke(erasable(0, 0o61), 0o76543);
+    cpu.poke(erasable(3, 0o1), 0o11111);
+    cpu.poke(erasable(7, 0o377), 0o77777);
+    cpu.write_io(CHANNEL_CHAN12, 0o00400);
+    cpu.write_io(CHANNEL_CHAN13, 0o40000);
+    let cyr = cpu.peek(erasable(0, 0o20));
+
+    let text = cpu.save_core().to_string();
+
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mut cpu = AgcCpu::new(MemoryMap::new_blank(rupt_rx));
+    cpu.load_core(&AgcCoreDump::parse(&text).unwrap());
+
+    // Central registers are not core.
+    assert_eq!(cpu.peek(erasable(0, REG_A as u16)), 0);
+    assert_eq!(cpu.peek(erasable(0, 0o10)), 0o00123);
+    // Editing registers come back without being edited again.
+    assert_eq!(cpu.peek(erasable(0, 0o20)), cyr);
+    assert_eq!(cpu.peek(erasable(0, 0o61)), 0o76543);
+    assert_eq!(cpu.peek(erasable(3, 0o1)), 0o11111);
+    assert_eq!(cpu.peek(erasable(7, 0o377)), 0o77777);
+    assert_eq!(cpu.peek_io(CHANNEL_CHAN12), 0o00400);
+    assert_eq!(cpu.peek_io(CHANNEL_CHAN13) & 0o40000, 0o40000);
+}
+
+#[test]
+fn yaagc_cpu_state_is_skipped() {
+    let mut text = "000000\n".repeat(CORE_DUMP_CHANNELS + 8 * 0o400 - 1);
+    text += "012345\n";
+    text += "12345 1 1 0 0 0\n";
+
+    let dump = AgcCoreDump::parse(&text).unwrap();
+    assert_eq!(dump.erasable[7][0o377], 0o12345);
+}
+
+#[test]
+fn dumps_use_the_yaagc_layout() {
+    let mut dump = AgcCoreDump::new();
+    dump.channels[0o12] = 0o00400;
+    dump.erasable[0][0o10] = 0o00123;
+    dump.erasable[7][0o377] = 0o77777;
+    let text = dump.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+
+    // One word per line, channels then erasable, then yaAGC's six CPU
+    // state fields as they are after a reset.
+    let words = CORE_DUMP_CHANNELS + 8 * 0o400;
+    assert_eq!(lines.len(), words + 1);
+    assert!(lines[..words].iter().all(|x| x.len() == 6));
+    let word = |idx: usize| u16::from_str_radix(lines[idx], 8).unwrap();
+    assert_eq!(word(0o12), 0o00400);
+    assert_eq!(word(CORE_DUMP_CHANNELS + 0o10), 0o00123);
+    assert_eq!(word(words - 1), 0o77777);
+    assert_eq!(lines[words], "0 0 0 0 0 0");
+    assert!(text.ends_with('\n'));
+
+    let parsed = AgcCoreDump::parse(&text).unwrap();
+    assert_eq!(parsed.channels[..], dump.channels[..]);
+    assert_eq!(parsed.erasable, dump.erasable);
+}
+
+#[test]
+fn invalid_dumps_are_reported() {
+    assert_eq!(
+        AgcCoreDump::parse("000000\n000001\n").err(),
+        Some(AgcCoreDumpError::Truncated(2))
+    );
+    assert_eq!(
+        AgcCoreDump::parse("000000\n000008\n").err(),
+        Some(AgcCoreDumpError::InvalidWord { line: 2 })
+    );
+}
@@ -0,0 +1,130 @@
+//! Counter tests. The timers are driven by a short assembled program that
+//! keeps interrupts inhibited, so the interrupts they request stay latched
+//! in `rupt`.
+
+mod common;
+
+use ragc_asm::assemble;
+use ragc_core::consts::cpu::*;
+use ragc_core::consts::timer::*;
+use ragc_core::counters::{dinc, mcdu, minc, pcdu, pinc, AgcDincPulse};
+use ragc_core::cpu::{AgcCpu, AgcOverflow};
+use ragc_core::mem::AgcAddr;
+
+const SOURCE: &str = "
+        SETLOC  4000
+START   INHINT
+LOOP    CA      ZERO
+        TCF     LOOP
+";
+
+// A little more than the 10 ms period of TIME1, TIME3, TIME4 and TIME5.
+const PERIOD_CYCLES: usize = 900;
+
+fn timer_rupts(cpu: &AgcCpu) -> u16 {
+    let mask = [RUPT_TIME3, RUPT_TIME4, RUPT_TIME5, RUPT_TIME6]
+        .iter()
+        .fold(0, |acc, x| acc | 1 << x);
+    cpu.rupt & mask
+}
+
+/// Powers up a CPU on the program and hands it to `f`.
+fn with_cpu<F: FnOnce(&mut AgcCpu)>(f: F) {
+    let asm = assemble(SOURCE).unwrap();
+    common::with_cpu(asm.rope(), f);
+}
+
+fn counter(addr: usize) -> AgcAddr {
+    AgcAddr::Erasable {
+        bank: 0,
+        offset: addr as u16,
+    }
+}
+
+fn run_for(cpu: &mut AgcCpu, cycles: usize) {
+    let end = cpu.total_cycles + cycles;
+    while cpu.total_cycles < end {
+        cpu.step().unwrap();
+    }
+}
+
+#[test]
+fn pinc_and_minc_overflow_at_full_scale() {
+    assert!(matches!(pinc(0o00005), (0o00006, AgcOverflow::None)));
+    assert!(matches!(pinc(0o37777), (0o00000, AgcOverflow::Positive)));
+    assert!(matches!(pinc(0o77777), (0o00001, AgcOverflow::None)));
+    assert!(matches!(minc(0o00005), (0o00004, AgcOverflow::None)));
+    assert!(matches!(minc(0o40000), (0o77777, AgcOverflow::Negative)));
+    assert!(matches!(minc(0o00000), (0o77776, AgcOverflow::None)));
+}
+
+#[test]
+fn cdu_counters_wrap_in_twos_complement() {
+    assert!(matches!(pcdu(0o37777), (0o40000, AgcOverflow::None)));
+    assert!(matches!(pcdu(0o77777), (0o00000, AgcOverflow::None)));
+    assert!(matches!(mcdu(0o00000), (0o77777, AgcOverflow::None)));
+    assert!(matches!(mcdu(0o40000), (0o37777, AgcOverflow::None)));
+}
+
+#[test]
+fn dinc_steps_towards_zero() {
+    assert_eq!(dinc(0o00002), (0o00001, AgcDincPulse::POUT));
+    assert_eq!(dinc(0o77775), (0o77776, AgcDincPulse::MOUT));
+    assert_eq!(dinc(0o00000), (0o00000, AgcDincPulse::ZOUT));
+    assert_eq!(dinc(0o77777), (0o77777, AgcDincPulse::ZOUT));
+}
+
+#[test]
+fn time1_overflow_increments_time2() {
+    with_cpu(|cpu| {
+        cpu.poke(counter(MM_TIME2), 0o00007);
+        cpu.poke(counter(MM_TIME1), 0o37777);
+        run_for(cpu, PERIOD_CYCLES);
+        assert_eq!(cpu.peek(counter(MM_TIME1)), 0o00000);
+        assert_eq!(cpu.peek(counter(MM_TIME2)), 0o00010);
+
+        // TIME1 itself does not interrupt.
+        assert_eq!(timer_rupts(cpu), 0);
+    });
+}
+
+#[test]
+fn timer_overflows_request_their_rupts() {
+    for &(addr, rupt) in [
+        (MM_TIME3, RUPT_TIME3),
+        (MM_TIME4, RUPT_TIME4),
+        (MM_TIME5, RUPT_TIME5),
+    ]
+    .iter()
+    {
+        with_cpu(|cpu| {
+            cpu.poke(counter(addr), 0o37776);
+            run_for(cpu, PERIOD_CYCLES);
+            assert_eq!(cpu.peek(counter(addr)), 0o37777, "{:o}", addr);
+            assert_eq!(timer_rupts(cpu), 0, "{:o}", addr);
+
+            run_for(cpu, PERIOD_CYCLES);
+            assert_eq!(cpu.peek(counter(addr)), 0o00000, "{:o}", addr);
+            assert_eq!(timer_rupts(cpu), 1 << rupt, "{:o}", addr);
+        });
+    }
+}
+
+#[test]
+fn time6_interrupts_once_it_counts_down() {
+    with_cpu(|cpu| {
+        // TIME6 does not count until enabled through channel 13.
+        cpu.poke(counter(MM_TIME6), 0o00003);
+        run_for(cpu, PERIOD_CYCLES);
+        assert_eq!(cpu.peek(counter(MM_TIME6)), 0o00003);
+
+        cpu.write_io(0o13, 0o40000);
+        while timer_rupts(cpu) == 0 {
+            assert!(cpu.total_cycles < 10 * PERIOD_CYCLES);
+            cpu.step().unwrap();
+        }
+        assert_eq!(timer_rupts(cpu), 1 << RUPT_TIME6);
+        assert_eq!(cpu.peek(counter(MM_TIME6)), 0o00000);
+        assert_eq!(cpu.read_io(0o13) & 0o40000, 0);
+    });
+}
@@ -0,0 +1,199 @@
+//! Debugger tests. Each case runs a short assembled program under an
+//! `AgcDebugger` and checks where and why it stops.
+
+mod common;
+
+use ragc_asm::{assemble, AgcAssembly};
+use ragc_core::cpu::AgcCpu;
+use ragc_core::debugger::{
+    AgcDebugError, AgcDebugger, AgcStopReason, AgcWatchKind, AgcWatchTarget, AgcWatchpoint,
+};
+use ragc_core::mem::AgcAddr;
+use ragc_core::observer::AgcMemAccess;
+
+const SOURCE: &str = "
+        SETLOC  100
+X       ERASE
+Y       ERASE
+Z       ERASE
+        SETLOC  4000
+START   INHINT
+        CA      ONE
+        TS      X
+CALL    TC      SUB
+AFTER   CA      X
+        AD      Z
+        TS      Y
+LOOP    CA      ONE
+        TCF     LOOP
+SUB     INCR    X
+        INCR    X
+        RETURN
+ONE     DEC     1
+";
+
+const LIMIT: usize = 10000;
+
+/// Physical address of a label in fixed-fixed or unswitched erasable
+/// memory.
+fn label(asm: &AgcAssembly, name: &str) -> AgcAddr {
+    AgcAddr::from_cpu(asm.addr(name).unwrap().cpu_addr(), 0, 0)
+}
+
+/// Powers up a CPU on the assembled program and hands it to `f`.
+fn with_cpu<F: FnOnce(&mut AgcCpu, &AgcAssembly)>(f: F) {
+    let asm = assemble(SOURCE).unwrap();
+    common::with_cpu(asm.rope(), |cpu| f(cpu, &asm));
+}
+
+fn watch(asm: &AgcAssembly, name: &str, kind: AgcWatchKind) -> AgcWatchpoint {
+    AgcWatchpoint {
+        target: AgcWatchTarget::Memory(label(asm, name)),
+        kind,
+    }
+}
+
+#[test]
+fn breakpoints_hit_until_removed() {
+    with_cpu(|cpu, asm| {
+        let mut dbg = AgcDebugger::new();
+        let sub = label(asm, "SUB");
+        let lp = label(asm, "LOOP");
+        dbg.add_breakpoint(sub).unwrap();
+        dbg.add_breakpoint(lp).unwrap();
+        dbg.add_breakpoint(lp).unwrap();
+        assert_eq!(dbg.breakpoints(), &[sub, lp]);
+
+        assert_eq!(dbg.run(cpu, LIMIT), AgcStopReason::Breakpoint(sub));
+        assert_eq!(cpu.resolve(cpu.registers().z), sub);
+
+        // Continuing steps off the breakpoint before checking again, so a
+        // loop on itself stops once per pass.
+        assert_eq!(dbg.run(cpu, LIMIT), AgcStopReason::Breakpoint(lp));
+        let cycles = cpu.total_cycles;
+        assert_eq!(dbg.run(cpu, LIMIT), AgcStopReason::Breakpoint(lp));
+        assert!(cpu.total_cycles > cycles);
+
+        assert!(dbg.remove_breakpoint(lp));
+        assert!(!dbg.remove_breakpoint(lp));
+        assert_eq!(dbg.run(cpu, LIMIT), AgcStopReason::CycleLimit);
+        assert!(cpu.total_cycles >= LIMIT);
+    });
+}
+
+#[test]
+fn breakpoints_are_bounded() {
+    let mut dbg = AgcDebugger::new();
+    for offset in 0..32 {
+        dbg.add_breakpoint(AgcAddr::Fixed { bank: 2, offset })
+            .unwrap();
+    }
+    assert_eq!(
+        dbg.add_breakpoint(AgcAddr::Fixed {
+            bank: 2,
+            offset: 32
+        }),
+        Err(AgcDebugError::TooManyBreakpoints)
+    );
+}
+
+#[test]
+fn write_watchpoints_report_every_store() {
+    with_cpu(|cpu, asm| {
+        let mut dbg = AgcDebugger::new();
+        let x = label(asm, "X");
+        let wp = watch(asm, "X", AgcWatchKind::Write);
+        dbg.add_watchpoint(wp).unwrap();
+
+        // TS X, then both INCRs of the subroutine.
+        for val in 1..=3 {
+            assert_eq!(
+                dbg.run(cpu, LIMIT),
+                AgcStopReason::Watchpoint(wp, AgcMemAccess::Write(x, val))
+            );
+        }
+
+        assert!(dbg.remove_watchpoint(wp.target));
+        assert!(!dbg.remove_watchpoint(wp.target));
+        assert_eq!(dbg.run(cpu, LIMIT), AgcStopReason::CycleLimit);
+        assert_eq!(cpu.peek(label(asm, "Y")), 3);
+    });
+}
+
+#[test]
+fn watchpoints_match_the_access_kind() {
+    with_cpu(|cpu, asm| {
+        let mut dbg = AgcDebugger::new();
+        let z = label(asm, "Z");
+        dbg.add_watchpoint(watch(asm, "Z", AgcWatchKind::Write))
+            .unwrap();
+        assert_eq!(dbg.run(cpu, LIMIT), AgcStopReason::CycleLimit);
+
+        let wp = watch(asm, "Z", AgcWatchKind::Read);
+        dbg.add_watchpoint(wp).unwrap();
+        assert_eq!(dbg.watchpoints().len(), 2);
+        cpu.reset();
+        cpu.rupt = 0;
+        assert_eq!(
+            dbg.run(cpu, cpu.total_cycles + LIMIT),
+            AgcStopReason::Watchpoint(wp, AgcMemAccess::Read(z, 0))
+        );
+        // The instruction that made the access has completed.
+        assert_eq!(cpu.registers().z, asm.addr("AFTER").unwrap().cpu_addr() + 2);
+    });
+}
+
+#[test]
+fn fixed_memory_cannot_be_watched() {
+    with_cpu(|_cpu, asm| {
+        let mut dbg = AgcDebugger::new();
+        assert_eq!(
+            dbg.add_watchpoint(watch(asm, "ONE", AgcWatchKind::Read)),
+            Err(AgcDebugError::FixedWatchpoint)
+        );
+    });
+}
+
+#[test]
+fn step_runs_one_instruction() {
+    with_cpu(|cpu, asm| {
+        let mut dbg = AgcDebugger::new();
+        let call = label(asm, "CALL");
+        let sub = label(asm, "SUB");
+        assert_eq!(dbg.run_until(cpu, LIMIT, call), AgcStopReason::Step);
+        assert_eq!(cpu.resolve(cpu.registers().z), call);
+        assert_eq!(dbg.return_address(cpu), Some(label(asm, "AFTER")));
+
+        assert_eq!(dbg.step(cpu), AgcStopReason::Step);
+        assert_eq!(cpu.resolve(cpu.registers().z), sub);
+        assert_eq!(cpu.peek(label(asm, "X")), 1);
+        assert_eq!(dbg.return_address(cpu), None);
+
+        assert_eq!(dbg.step(cpu), AgcStopReason::Step);
+        assert_eq!(cpu.registers().z, asm.addr("SUB").unwrap().cpu_addr() + 1);
+        assert_eq!(cpu.peek(label(asm, "X")), 2);
+    });
+}
+
+#[test]
+fn step_over_runs_the_whole_subroutine() {
+    with_cpu(|cpu, asm| {
+        let mut dbg = AgcDebugger::new();
+        let call = label(asm, "CALL");
+        assert_eq!(dbg.run_until(cpu, LIMIT, call), AgcStopReason::Step);
+        assert_eq!(dbg.step_over(cpu, LIMIT), AgcStopReason::Step);
+        assert_eq!(cpu.resolve(cpu.registers().z), label(asm, "AFTER"));
+        assert_eq!(cpu.peek(label(asm, "X")), 3);
+
+        // Breakpoints inside the subroutine still stop it.
+        cpu.reset();
+        cpu.rupt = 0;
+        let limit = cpu.total_cycles + LIMIT;
+        assert_eq!(dbg.run_until(cpu, limit, call), AgcStopReason::Step);
+        dbg.add_breakpoint(label(asm, "SUB")).unwrap();
+        assert_eq!(
+            dbg.step_over(cpu, limit),
+            AgcStopReason::Breakpoint(label(asm, "SUB"))
+        );
+    });
+}
@@ -0,0 +1,63 @@
+use heapless::spsc::Queue;
+
+use ragc_core::cpu::AgcCpu;
+use ragc_core::decoder::decode;
+use ragc_core::instructions::{AgcInst, AgcMnem};
+use ragc_core::mem::MemoryMap;
+use ragc_core::observer::{AgcStepInfo, CpuObserver};
+
+const START: u16 = 0o100;
+
+/// Remembers the last instruction the CPU executed.
+struct LastInst(Option<AgcInst>);
+
+impl CpuObserver for LastInst {
+    fn programmed(&mut self, inst: &AgcInst, _info: &AgcStepInfo) {
+        self.0 = Some(*inst);
+    }
+}
+
+/// Runs `word` from erasable memory on a fresh CPU, after an EXTEND when
+/// `extended` is set, and returns the instruction it executed as.
+fn run_word(word: u16, extended: bool) -> AgcInst {
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mut cpu = AgcCpu::new(MemoryMap::new_blank(rupt_rx));
+    cpu.rupt = 0;
+
+    let mut last = LastInst(None);
+    if extended {
+        cpu.write(START as usize, 0o00006);
+        cpu.write(START as usize + 1, word);
+        cpu.update_pc(START);
+        cpu.step_observed(&mut last).unwrap();
+        assert!(cpu.ec_flag);
+    } else {
+        cpu.write(START as usize, word);
+        cpu.update_pc(START);
+    }
+    cpu.step_observed(&mut last).unwrap();
+    last.0.unwrap()
+}
+
+#[test]
+fn every_word_decodes() {
+    for inst_data in 0..=0xFFFF {
+        let i = decode(0o4000, inst_data);
+        assert_ne!(i.mnem, AgcMnem::INVALID, "{:06o}", inst_data);
+    }
+}
+
+#[test]
+fn every_word_executes_as_it_decodes() {
+    for word in 0..0o100000 {
+        let i = run_word(word, false);
+        assert_eq!(i.mnem, decode(START, word).mnem, "{:05o}", word);
+        assert_eq!(i.inst_data, word);
+
+        let i = run_word(word, true);
+        let expected = decode(START + 1, word | 0x8000);
+        assert_eq!(i.mnem, expected.mnem, "EXTEND {:05o}", word);
+        assert_eq!(i.inst_data, expected.inst_data);
+    }
+}
@@ -0,0 +1,59 @@
+use ragc_asm::assemble;
+use ragc_core::disasm::AgcBankListing;
+
+fn listing(source: &str, count: usize) -> Vec<String> {
+    let asm = assemble(source).unwrap();
+    AgcBankListing::new(asm.rope(), 2)
+        .take(count)
+        .map(|x| x.to_string())
+        .collect()
+}
+
+#[test]
+fn extended_index_keeps_the_next_word_extended() {
+    let source = "
+        SETLOC  100
+X       ERASE
+Y       ERASE
+        SETLOC  4000
+        EXTEND
+        INDEX   X
+        MP      Y
+        CA      Y
+        EXTEND
+        DV      Y
+        CA      Y
+";
+    assert_eq!(
+        listing(source, 7),
+        vec![
+            "4000     00006    EXTEND",
+            "4001     50100    INDEX  0100",
+            "4002     70101    MP     0101",
+            "4003     30101    CA     0101",
+            "4004     00006    EXTEND",
+            "4005     10101    DV     0101",
+            "4006     30101    CA     0101",
+        ]
+    );
+}
+
+#[test]
+fn edrupt_operand_drops_the_extrabits() {
+    let source = "
+        SETLOC  4000
+        EXTEND
+        EDRUPT  0
+        EXTEND
+        EDRUPT  17
+";
+    assert_eq!(
+        listing(source, 4),
+        vec![
+            "4000     00006    EXTEND",
+            "4001     07000    EDRUPT 0000",
+            "4002     00006    EXTEND",
+            "4003     07017    EDRUPT 0017",
+        ]
+    );
+}
@@ -0,0 +1,44 @@
+use heapless::spsc::Queue;
+
+use ragc_core::consts::cpu::*;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::MemoryMap;
+
+const START: u16 = 0o100;
+const SEVEN: u16 = 0o110;
+
+#[test]
+fn edrupt_saves_zrupt_and_brupt() {
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mut cpu = AgcCpu::new(MemoryMap::new_blank(rupt_rx));
+    cpu.rupt = 0;
+    cpu.gint = true;
+
+    // EXTEND, EDRUPT 0, CA SEVEN, run from erasable.
+    let program = [0o00006, 0o07000, 0o30000 | SEVEN];
+    for (idx, word) in program.iter().enumerate() {
+        cpu.write(START as usize + idx, *word);
+    }
+    cpu.write(SEVEN as usize, 7);
+    cpu.update_pc(START);
+    cpu.step().unwrap();
+
+    // EDRUPT lands at location 0, which is A. A RESUME there returns to
+    // the interrupted program.
+    cpu.write(REG_A, 0o50017);
+    cpu.step().unwrap();
+    assert_eq!(cpu.read(REG_PC), 0);
+    assert!(cpu.is_irupt);
+    assert!(!cpu.gint);
+    assert_eq!(cpu.read(REG_PC_SHADOW), START + 3);
+    assert_eq!(cpu.read(REG_IR), program[2]);
+
+    cpu.step().unwrap();
+    assert!(!cpu.is_irupt);
+    assert!(cpu.gint);
+    assert_eq!(cpu.read(REG_PC), START + 2);
+
+    cpu.step().unwrap();
+    assert_eq!(cpu.read(REG_A), 7);
+}
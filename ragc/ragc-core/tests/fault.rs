@@ -0,0 +1,219 @@
+//! Fault injection tests. A short assembled program reads `X` in a loop
+//! while faults are armed against it.
+
+mod common;
+
+use ragc_asm::assemble;
+use ragc_core::consts::cpu::RUPT_HANDRUPT;
+use ragc_core::consts::io::{
+    CHAN77_COUNTER_FAIL, CHAN77_ERASABLE_PARITY_FAIL, CHAN77_PARITY_FAIL, CHANNEL_CHAN30,
+    CHANNEL_CHAN77,
+};
+use ragc_core::consts::timer::MM_TIME1;
+use ragc_core::cpu::{AgcCpu, AgcRestartCause};
+use ragc_core::fault::{AgcFault, AgcFaultError, AgcFaultKind, AgcFaultTrigger};
+use ragc_core::mem::AgcAddr;
+
+const SOURCE: &str = "
+        SETLOC  100
+X       ERASE
+        SETLOC  4000
+START   INHINT
+LOOP    CA      X
+        TCF     LOOP
+";
+
+const X: AgcAddr = AgcAddr::Erasable {
+    bank: 0,
+    offset: 0o100,
+};
+
+// Well within the restart monitors' limits for the program above.
+const CYCLES: usize = 10000;
+
+/// Powers up a CPU on the program and hands it to `f`.
+fn with_cpu<F: FnOnce(&mut AgcCpu)>(f: F) {
+    let asm = assemble(SOURCE).unwrap();
+    common::with_cpu(asm.rope(), f);
+}
+
+fn fault(trigger: AgcFaultTrigger, kind: AgcFaultKind) -> AgcFault {
+    AgcFault { trigger, kind }
+}
+
+#[test]
+fn cycle_faults_fire_once_at_their_cycle() {
+    with_cpu(|cpu| {
+        let at = cpu.total_cycles + 1000;
+        cpu.inject_fault(fault(
+            AgcFaultTrigger::Cycle(at),
+            AgcFaultKind::ErasableParity,
+        ))
+        .unwrap();
+
+        while cpu.total_cycles < at {
+            assert_eq!(
+                cpu.restart_count(),
+                0,
+                "fired at cycle {}",
+                cpu.total_cycles
+            );
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.restart_count(), 1);
+        let restart = cpu.last_restart().unwrap();
+        assert_eq!(restart.cause, AgcRestartCause::Parity);
+        assert!(restart.total_cycles >= at && restart.total_cycles < at + 3);
+        assert_eq!(
+            cpu.peek_io(CHANNEL_CHAN77),
+            CHAN77_PARITY_FAIL | CHAN77_ERASABLE_PARITY_FAIL
+        );
+
+        // The GOJ sequence restarts the program at 4000.
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers().z, 0o4000);
+
+        while cpu.total_cycles < at + CYCLES {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.restart_count(), 1);
+    });
+}
+
+#[test]
+fn faults_due_together_all_fire() {
+    with_cpu(|cpu| {
+        let at = cpu.total_cycles + 100;
+        for _ in 0..2 {
+            cpu.inject_fault(fault(AgcFaultTrigger::Cycle(at), AgcFaultKind::ForcedGoj))
+                .unwrap();
+        }
+        cpu.inject_fault(fault(
+            AgcFaultTrigger::Cycle(at + 500),
+            AgcFaultKind::ForcedGoj,
+        ))
+        .unwrap();
+
+        while cpu.total_cycles < at {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.restart_count(), 2);
+        while cpu.total_cycles < at + CYCLES {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.restart_count(), 3);
+        assert_eq!(cpu.last_restart().unwrap().cause, AgcRestartCause::Forced);
+    });
+}
+
+#[test]
+fn address_faults_fire_on_the_first_read() {
+    with_cpu(|cpu| {
+        cpu.inject_fault(fault(
+            AgcFaultTrigger::Address(X),
+            AgcFaultKind::FixedParity,
+        ))
+        .unwrap();
+
+        // INHINT, then the first CA X.
+        cpu.step().unwrap();
+        assert_eq!(cpu.restart_count(), 0);
+        cpu.step().unwrap();
+        assert_eq!(cpu.restart_count(), 1);
+        assert_eq!(cpu.peek_io(CHANNEL_CHAN77), CHAN77_PARITY_FAIL);
+
+        let start = cpu.total_cycles;
+        while cpu.total_cycles < start + CYCLES {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.restart_count(), 1);
+    });
+}
+
+#[test]
+fn stuck_bits_hold_until_cleared() {
+    with_cpu(|cpu| {
+        let at = cpu.total_cycles + 10;
+        let stuck = AgcFaultKind::StuckBits {
+            channel: CHANNEL_CHAN30,
+            mask: 0o00003,
+            value: 0o00001,
+        };
+        cpu.inject_fault(fault(AgcFaultTrigger::Cycle(at), stuck))
+            .unwrap();
+
+        let before = cpu.peek_io(CHANNEL_CHAN30);
+        while cpu.total_cycles < at {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.peek_io(CHANNEL_CHAN30), (before & !0o3) | 0o1);
+
+        cpu.clear_faults();
+        assert_eq!(cpu.peek_io(CHANNEL_CHAN30), before);
+    });
+}
+
+/// Runs the program with `count` increments of TIME1 dropped, and returns
+/// TIME1 and channel 77 afterwards.
+fn drop_time1(count: u16) -> (u16, u16) {
+    let mut res = (0, 0);
+    with_cpu(|cpu| {
+        let kind = AgcFaultKind::DropCounter {
+            counter: MM_TIME1,
+            count,
+        };
+        cpu.inject_fault(fault(AgcFaultTrigger::Cycle(0), kind))
+            .unwrap();
+        while cpu.total_cycles < CYCLES {
+            cpu.step().unwrap();
+        }
+        let time1 = AgcAddr::Erasable {
+            bank: 0,
+            offset: MM_TIME1 as u16,
+        };
+        res = (cpu.peek(time1), cpu.peek_io(CHANNEL_CHAN77));
+    });
+    res
+}
+
+#[test]
+fn dropped_increments_raise_the_counter_alarm() {
+    let (time1, ch77) = drop_time1(0);
+    assert!(time1 > 3);
+    assert_eq!(ch77 & CHAN77_COUNTER_FAIL, 0);
+
+    let (dropped, ch77) = drop_time1(3);
+    assert_eq!(dropped, time1 - 3);
+    assert_eq!(ch77 & CHAN77_COUNTER_FAIL, CHAN77_COUNTER_FAIL);
+}
+
+#[test]
+fn invalid_faults_are_rejected() {
+    with_cpu(|cpu| {
+        let stuck = AgcFaultKind::StuckBits {
+            channel: 0o400,
+            mask: 1,
+            value: 1,
+        };
+        assert_eq!(
+            cpu.inject_fault(fault(AgcFaultTrigger::Cycle(0), stuck)),
+            Err(AgcFaultError::InvalidChannel)
+        );
+        assert_eq!(
+            cpu.inject_fault(fault(
+                AgcFaultTrigger::Cycle(0),
+                AgcFaultKind::SpuriousRupt(RUPT_HANDRUPT + 1)
+            )),
+            Err(AgcFaultError::InvalidRupt)
+        );
+
+        for _ in 0..ragc_core::fault::MAX_FAULTS {
+            cpu.inject_fault(fault(AgcFaultTrigger::Address(X), AgcFaultKind::ForcedGoj))
+                .unwrap();
+        }
+        assert_eq!(
+            cpu.inject_fault(fault(AgcFaultTrigger::Address(X), AgcFaultKind::ForcedGoj)),
+            Err(AgcFaultError::TooManyFaults)
+        );
+    });
+}
@@ -0,0 +1,111 @@
+use heapless::spsc::Queue;
+
+use ragc_asm::assemble;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::inst_cache::AgcInstCache;
+use ragc_core::instructions::{AgcInst, AgcMnem};
+use ragc_core::mem::mods::AgcNullPeriph;
+use ragc_core::mem::{rope_word, MemoryMap};
+
+#[test]
+fn switched_banks_use_their_own_entries() {
+    let source = "
+        SETLOC  4000
+START   CA      B24
+        TS      FB
+        TC      SUBA
+        CA      B25
+        TS      FB
+        TC      SUBB
+DONE    TCF     DONE
+ONE     DEC     1
+TWO     DEC     2
+B24     OCT     50000
+B25     OCT     52000
+        BANK    24
+SUBA    CA      ONE
+        TC      Q
+        BANK    25
+SUBB    CA      TWO
+        TC      Q
+";
+    let asm = assemble(source).unwrap();
+    let suba = asm.addr("SUBA").unwrap();
+    let subb = asm.addr("SUBB").unwrap();
+    assert_eq!(suba.cpu_addr(), subb.cpu_addr());
+
+    let mut cache = Box::new(AgcInstCache::new());
+    {
+        let mut queue: Queue<u8, 8> = Queue::new();
+        let (_rupt_tx, rupt_rx) = queue.split();
+        let (mut downrupt, mut dsky) = (AgcNullPeriph, AgcNullPeriph);
+        let mm = MemoryMap::new(asm.rope(), &mut downrupt, &mut dsky, rupt_rx);
+        let mut cpu = AgcCpu::new(mm);
+        cpu.set_inst_cache(&mut cache);
+        cpu.reset();
+        cpu.rupt = 0;
+
+        let done = asm.addr("DONE").unwrap().cpu_addr();
+        while cpu.registers().z != done || cpu.unprog_pending() {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.registers().a, 2);
+    }
+
+    let word = |bank| rope_word(asm.rope(), bank, 0);
+    assert_eq!(cache.get(suba, word(0o24)).unwrap().inst_data, word(0o24));
+    assert_eq!(cache.get(subb, word(0o25)).unwrap().inst_data, word(0o25));
+    assert!(cache.get(subb, word(0o24)).is_none());
+}
+
+#[test]
+fn mismatched_words_are_decoded_again() {
+    let source = "
+        SETLOC  4000
+START   CA      ONE
+        INDEX   A
+LOAD    CA      TABLE
+        TCF     LOAD
+ONE     DEC     1
+TABLE   DEC     5
+        DEC     6
+";
+    let asm = assemble(source).unwrap();
+    let start = asm.addr("START").unwrap();
+    let ca = asm.addr("LOAD").unwrap();
+    let start_word = rope_word(asm.rope(), 0o2, 0);
+    let ca_word = rope_word(asm.rope(), 0o2, 2);
+
+    // Stale entries, left over from words that no longer match.
+    let mut cache = Box::new(AgcInstCache::new());
+    let mut stale = AgcInst::new();
+    stale.inst_data = start_word + 1;
+    cache.insert(start, stale);
+    stale.inst_data = ca_word + 1;
+    cache.insert(ca, stale);
+    assert!(cache.get(start, start_word).is_none());
+    assert!(cache.get(ca, ca_word).is_none());
+
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let (mut downrupt, mut dsky) = (AgcNullPeriph, AgcNullPeriph);
+    let mm = MemoryMap::new(asm.rope(), &mut downrupt, &mut dsky, rupt_rx);
+    let mut cpu = AgcCpu::new(mm);
+    cpu.set_inst_cache(&mut cache);
+    cpu.reset();
+    cpu.rupt = 0;
+
+    // The INDEXed CA reads TABLE +1, and the same CA without INDEX TABLE.
+    for _ in 0..3 {
+        cpu.step().unwrap();
+    }
+    assert_eq!(cpu.registers().a, 6);
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.registers().a, 5);
+    drop(cpu);
+
+    assert_eq!(cache.get(start, start_word).unwrap().mnem, AgcMnem::CA);
+    assert_eq!(cache.get(ca, ca_word).unwrap().mnem, AgcMnem::CA);
+    assert!(cache.get(ca, ca_word + 1).is_none());
+}
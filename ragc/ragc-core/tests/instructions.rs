@@ -0,0 +1,811 @@
+//! Instruction conformance tests. Each case assembles a short program that
+//! starts at 4000, runs it until it reaches `DONE` and checks the central
+//! registers and erasable memory against the Block II instruction
+//! descriptions.
+
+mod common;
+
+use ragc_asm::{assemble, AgcAssembly};
+use ragc_core::consts::cpu::RUPT_TIME6;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::AgcAddr;
+use ragc_core::observer::AgcRegisters;
+
+/// Erasable variables shared by all programs.
+const VARIABLES: &str = "
+        SETLOC  100
+X       ERASE
+Y       ERASE
+I       ERASE
+PAIR    ERASE   +1
+R0      ERASE
+R1      ERASE
+R2      ERASE
+R3      ERASE
+        SETLOC  4000
+";
+
+const MAX_STEPS: usize = 1000;
+
+/// Resolves a variable name, optionally followed by `+n`.
+fn variable(asm: &AgcAssembly, name: &str) -> AgcAddr {
+    let (name, n) = match name.split_once('+') {
+        Some((name, n)) => (name, n.parse().unwrap()),
+        None => (name, 0),
+    };
+    let addr = asm.addr(name).unwrap();
+    AgcAddr::from_cpu(addr.cpu_addr() + n, 0, 0)
+}
+
+/// Machine state once a program reached `DONE`.
+struct Outcome {
+    asm: AgcAssembly,
+    regs: AgcRegisters,
+    erasable: Vec<u16>,
+}
+
+impl Outcome {
+    /// Word at a label in unswitched erasable memory.
+    fn word(&self, name: &str) -> u16 {
+        self.word_at(name, 0)
+    }
+
+    fn word_at(&self, name: &str, n: usize) -> u16 {
+        let addr = self.asm.addr(name).unwrap();
+        self.erasable[addr.cpu_addr() as usize + n]
+    }
+
+    fn label(&self, name: &str) -> u16 {
+        self.asm.addr(name).unwrap().cpu_addr()
+    }
+}
+
+/// Runs `source` with the variables of `init` set beforehand. Variables are
+/// named by label, `PAIR+1` names the word after `PAIR`.
+fn run(source: &str, init: &[(&str, u16)]) -> Outcome {
+    run_with(source, init, |_| {})
+}
+
+/// Same as `run`, with a hook to prepare the CPU before the first step.
+fn run_with<F: FnOnce(&mut AgcCpu)>(source: &str, init: &[(&str, u16)], setup: F) -> Outcome {
+    let source = format!("{}{}DONE    TCF     DONE\n", VARIABLES, source);
+    let asm = match assemble(&source) {
+        Ok(x) => x,
+        Err(x) => panic!("{}\n{}", x, source),
+    };
+
+    let (regs, erasable) = common::with_cpu(asm.rope(), |cpu| {
+        for &(name, val) in init.iter() {
+            cpu.poke(variable(&asm, name), val);
+        }
+        setup(cpu);
+
+        let done = asm.addr("DONE").unwrap().cpu_addr();
+        let mut steps = 0;
+        while cpu.registers().z != done || cpu.unprog_pending() {
+            assert!(steps < MAX_STEPS, "program did not reach DONE");
+            cpu.step().unwrap();
+            steps += 1;
+        }
+
+        let erasable = (0..0o1400)
+            .map(|x| cpu.peek(AgcAddr::from_cpu(x, 0, 0)))
+            .collect();
+        (cpu.registers(), erasable)
+    });
+    Outcome {
+        regs,
+        erasable,
+        asm,
+    }
+}
+
+#[test]
+fn ad_keeps_overflow_in_a() {
+    let source = "
+        CA      X
+        AD      Y
+        TS      R0
+        TCF     DONE
+        TS      R1
+";
+    // Positive overflow: TS stores the corrected sum, sets A to +1 and
+    // skips.
+    let out = run(source, &[("X", 0o37777), ("Y", 0o00001)]);
+    assert_eq!(out.word("R0"), 0o00000);
+    assert_eq!(out.word("R1"), 0o00001);
+    assert_eq!(out.regs.a, 0o000001);
+
+    // Negative overflow sets A to -1.
+    let out = run(source, &[("X", 0o40000), ("Y", 0o77776)]);
+    assert_eq!(out.word("R0"), 0o77777);
+    assert_eq!(out.word("R1"), 0o77776);
+    assert_eq!(out.regs.a, 0o177776);
+
+    // No overflow, no skip.
+    let out = run(source, &[("X", 0o00005), ("Y", 0o00003), ("R1", 0o55555)]);
+    assert_eq!(out.word("R0"), 0o00010);
+    assert_eq!(out.word("R1"), 0o55555);
+}
+
+#[test]
+fn overflow_is_corrected_when_stored_in_erasable() {
+    let source = "
+        CA      X
+        ADS     Y
+        TS      R0
+        TCF     DONE
+        TS      R1
+";
+    let out = run(source, &[("X", 0o37777), ("Y", 0o00002)]);
+    assert_eq!(out.word("Y"), 0o00001);
+    assert_eq!(out.word("R0"), 0o00001);
+    assert_eq!(out.word("R1"), 0o00001);
+
+    let source = "
+        CA      X
+        AD      Y
+        XCH     I
+";
+    let out = run(source, &[("X", 0o37777), ("Y", 0o00003), ("I", 0o12345)]);
+    assert_eq!(out.word("I"), 0o00002);
+    assert_eq!(out.regs.a, 0o012345);
+}
+
+#[test]
+fn ovsk_skips_without_touching_a() {
+    let source = "
+        CA      X
+        AD      Y
+        OVSK
+        TCF     DONE
+        INCR    R0
+";
+    let out = run(source, &[("X", 0o37777), ("Y", 0o00001)]);
+    assert_eq!(out.word("R0"), 1);
+    assert_eq!(out.regs.a, 0o040000);
+
+    let out = run(source, &[("X", 0o00001), ("Y", 0o00001)]);
+    assert_eq!(out.word("R0"), 0);
+    assert_eq!(out.regs.a, 0o000002);
+}
+
+#[test]
+fn additions_with_zeros_follow_ones_complement() {
+    let source = "
+        CA      X
+        AD      Y
+        TS      R0
+";
+    let cases = [
+        (0o00000, 0o00000, 0o00000),
+        (0o00000, 0o77777, 0o77777),
+        (0o77777, 0o77777, 0o77777),
+        (0o00001, 0o77776, 0o77777),
+        (0o00007, 0o77772, 0o00002),
+    ];
+    for &(x, y, res) in cases.iter() {
+        let out = run(source, &[("X", x), ("Y", y)]);
+        assert_eq!(out.word("R0"), res, "{:05o} + {:05o}", x, y);
+    }
+}
+
+#[test]
+fn ca_and_cs_sign_extend_into_a() {
+    let out = run("        CA      X\n", &[("X", 0o77776)]);
+    assert_eq!(out.regs.a, 0o177776);
+
+    let out = run("        CS      X\n", &[("X", 0o77776)]);
+    assert_eq!(out.regs.a, 0o000001);
+
+    let out = run("        CS      X\n", &[("X", 0o00000)]);
+    assert_eq!(out.regs.a, 0o177777);
+}
+
+#[test]
+fn su_subtracts_in_ones_complement() {
+    let source = "
+        CA      X
+        EXTEND
+        SU      Y
+        TS      R0
+";
+    let cases = [
+        (0o00005, 0o00003, 0o00002),
+        (0o00003, 0o00005, 0o77775),
+        (0o00005, 0o00005, 0o77777),
+        (0o77772, 0o77775, 0o77774),
+    ];
+    for &(x, y, res) in cases.iter() {
+        let out = run(source, &[("X", x), ("Y", y)]);
+        assert_eq!(out.word("R0"), res, "{:05o} - {:05o}", x, y);
+    }
+}
+
+#[test]
+fn msu_subtracts_modulo_two_and_converts() {
+    let source = "
+        CA      X
+        EXTEND
+        MSU     Y
+        TS      R0
+";
+    let cases = [
+        (0o00005, 0o00003, 0o00002),
+        (0o00003, 0o00005, 0o77775),
+        (0o00005, 0o00005, 0o00000),
+        (0o00001, 0o77777, 0o00002),
+        (0o77777, 0o00001, 0o77775),
+    ];
+    for &(x, y, res) in cases.iter() {
+        let out = run(source, &[("X", x), ("Y", y)]);
+        assert_eq!(out.word("R0"), res, "{:05o} - {:05o}", x, y);
+    }
+}
+
+#[test]
+fn incr_aug_and_dim() {
+    let cases: [(&str, u16, u16); 15] = [
+        ("        INCR    X\n", 0o00005, 0o00006),
+        ("        INCR    X\n", 0o77777, 0o00001),
+        ("        INCR    X\n", 0o77776, 0o77777),
+        ("        INCR    X\n", 0o37777, 0o00000),
+        ("        EXTEND\n        AUG     X\n", 0o00005, 0o00006),
+        ("        EXTEND\n        AUG     X\n", 0o77772, 0o77771),
+        ("        EXTEND\n        AUG     X\n", 0o00000, 0o00001),
+        ("        EXTEND\n        AUG     X\n", 0o77777, 0o77776),
+        ("        EXTEND\n        DIM     X\n", 0o00005, 0o00004),
+        ("        EXTEND\n        DIM     X\n", 0o77772, 0o77773),
+        ("        EXTEND\n        DIM     X\n", 0o00000, 0o00000),
+        ("        EXTEND\n        DIM     X\n", 0o77777, 0o77777),
+        // The adder turns both +1 - 1 and -1 + 1 into -0.
+        ("        EXTEND\n        DIM     X\n", 0o00001, 0o77777),
+        ("        EXTEND\n        DIM     X\n", 0o77776, 0o77777),
+        ("        INCR    X\n", 0o00000, 0o00001),
+    ];
+    for &(source, x, res) in cases.iter() {
+        let out = run(source, &[("X", x)]);
+        assert_eq!(out.word("X"), res, "{}X = {:05o}", source, x);
+    }
+}
+
+#[test]
+fn ccs_branches_four_ways() {
+    let source = "
+        CCS     X
+        TCF     B1
+        TCF     B2
+        TCF     B3
+        TS      R3
+        TCF     DONE
+B1      TS      R0
+        TCF     DONE
+B2      TS      R1
+        TCF     DONE
+B3      TS      R2
+        TCF     DONE
+";
+    let unset = 0o55555;
+    let init = |x| {
+        [
+            ("X", x),
+            ("R0", unset),
+            ("R1", unset),
+            ("R2", unset),
+            ("R3", unset),
+        ]
+    };
+    // Value of X, branch taken and the diminished absolute value in A.
+    let cases = [
+        (0o00005, "R0", 0o00004),
+        (0o00001, "R0", 0o00000),
+        (0o00000, "R1", 0o00000),
+        (0o77772, "R2", 0o00004),
+        (0o77776, "R2", 0o00000),
+        (0o77777, "R3", 0o00000),
+    ];
+    for &(x, branch, dabs) in cases.iter() {
+        let out = run(source, &init(x));
+        for &r in ["R0", "R1", "R2", "R3"].iter() {
+            let expected = if r == branch { dabs } else { unset };
+            assert_eq!(out.word(r), expected, "CCS {:05o}: {}", x, r);
+        }
+    }
+}
+
+#[test]
+fn mp_gives_both_words_the_product_sign() {
+    let source = "
+        CA      X
+        EXTEND
+        MP      Y
+        DXCH    PAIR
+";
+    let cases = [
+        (0o00003, 0o77775, [0o77777, 0o77771]),
+        (0o20000, 0o20000, [0o10000, 0o00000]),
+        (0o57777, 0o57777, [0o10000, 0o00000]),
+        (0o20000, 0o57777, [0o67777, 0o77777]),
+        (0o37777, 0o37777, [0o37776, 0o00001]),
+        (0o00000, 0o00005, [0o00000, 0o00000]),
+        (0o00005, 0o00000, [0o00000, 0o00000]),
+    ];
+    for &(x, y, res) in cases.iter() {
+        let out = run(source, &[("X", x), ("Y", y)]);
+        let product = [out.word_at("PAIR", 0), out.word_at("PAIR", 1)];
+        assert_eq!(product, res, "{:05o} * {:05o}", x, y);
+    }
+
+    let source = "
+        CA      X
+        EXTEND
+        SQUARE
+        DXCH    PAIR
+";
+    let out = run(source, &[("X", 0o57777)]);
+    assert_eq!(
+        [out.word_at("PAIR", 0), out.word_at("PAIR", 1)],
+        [0o10000, 0]
+    );
+}
+
+#[test]
+fn dv_quotient_and_remainder_signs() {
+    let source = "
+        EXTEND
+        DCA     PAIR
+        EXTEND
+        DV      Y
+        TS      R0
+        LXCH    R1
+";
+    // Dividend, divisor, quotient and remainder. The remainder takes the
+    // sign of the dividend.
+    let cases = [
+        ([0o10000, 0o00000], 0o20000, 0o20000, 0o00000),
+        ([0o00000, 0o00007], 0o00002, 0o00003, 0o00001),
+        ([0o00000, 0o00007], 0o77775, 0o77774, 0o00001),
+        ([0o77777, 0o77770], 0o00002, 0o77774, 0o77776),
+        ([0o77777, 0o77770], 0o77775, 0o00003, 0o77776),
+        ([0o77777, 0o77776], 0o37777, 0o77777, 0o77776),
+        ([0o20000, 0o00000], 0o20000, 0o37777, 0o20000),
+    ];
+    for &(dividend, divisor, quotient, remainder) in cases.iter() {
+        let init = [
+            ("PAIR", dividend[0]),
+            ("PAIR+1", dividend[1]),
+            ("Y", divisor),
+        ];
+        let out = run(source, &init);
+        let case = format!("{:05o} {:05o} / {:05o}", dividend[0], dividend[1], divisor);
+        assert_eq!(out.word("R0"), quotient, "quotient of {}", case);
+        assert_eq!(out.word("R1"), remainder, "remainder of {}", case);
+    }
+}
+
+#[test]
+fn dv_zero_quotient_follows_the_sign_rule() {
+    let source = "
+        EXTEND
+        DCA     PAIR
+        EXTEND
+        DV      Y
+        TS      R0
+";
+    // A dividend smaller than the divisor gives +0 when the signs agree
+    // and -0 when they differ.
+    let cases = [
+        ([0o00000, 0o00001], 0o37777, 0o00000),
+        ([0o77777, 0o77776], 0o40000, 0o00000),
+        ([0o00000, 0o00001], 0o40000, 0o77777),
+        ([0o77777, 0o77776], 0o37777, 0o77777),
+    ];
+    for &(dividend, divisor, quotient) in cases.iter() {
+        let init = [
+            ("PAIR", dividend[0]),
+            ("PAIR+1", dividend[1]),
+            ("Y", divisor),
+        ];
+        let case = format!("{:05o} {:05o} / {:05o}", dividend[0], dividend[1], divisor);
+        assert_eq!(run(source, &init).word("R0"), quotient, "{}", case);
+    }
+}
+
+#[test]
+fn dv_by_zero_saturates_the_quotient() {
+    let source = "
+        EXTEND
+        DCA     PAIR
+        EXTEND
+        DV      Y
+        TS      R0
+";
+    // The hardware result is undefined. Rather than dividing by zero, the
+    // quotient saturates with the sign rule.
+    let cases = [
+        ([0o00001, 0o00007], 0o00000, 0o37777),
+        ([0o00001, 0o00007], 0o77777, 0o40000),
+        ([0o77776, 0o77770], 0o00000, 0o40000),
+        ([0o77776, 0o77770], 0o77777, 0o37777),
+        ([0o01234, 0o00000], 0o00000, 0o37777),
+    ];
+    for &(dividend, divisor, quotient) in cases.iter() {
+        let init = [
+            ("PAIR", dividend[0]),
+            ("PAIR+1", dividend[1]),
+            ("Y", divisor),
+        ];
+        let case = format!("{:05o} {:05o} / {:05o}", dividend[0], dividend[1], divisor);
+        assert_eq!(run(source, &init).word("R0"), quotient, "{}", case);
+    }
+}
+
+#[test]
+fn das_carries_between_words() {
+    let source = "
+        CA      Y
+        TS      L
+        CA      X
+        DAS     PAIR
+        TS      R0
+        LXCH    R1
+";
+    // A,L and K,K+1 before, K,K+1 and the overflow left in A after.
+    let cases = [
+        (
+            [0o00000, 0o00001],
+            [0o00000, 0o37777],
+            [0o00001, 0o00000],
+            0o00000,
+        ),
+        (
+            [0o00001, 0o00000],
+            [0o37777, 0o00000],
+            [0o00000, 0o00000],
+            0o00001,
+        ),
+        (
+            [0o00000, 0o77776],
+            [0o00005, 0o40000],
+            [0o00004, 0o77777],
+            0o00000,
+        ),
+        (
+            [0o77776, 0o00000],
+            [0o40000, 0o00000],
+            [0o77777, 0o00000],
+            0o77776,
+        ),
+        (
+            [0o00003, 0o00004],
+            [0o00001, 0o00002],
+            [0o00004, 0o00006],
+            0o00000,
+        ),
+    ];
+    for &(al, k, sum, overflow) in cases.iter() {
+        let init = [
+            ("X", al[0]),
+            ("Y", al[1]),
+            ("PAIR", k[0]),
+            ("PAIR+1", k[1]),
+            ("R1", 0o55555),
+        ];
+        let out = run(source, &init);
+        let case = format!("{:05o} {:05o} + {:05o} {:05o}", al[0], al[1], k[0], k[1]);
+        assert_eq!(
+            [out.word_at("PAIR", 0), out.word_at("PAIR", 1)],
+            sum,
+            "sum of {}",
+            case
+        );
+        assert_eq!(out.word("R0"), overflow, "overflow of {}", case);
+        assert_eq!(out.word("R1"), 0, "L after {}", case);
+    }
+}
+
+#[test]
+fn ddoubl_doubles_a_and_l() {
+    let source = "
+        CA      Y
+        TS      L
+        CA      X
+        DDOUBL
+        DXCH    PAIR
+";
+    let out = run(source, &[("X", 0o00001), ("Y", 0o00002)]);
+    assert_eq!([out.word_at("PAIR", 0), out.word_at("PAIR", 1)], [2, 4]);
+
+    let out = run(source, &[("X", 0o00000), ("Y", 0o20000)]);
+    assert_eq!([out.word_at("PAIR", 0), out.word_at("PAIR", 1)], [1, 0]);
+}
+
+#[test]
+fn dxch_dca_and_dcs_move_pairs() {
+    let source = "
+        CA      Y
+        TS      L
+        CA      X
+        DXCH    PAIR
+";
+    let init = [("X", 1), ("Y", 2), ("PAIR", 3), ("PAIR+1", 4)];
+    let out = run(source, &init);
+    assert_eq!([out.regs.a, out.regs.l], [3, 4]);
+    assert_eq!([out.word_at("PAIR", 0), out.word_at("PAIR", 1)], [1, 2]);
+
+    let out = run(
+        "        EXTEND
+        DCA     PAIR
+",
+        &init,
+    );
+    assert_eq!([out.regs.a, out.regs.l], [3, 4]);
+
+    let out = run(
+        "        EXTEND
+        DCS     PAIR
+",
+        &init,
+    );
+    assert_eq!([out.regs.a, out.regs.l & 0o77777], [0o177774, 0o77773]);
+
+    let out = run(
+        "        EXTEND
+        DCA     PAIR
+        EXTEND
+        DCOM
+",
+        &init,
+    );
+    assert_eq!([out.regs.a, out.regs.l & 0o77777], [0o177774, 0o77773]);
+}
+
+#[test]
+fn dtcf_jumps_to_another_bank() {
+    let source = "
+        CA      FARADR
+        TS      L
+        CA      FARBANK
+JUMP    DTCF
+        TCF     DONE
+FARADR  ADRES   FAR
+FARBANK OCT     50000
+        BANK    24
+FAR     INCR    R0
+        TCF     DONE
+        SETLOC  4100
+";
+    let out = run(source, &[]);
+    assert_eq!(out.word("R0"), 1);
+    assert_eq!(out.regs.fb, 0o50000);
+    assert_eq!(out.regs.a, 0);
+    assert_eq!(out.regs.l, out.label("JUMP") + 1);
+}
+
+#[test]
+fn exchanges_with_l_and_q() {
+    let source = "
+        CA      X
+        TS      L
+        LXCH    Y
+        CA      X
+        TS      Q
+        EXTEND
+        QXCH    I
+";
+    let out = run(source, &[("X", 5), ("Y", 6), ("I", 9)]);
+    assert_eq!(out.regs.l, 6);
+    assert_eq!(out.regs.q, 9);
+    assert_eq!(out.word("Y"), 5);
+    assert_eq!(out.word("I"), 5);
+}
+
+#[test]
+fn mask_ands_with_sign_extension() {
+    let source = "
+        CA      X
+        MASK    Y
+";
+    let out = run(source, &[("X", 0o70707), ("Y", 0o52525)]);
+    assert_eq!(out.regs.a, 0o150505);
+
+    let out = run(source, &[("X", 0o70707), ("Y", 0o12525)]);
+    assert_eq!(out.regs.a, 0o010505);
+}
+
+#[test]
+fn editing_registers_shift_on_write() {
+    let source = "
+        CA      X
+        TS      CYR
+        TS      SR
+        TS      CYL
+        CA      Y
+        TS      EDOP
+";
+    let out = run(source, &[("X", 0o40002), ("Y", 0o12345)]);
+    let edit = |addr: usize| out.erasable[addr];
+    assert_eq!(edit(0o20), 0o20001);
+    assert_eq!(edit(0o21), 0o60001);
+    assert_eq!(edit(0o22), 0o00005);
+    assert_eq!(edit(0o23), 0o00051);
+
+    // Bit 1 cycles round into the sign.
+    let out = run(source, &[("X", 0o00003), ("Y", 0)]);
+    let edit = |addr: usize| out.erasable[addr];
+    assert_eq!(edit(0o20), 0o40001);
+    assert_eq!(edit(0o21), 0o00001);
+    assert_eq!(edit(0o22), 0o00006);
+
+    let out = run(
+        "        CA      X
+        TS      CYR
+        CA      CYR
+",
+        &[("X", 2)],
+    );
+    assert_eq!(out.regs.a, 0o000001);
+}
+
+#[test]
+fn index_modifies_the_next_instruction() {
+    let source = "
+        INDEX   I
+        CA      TABLE
+        TS      R0
+        TCF     DONE
+TABLE   DEC     10
+        DEC     20
+        DEC     30
+";
+    let out = run(source, &[("I", 2)]);
+    assert_eq!(out.word("R0"), 30);
+
+    let out = run(
+        source.replace("TABLE\n", "TABLE +1\n").as_str(),
+        &[("I", 0o77776)],
+    );
+    assert_eq!(out.word("R0"), 10);
+}
+
+#[test]
+fn index_carries_across_extend() {
+    // EXTEND stays in force through INDEX, and the extracode it selects
+    // also consumes it.
+    let source = "
+        CA      X
+        EXTEND
+        INDEX   I
+        MP      TABLE
+        DXCH    PAIR
+        CA      X
+        TS      R0
+        TCF     DONE
+TABLE   DEC     10
+        DEC     20
+        DEC     30
+";
+    let out = run(source, &[("X", 30), ("I", 2)]);
+    assert_eq!([out.word_at("PAIR", 0), out.word_at("PAIR", 1)], [0, 900]);
+    assert_eq!(out.word("R0"), 30);
+}
+
+#[test]
+fn bzf_and_bzmf_branch_on_a() {
+    let bzf = "
+        CA      X
+        EXTEND
+        BZF     TAKEN
+        TCF     DONE
+TAKEN   INCR    R0
+";
+    let bzmf = bzf.replace("BZF ", "BZMF");
+    let cases = [
+        (0o00000, 1, 1),
+        (0o77777, 1, 1),
+        (0o00001, 0, 0),
+        (0o77776, 0, 1),
+    ];
+    for &(x, zero, not_positive) in cases.iter() {
+        assert_eq!(run(bzf, &[("X", x)]).word("R0"), zero, "BZF {:05o}", x);
+        assert_eq!(
+            run(&bzmf, &[("X", x)]).word("R0"),
+            not_positive,
+            "BZMF {:05o}",
+            x
+        );
+    }
+}
+
+#[test]
+fn tc_links_through_q() {
+    let source = "
+CALL    TC      SUB
+        TS      R0
+        TCF     DONE
+SUB     CA      Q
+        TS      R1
+        CA      SEVEN
+        RETURN
+SEVEN   DEC     7
+";
+    let out = run(source, &[]);
+    assert_eq!(out.word("R0"), 7);
+    assert_eq!(out.word("R1"), out.label("CALL") + 1);
+    // RETURN executes the TC left in Q, which links Q to the word after Q.
+    assert_eq!(out.regs.q, 0o00003);
+}
+
+#[test]
+fn channel_instructions() {
+    let source = "
+        CA      X
+        EXTEND
+        WRITE   5
+        CA      Y
+        EXTEND
+        ROR     5
+        TS      R0
+        CA      Y
+        EXTEND
+        RAND    5
+        TS      R1
+        CA      Y
+        EXTEND
+        RXOR    5
+        TS      R2
+        CA      Y
+        EXTEND
+        WAND    5
+        CA      Y
+        EXTEND
+        WOR     5
+        EXTEND
+        READ    5
+        TS      R3
+";
+    let out = run(source, &[("X", 0o12345), ("Y", 0o07070)]);
+    assert_eq!(out.word("R0"), 0o17375);
+    assert_eq!(out.word("R1"), 0o02040);
+    assert_eq!(out.word("R2"), 0o15335);
+    assert_eq!(out.word("R3"), 0o07070);
+}
+
+#[test]
+fn resume_runs_brupt_before_returning() {
+    let source = "
+        CA      RADDR
+        TS      ZRUPT
+        CA      RINST
+        TS      BRUPT
+        RESUME
+        TCF     DONE
+CONT    TS      R0
+        TCF     DONE
+RADDR   ADRES   CONT
+RINST   CA      SEVEN
+SEVEN   DEC     7
+";
+    let out = run(source, &[]);
+    assert_eq!(out.word("R0"), 7);
+}
+
+#[test]
+fn interrupt_returns_through_resume() {
+    let source = "
+        TCF     MAIN
+        SETLOC  4004
+        INCR    R0
+        RESUME
+        SETLOC  4060
+MAIN    CA      X
+        RELINT
+        AD      Y
+        TS      R1
+        INHINT
+";
+    let init = [("X", 3), ("Y", 4)];
+    let out = run_with(source, &init, |cpu| cpu.request_rupt(RUPT_TIME6));
+    assert_eq!(out.word("R0"), 1);
+    assert_eq!(out.word("R1"), 7);
+}
@@ -0,0 +1,73 @@
+use heapless::spsc::Queue;
+
+use ragc_core::consts::cpu::*;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::MemoryMap;
+
+const VECTORS: [u8; 10] = [
+    RUPT_TIME6,
+    RUPT_TIME5,
+    RUPT_TIME3,
+    RUPT_TIME4,
+    RUPT_KEY1,
+    RUPT_KEY2,
+    RUPT_UPRUPT,
+    RUPT_DOWNRUPT,
+    RUPT_RADAR,
+    RUPT_HANDRUPT,
+];
+
+fn enable_rupts(cpu: &mut AgcCpu) {
+    cpu.rupt = 0;
+    cpu.gint = true;
+}
+
+#[test]
+fn each_vector_lands_at_its_address() {
+    for &n in VECTORS.iter() {
+        let mut queue: Queue<u8, 8> = Queue::new();
+        let (mut rupt_tx, rupt_rx) = queue.split();
+        let mut cpu = AgcCpu::new(MemoryMap::new_blank(rupt_rx));
+        enable_rupts(&mut cpu);
+
+        rupt_tx.enqueue(n).unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.registers().z, 0o4000 + 4 * n as u16, "RUPT {}", n);
+        assert!(cpu.is_irupt);
+    }
+}
+
+#[test]
+fn vectors_are_taken_in_priority_order() {
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (mut rupt_tx, rupt_rx) = queue.split();
+    let mut cpu = AgcCpu::new(MemoryMap::new_blank(rupt_rx));
+    enable_rupts(&mut cpu);
+
+    rupt_tx.enqueue(RUPT_HANDRUPT).unwrap();
+    rupt_tx.enqueue(RUPT_RADAR).unwrap();
+    rupt_tx.enqueue(RUPT_UPRUPT).unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.registers().z, 0o4000 + 4 * RUPT_UPRUPT as u16);
+
+    // The remaining requests stay latched while the first is serviced.
+    assert_eq!(cpu.rupt, (1 << RUPT_RADAR) | (1 << RUPT_HANDRUPT));
+}
+
+#[test]
+fn requests_wait_for_interrupts_to_be_enabled() {
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (mut rupt_tx, rupt_rx) = queue.split();
+    let mut cpu = AgcCpu::new(MemoryMap::new_blank(rupt_rx));
+    cpu.rupt = 0;
+
+    rupt_tx.enqueue(RUPT_HANDRUPT).unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.rupt, 1 << RUPT_HANDRUPT);
+    assert!(!cpu.is_irupt);
+
+    cpu.gint = true;
+    cpu.step().unwrap();
+    assert_eq!(cpu.registers().z, 0o4050);
+}
@@ -0,0 +1,74 @@
+use ragc_core::pacer::{
+    PaceMode, Pacer, DEFAULT_MAX_CATCHUP_NANOS, MAX_SPEED_SLICE, MCTS_PER_SECOND,
+};
+
+const MS: u64 = 1_000_000;
+
+#[test]
+fn budgets_follow_the_mode() {
+    let mut pacer = Pacer::new(PaceMode::RealTime);
+    assert_eq!(pacer.budget(10 * MS), MCTS_PER_SECOND / 100);
+
+    let mut pacer = Pacer::new(PaceMode::Accelerated(4.0));
+    assert_eq!(pacer.budget(10 * MS), 4 * MCTS_PER_SECOND / 100);
+
+    let mut pacer = Pacer::new(PaceMode::MaxSpeed);
+    assert_eq!(pacer.budget(10 * MS), MAX_SPEED_SLICE);
+
+    let mut pacer = Pacer::new(PaceMode::Paused);
+    assert_eq!(pacer.budget(10 * MS), 0);
+    assert_eq!(pacer.stats().dropped_cycles, 0);
+}
+
+#[test]
+fn overrun_is_taken_from_the_next_budget() {
+    // 100ms is a whole number of MCTs, so no fraction carries over.
+    let mut pacer = Pacer::new(PaceMode::RealTime);
+    let overrun = 100;
+    let budget = pacer.budget(100 * MS);
+    pacer.ran(budget + overrun);
+    assert_eq!(pacer.budget(100 * MS), budget - overrun);
+}
+
+#[test]
+fn stalls_beyond_the_bound_are_dropped() {
+    let mut pacer = Pacer::new(PaceMode::RealTime);
+    let bound = DEFAULT_MAX_CATCHUP_NANOS * MCTS_PER_SECOND / 1000 / MS;
+    assert_eq!(pacer.budget(1000 * MS), bound);
+    assert_eq!(pacer.stats().dropped_cycles, MCTS_PER_SECOND - bound);
+
+    pacer.reset_stats();
+    assert_eq!(pacer.stats().dropped_cycles, 0);
+}
+
+#[test]
+fn accelerated_modes_are_not_capped_by_the_bound() {
+    // The CPU runs a budget every 5ms of host time at most.
+    for factor in [20.0, 50.0, 100.0] {
+        let mut pacer = Pacer::new(PaceMode::Accelerated(factor));
+        let mut ran = 0;
+        for _ in 0..200 {
+            let budget = pacer.budget(5 * MS);
+            pacer.ran(budget);
+            ran += budget;
+        }
+        let expected = MCTS_PER_SECOND as f64 * factor;
+        assert!(
+            (expected - ran as f64).abs() < 200.0,
+            "{}x ran {}",
+            factor,
+            ran
+        );
+        assert_eq!(pacer.stats().dropped_cycles, 0);
+    }
+}
+
+#[test]
+fn changing_the_mode_forgets_owed_cycles() {
+    let mut pacer = Pacer::new(PaceMode::RealTime);
+    pacer.budget(50 * MS);
+    pacer.set_mode(PaceMode::Paused);
+    assert_eq!(pacer.budget(50 * MS), 0);
+    pacer.set_mode(PaceMode::RealTime);
+    assert_eq!(pacer.budget(10 * MS), MCTS_PER_SECOND / 100);
+}
@@ -0,0 +1,157 @@
+use heapless::spsc::Queue;
+
+use ragc_asm::assemble;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::mods::AgcNullPeriph;
+use ragc_core::mem::{AgcAddr, MemoryMap};
+use ragc_core::padload::{AgcPadLoad, AgcPadLoadError};
+
+fn erasable(bank: u8, offset: u16) -> AgcAddr {
+    AgcAddr::Erasable { bank, offset }
+}
+
+fn symbols(name: &str) -> Option<AgcAddr> {
+    match name {
+        "AZIMUTH" => Some(erasable(0, 0o61)),
+        "REFSMMAT" => Some(erasable(3, 0o376)),
+        _ => None,
+    }
+}
+
+#[test]
+fn values_are_in_place_at_power_up() {
+    let text = "
+        # Launch azimuth and the first words of REFSMMAT
+        AZIMUTH     12345
+        REFSMMAT    00001
+        REFSMMAT+2  77776   # crosses into bank 4
+        E5,1777     00077
+        1300        40000
+    ";
+
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mut mm = MemoryMap::new_blank(rupt_rx);
+    assert_eq!(AgcPadLoad::new(text, symbols).apply(&mut mm), Ok(5));
+    let cpu = AgcCpu::new(mm);
+
+    assert_eq!(cpu.peek(erasable(0, 0o61)), 0o12345);
+    assert_eq!(cpu.peek(erasable(3, 0o376)), 0o00001);
+    assert_eq!(cpu.peek(erasable(4, 0o000)), 0o77776);
+    assert_eq!(cpu.peek(erasable(5, 0o377)), 0o00077);
+    assert_eq!(cpu.peek(erasable(2, 0o300)), 0o40000);
+}
+
+#[test]
+fn invalid_lines_are_reported() {
+    let check = |text: &str| AgcPadLoad::new(text, symbols).next().unwrap().err();
+
+    assert_eq!(
+        check("AZIMUTH"),
+        Some(AgcPadLoadError::InvalidLine { line: 1 })
+    );
+    assert_eq!(
+        check("1400 00000"),
+        Some(AgcPadLoadError::InvalidAddress { line: 1 })
+    );
+    assert_eq!(
+        check("\nPITCH 00000"),
+        Some(AgcPadLoadError::UnknownSymbol { line: 2 })
+    );
+    assert_eq!(
+        check("4000 00000"),
+        Some(AgcPadLoadError::NotErasable { line: 1 })
+    );
+    assert_eq!(
+        check("AZIMUTH 100000"),
+        Some(AgcPadLoadError::InvalidValue { line: 1 })
+    );
+    assert_eq!(
+        AgcPadLoad::new("AZIMUTH 00001", |_| None)
+            .next()
+            .unwrap()
+            .err(),
+        Some(AgcPadLoadError::UnknownSymbol { line: 1 })
+    );
+}
+
+#[test]
+fn nothing_is_written_on_errors() {
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mut mm = MemoryMap::new_blank(rupt_rx);
+
+    let res = AgcPadLoad::new("AZIMUTH 12345\nPITCH 00001\n", symbols).apply(&mut mm);
+    assert_eq!(res, Err(AgcPadLoadError::UnknownSymbol { line: 2 }));
+    assert_eq!(mm.read_physical(erasable(0, 0o61)), 0);
+}
+
+#[test]
+fn malformed_addresses_are_reported() {
+    let check = |text: &str| AgcPadLoad::new(text, symbols).next().unwrap().err();
+
+    assert_eq!(
+        check("AZIMUTH+9 00000"),
+        Some(AgcPadLoadError::InvalidAddress { line: 1 })
+    );
+    assert_eq!(
+        check("AZIMUTH+ 00000"),
+        Some(AgcPadLoadError::InvalidAddress { line: 1 })
+    );
+    assert_eq!(
+        check("E9,1400 00000"),
+        Some(AgcPadLoadError::InvalidAddress { line: 1 })
+    );
+    assert_eq!(
+        check("REFSMMAT+4000 00000"),
+        Some(AgcPadLoadError::NotErasable { line: 1 })
+    );
+    assert_eq!(
+        check("AZIMUTH 7777x"),
+        Some(AgcPadLoadError::InvalidValue { line: 1 })
+    );
+    assert_eq!(
+        check("# header\n\nAZIMUTH 00001 00002 # extra"),
+        Some(AgcPadLoadError::InvalidLine { line: 3 })
+    );
+    assert_eq!(
+        AgcPadLoad::new("ROPE 00001", |_| Some(AgcAddr::Fixed {
+            bank: 2,
+            offset: 0
+        }))
+        .next()
+        .unwrap()
+        .err(),
+        Some(AgcPadLoadError::NotErasable { line: 1 })
+    );
+}
+
+#[test]
+fn symbols_resolve_before_reset() {
+    let source = "
+        SETLOC  61
+AZIM    ERASE
+TABLE   ERASE   +2
+        SETLOC  4000
+        CA      AZIM
+        AD      TABLE +2
+        TCF     +0
+";
+    let asm = assemble(source).unwrap();
+    let text = "AZIM 00100\nTABLE+2 00023\n";
+
+    let mut queue: Queue<u8, 8> = Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let (mut downrupt, mut dsky) = (AgcNullPeriph, AgcNullPeriph);
+    let mut mm = MemoryMap::new(asm.rope(), &mut downrupt, &mut dsky, rupt_rx);
+    let res = AgcPadLoad::new(text, |x| asm.addr(x)).apply(&mut mm);
+    assert_eq!(res, Ok(2));
+
+    let mut cpu = AgcCpu::new(mm);
+    cpu.reset();
+    cpu.rupt = 0;
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.registers().a, 0o123);
+    assert_eq!(cpu.peek(erasable(0, 0o64)), 0o23);
+}
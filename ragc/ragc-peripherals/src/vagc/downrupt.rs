@@ -46,6 +46,16 @@ impl DownruptPeriph {
             word_order: false,
         }
     }
+
+    /// Creates a downlink that opens no port and drops every word, for
+    /// deterministic runs.
+    pub fn new_offline() -> Self {
+        let (tx, _rx) = unbounded();
+        DownruptPeriph {
+            tx,
+            word_order: false,
+        }
+    }
 }
 
 impl AgcIoPeriph for DownruptPeriph {
@@ -78,11 +88,11 @@ impl AgcIoPeriph for DownruptPeriph {
             }
             ragc_core::consts::io::CHANNEL_CHAN34 => {
                 let packet = generate_yaagc_packet(channel_idx, value);
-                self.tx.send(packet).unwrap();
+                let _res = self.tx.send(packet);
             }
             ragc_core::consts::io::CHANNEL_CHAN35 => {
                 let packet = generate_yaagc_packet(channel_idx, value);
-                self.tx.send(packet).unwrap();
+                let _res = self.tx.send(packet);
             }
             _ => {}
         }
@@ -91,4 +101,15 @@ impl AgcIoPeriph for DownruptPeriph {
     fn is_interrupt(&mut self) -> u16 {
         0
     }
+
+    fn save_state(&self, buf: &mut [u8]) -> usize {
+        buf[0] = self.word_order as u8;
+        1
+    }
+
+    fn restore_state(&mut self, buf: &[u8]) {
+        if let Some(x) = buf.first() {
+            self.word_order = *x != 0;
+        }
+    }
 }
@@ -4,9 +4,17 @@ use dsky_protocol::agc::{generate_yaagc_packet, parse_yaagc_packet};
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use log::{debug, warn};
 
+use std::collections::VecDeque;
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
 use std::println;
+use std::vec::Vec;
+
+use ragc_core::pacer::MCTS_PER_SECOND;
+
+// AGC time base used to flash the DSKY lamps in deterministic mode. Lamps
+// are lit for the first 750ms of every second.
+const MCTS_FLASH_ON: usize = MCTS_PER_SECOND as usize * 3 / 4;
 
 pub struct DskyDisplay {
     digit: [u8; 15],
@@ -21,8 +29,22 @@ pub struct DskyDisplay {
     flash_tx: Sender<u16>,
     last_dsalmout: u16,
     last_dskyval: u16,
+
+    // Last value written to each relay row of channel 10, kept so a
+    // restored snapshot can repaint the display.
+    relays: [u16; 16],
+
+    // Deterministic mode: key presses scheduled by AGC cycle, keys that
+    // are due but not yet delivered, and the flashing phase last sent.
+    schedule: Option<VecDeque<(usize, u16)>>,
+    pending_keys: VecDeque<u16>,
+    flash_lit: Option<bool>,
 }
 
+// Size of the display state saved by DskyDisplay::save_state. The key
+// queues of deterministic mode follow it.
+const DSKY_STATE_SIZE: usize = 15 + 8 * 2 + 16 * 2;
+
 fn handle_stream_input(stream: &mut TcpStream, keypress_tx: &Sender<u16>) {
     loop {
         let mut buf = [0; 4];
@@ -70,6 +92,55 @@ fn handle_steam_output(stream: &mut TcpStream, dsky_rx: &Receiver<[u8; 4]>) {
     println!("Disconnecting");
 }
 
+/// Channel 163 value to send for the lit or dark half of the flashing
+/// cycle, if any.
+fn flash_value(channel_value: u16, lit: bool) -> Option<u16> {
+    if lit {
+        let mut value = channel_value;
+        if channel_value & 0o00040 == 0o00040 {
+            value &= !0o00040;
+        }
+        Some(value)
+    } else if channel_value != 0o00000 {
+        let mut value = channel_value & !0o00160;
+        if channel_value & 0o00040 == 0o00040 {
+            value |= 0o00040;
+        }
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Returns the key code for a DSKY key name, in the form `is_interrupt`
+/// expects. PRO presses the proceed key and PROREL releases it.
+pub fn dsky_keycode(name: &str) -> Option<u16> {
+    let code = match name {
+        "0" => 0o20,
+        "1" => 0o1,
+        "2" => 0o2,
+        "3" => 0o3,
+        "4" => 0o4,
+        "5" => 0o5,
+        "6" => 0o6,
+        "7" => 0o7,
+        "8" => 0o10,
+        "9" => 0o11,
+        "VERB" => 0o21,
+        "RSET" => 0o22,
+        "KEYREL" => 0o31,
+        "+" => 0o32,
+        "-" => 0o33,
+        "ENTR" => 0o34,
+        "CLR" => 0o36,
+        "NOUN" => 0o37,
+        "PRO" => 0o40000,
+        "PROREL" => 0o40000 | 0o20000,
+        _ => return None,
+    };
+    Some(code)
+}
+
 fn flashing_thread(flash_rx: Receiver<u16>, dsky_tx: Sender<[u8; 4]>) {
     let mut channel_value = 0o00000;
     let start_time = std::time::SystemTime::now();
@@ -80,20 +151,8 @@ fn flashing_thread(flash_rx: Receiver<u16>, dsky_tx: Sender<[u8; 4]>) {
         }
 
         let elapsed = start_time.elapsed().unwrap().as_millis();
-        if elapsed % 1000 < 750 {
-            let mut value = channel_value;
-            if channel_value & 0o00040 == 0o00040 {
-                value &= !0o00040;
-            }
+        if let Some(value) = flash_value(channel_value, elapsed % 1000 < 750) {
             dsky_tx.send(generate_yaagc_packet(0o0163, value)).unwrap();
-        } else {
-            if channel_value != 0o00000 {
-                let mut value = channel_value & !0o00160;
-                if channel_value & 0o00040 == 0o00040 {
-                    value |= 0o00040;
-                }
-                dsky_tx.send(generate_yaagc_packet(0o0163, value)).unwrap();
-            }
         }
 
         std::thread::sleep(std::time::Duration::new(0, 10000000));
@@ -126,13 +185,84 @@ fn dsky_network_thread(keypress_tx: Sender<u16>, dsky_rx: Receiver<[u8; 4]>) {
 
 impl DskyDisplay {
     pub fn new() -> Self {
+        Self::with_schedule(None)
+    }
+
+    /// Creates a DSKY driven entirely by AGC time. Key presses come from
+    /// `keys`, a list of (cycle, key code) pairs, and the lamps flash based
+    /// on the cycle count. No network server is started, so runs neither
+    /// take input from nor depend on the host. Keys still scheduled take 10
+    /// bytes each in a machine snapshot, which holds a few hundred of them.
+    pub fn new_deterministic(mut keys: Vec<(usize, u16)>) -> Self {
+        keys.sort_by_key(|x| x.0);
+        Self::with_schedule(Some(keys.into_iter().collect()))
+    }
+
+    /// Restores the key queues saved after the display state. A schedule
+    /// is only taken over in deterministic mode, live DSKYs keep reading
+    /// the network. Returns false if `buf` is truncated.
+    fn restore_keys(&mut self, buf: &[u8]) -> bool {
+        let mut pos = 0;
+        let mut take = |len: usize| {
+            let res = buf.get(pos..pos + len);
+            pos += len;
+            res
+        };
+        let word = |x: &[u8]| u16::from_le_bytes([x[0], x[1]]);
+
+        let pending = match take(2) {
+            Some(x) => word(x),
+            None => return false,
+        };
+        let mut pending_keys = VecDeque::new();
+        for _ in 0..pending {
+            match take(2) {
+                Some(x) => pending_keys.push_back(word(x)),
+                None => return false,
+            }
+        }
+
+        let mut schedule = None;
+        if take(1) == Some(&[1]) {
+            let len = match take(2) {
+                Some(x) => word(x),
+                None => return false,
+            };
+            let mut keys = VecDeque::new();
+            for _ in 0..len {
+                match take(10) {
+                    Some(x) => {
+                        let mut cycle = [0u8; 8];
+                        cycle.copy_from_slice(&x[..8]);
+                        keys.push_back((u64::from_le_bytes(cycle) as usize, word(&x[8..])));
+                    }
+                    None => return false,
+                }
+            }
+            schedule = Some(keys);
+        }
+
+        self.pending_keys = pending_keys;
+        if self.schedule.is_some() {
+            if let Some(x) = schedule {
+                self.schedule = Some(x);
+            }
+        }
+        true
+    }
+
+    fn with_schedule(schedule: Option<VecDeque<(usize, u16)>>) -> Self {
         let (keypress_tx, keypress_rx) = unbounded();
         let (dsky_tx, dsky_rx) = unbounded();
         let (flash_tx, flash_rx) = unbounded();
 
-        let flash_dsky_tx = dsky_tx.clone();
-        std::thread::spawn(move || flashing_thread(flash_rx, flash_dsky_tx));
-        std::thread::spawn(move || dsky_network_thread(keypress_tx, dsky_rx));
+        // In deterministic mode the display channel has no receiver and
+        // packets sent to it are dropped.
+        if schedule.is_none() {
+            let flash_dsky_tx = dsky_tx.clone();
+            std::thread::spawn(move || flashing_thread(flash_rx, flash_dsky_tx));
+            std::thread::spawn(move || dsky_network_thread(keypress_tx, dsky_rx));
+        }
 
         Self {
             digit: [0; 15],
@@ -147,6 +277,34 @@ impl DskyDisplay {
             output_flags: 0x0,
             last_dsalmout: 0x0,
             last_dskyval: 0x0,
+            relays: [0; 16],
+            schedule,
+            pending_keys: VecDeque::new(),
+            flash_lit: None,
+        }
+    }
+
+    /// Hands the lamp flags to whatever does the flashing: the flashing
+    /// thread in real-time mode, or directly for the current phase in
+    /// deterministic mode.
+    fn publish_flags(&mut self) {
+        match (&self.schedule, self.flash_lit) {
+            (None, _) => {
+                self.flash_tx.send(self.output_flags).unwrap();
+            }
+            (Some(_), Some(lit)) => {
+                if let Some(value) = flash_value(self.output_flags, lit) {
+                    let _res = self.dsky_tx.send(generate_yaagc_packet(0o0163, value));
+                }
+            }
+            (Some(_), None) => {}
+        }
+    }
+
+    fn next_keypress(&mut self) -> Option<u16> {
+        match self.schedule {
+            Some(_) => self.pending_keys.pop_front(),
+            None => self.keypress.try_recv().ok(),
         }
     }
 
@@ -175,11 +333,11 @@ impl DskyDisplay {
                 } else {
                     self.output_flags &= 0o77377;
                 }
-                self.flash_tx.send(self.output_flags).unwrap();
+                self.publish_flags();
             }
             0o163 => {
                 self.output_flags = value;
-                self.flash_tx.send(self.output_flags).unwrap();
+                self.publish_flags();
             }
             _ => {}
         }
@@ -199,12 +357,10 @@ impl DskyDisplay {
         if self.last_dsalmout != flags {
             debug!("DSKY: Setting CHANNEL_DSALMOUT Flags: {:o}", flags);
             self.last_dsalmout = flags;
-            self.dsky_tx
-                .send(generate_yaagc_packet(0o11, flags))
-                .unwrap();
+            let _res = self.dsky_tx.send(generate_yaagc_packet(0o11, flags));
 
             self.output_flags = (self.output_flags & 0o77607) | (flags & 0o00170);
-            self.flash_tx.send(self.output_flags).unwrap();
+            self.publish_flags();
         }
     }
     pub fn set_adv_flags(&mut self, _flags: u16) {}
@@ -213,9 +369,10 @@ impl DskyDisplay {
             return;
         }
         self.last_dskyval = val;
-        self.dsky_tx.send(generate_yaagc_packet(0o10, val)).unwrap();
+        let _res = self.dsky_tx.send(generate_yaagc_packet(0o10, val));
 
         let (a, _b, c, d) = self.parse_fields(val);
+        self.relays[a as usize] = val;
         match a {
             1 => {
                 self.digit[13] = get_7seg(c);
@@ -297,9 +454,116 @@ impl ragc_core::mem::mods::AgcIoPeriph for DskyDisplay {
         }
     }
 
+    fn save_state(&self, buf: &mut [u8]) -> usize {
+        if buf.len() < DSKY_STATE_SIZE {
+            return usize::MAX;
+        }
+
+        buf[0..15].copy_from_slice(&self.digit);
+        let words = [
+            self.noun,
+            self.verb,
+            self.prog,
+            self.proceed,
+            self.output_flags,
+            self.keypress_val,
+            self.last_dsalmout,
+            self.last_dskyval,
+        ];
+        for (idx, val) in words.iter().chain(self.relays.iter()).enumerate() {
+            buf[15 + idx * 2..17 + idx * 2].copy_from_slice(&val.to_le_bytes());
+        }
+
+        // Keys due but not delivered yet, then the keys still scheduled,
+        // which are only present in deterministic mode.
+        let schedule = self.schedule.as_ref();
+        let len = DSKY_STATE_SIZE
+            + 2
+            + self.pending_keys.len() * 2
+            + 1
+            + schedule.map_or(0, |x| 2 + x.len() * 10);
+        if buf.len() < len {
+            return usize::MAX;
+        }
+        let mut pos = DSKY_STATE_SIZE;
+        let mut put = |data: &[u8]| {
+            buf[pos..pos + data.len()].copy_from_slice(data);
+            pos += data.len();
+        };
+        put(&(self.pending_keys.len() as u16).to_le_bytes());
+        for key in self.pending_keys.iter() {
+            put(&key.to_le_bytes());
+        }
+        match schedule {
+            Some(x) => {
+                put(&[1]);
+                put(&(x.len() as u16).to_le_bytes());
+                for (cycle, key) in x.iter() {
+                    put(&(*cycle as u64).to_le_bytes());
+                    put(&key.to_le_bytes());
+                }
+            }
+            None => put(&[0]),
+        }
+        len
+    }
+
+    fn restore_state(&mut self, buf: &[u8]) {
+        if buf.len() < DSKY_STATE_SIZE {
+            warn!("DSKY: Ignoring truncated snapshot state");
+            return;
+        }
+
+        let word = |idx: usize| u16::from_le_bytes([buf[15 + idx * 2], buf[16 + idx * 2]]);
+        self.digit.copy_from_slice(&buf[0..15]);
+        self.noun = word(0);
+        self.verb = word(1);
+        self.prog = word(2);
+        self.proceed = word(3);
+        self.output_flags = word(4);
+        self.keypress_val = word(5);
+        self.last_dsalmout = word(6);
+        self.last_dskyval = word(7);
+        for (idx, relay) in self.relays.iter_mut().enumerate() {
+            *relay = word(8 + idx);
+        }
+        if !self.restore_keys(&buf[DSKY_STATE_SIZE..]) {
+            warn!("DSKY: Ignoring truncated key schedule");
+        }
+
+        // Repaint whatever DSKY is connected with the restored display.
+        for val in self.relays.iter().filter(|x| **x != 0) {
+            let _res = self.dsky_tx.send(generate_yaagc_packet(0o10, *val));
+        }
+        let _res = self
+            .dsky_tx
+            .send(generate_yaagc_packet(0o11, self.last_dsalmout));
+        self.publish_flags();
+    }
+
+    fn tick(&mut self, total_cycles: usize) {
+        let schedule = match &mut self.schedule {
+            Some(x) => x,
+            None => return,
+        };
+
+        while let Some(&(cycle, key)) = schedule.front() {
+            if cycle > total_cycles {
+                break;
+            }
+            schedule.pop_front();
+            self.pending_keys.push_back(key);
+        }
+
+        let lit = total_cycles % (MCTS_PER_SECOND as usize) < MCTS_FLASH_ON;
+        if self.flash_lit != Some(lit) {
+            self.flash_lit = Some(lit);
+            self.publish_flags();
+        }
+    }
+
     fn is_interrupt(&mut self) -> u16 {
-        if self.keypress.len() > 0 {
-            let val = self.keypress.recv().unwrap();
+        if let Some(val) = self.next_keypress() {
             match val & 0o40000 {
                 0o40000 => {
                     self.proceed = val & 0o37777;
@@ -0,0 +1,303 @@
+use std::io::Write;
+
+use ragc_binaries::AgcRope;
+use ragc_core::consts::{RAM_BANK_NUM_WORDS, RAM_NUM_BANKS, ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use ragc_core::cpu::AgcUnprogSeq;
+use ragc_core::instructions::AgcInst;
+use ragc_core::mem::{rope_word, AgcAddr};
+use ragc_core::observer::{AgcMemAccess, AgcStepInfo, CpuObserver};
+use ragc_core::symbols::AgcSymbols;
+
+const READ: u8 = 1;
+const WRITTEN: u8 = 2;
+
+/// Words per line of the text maps.
+const MAP_LINE_WORDS: usize = 64;
+
+/// Observer recording which fixed words were executed and which erasable
+/// words were read or written.
+///
+/// Maps show one character per word. Fixed words are `X` when executed,
+/// `.` when not and blank when the rope word is zero. Erasable words are
+/// `r`, `w` or `b` when read, written or both, and `.` when untouched.
+/// Every bank is always listed, so maps of two runs can be diffed.
+pub struct Coverage<'r, 's> {
+    rope: &'r AgcRope,
+    symbols: Option<&'s dyn AgcSymbols>,
+    executed: Vec<bool>,
+    erasable: Vec<u8>,
+}
+
+/// Coverage of one routine, from its symbol up to the next one.
+struct RoutineCoverage<'s> {
+    name: &'s str,
+    start: AgcAddr,
+    executed: usize,
+    words: usize,
+}
+
+impl<'r, 's> Coverage<'r, 's> {
+    pub fn new(rope: &'r AgcRope) -> Self {
+        Coverage {
+            rope,
+            symbols: None,
+            executed: vec![false; ROM_NUM_BANKS * ROM_BANK_NUM_WORDS],
+            erasable: vec![0; RAM_NUM_BANKS * RAM_BANK_NUM_WORDS],
+        }
+    }
+
+    /// Adds the coverage of each routine named in `symbols`.
+    pub fn with_symbols(mut self, symbols: Option<&'s dyn AgcSymbols>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    pub fn write_text<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "Fixed     executed      words")?;
+        for bank in 0..ROM_NUM_BANKS {
+            let (executed, words) = self.fixed_bank(bank);
+            writeln!(
+                out,
+                "  {:02o}      {:>8}   {:>8}  {:5.1}%",
+                bank,
+                executed,
+                words,
+                percent(executed, words)
+            )?;
+        }
+        writeln!(out, "Erasable      read    written")?;
+        for bank in 0..RAM_NUM_BANKS {
+            let (read, written) = self.erasable_bank(bank);
+            writeln!(out, "  E{:o}      {:>8}   {:>8}", bank, read, written)?;
+        }
+
+        let routines = self.routines();
+        if !routines.is_empty() {
+            writeln!(out, "\nRoutines")?;
+            for x in routines.iter() {
+                writeln!(
+                    out,
+                    "  {:<12} {:<9}{:>6}/{:<6} {:5.1}%",
+                    x.name,
+                    x.start,
+                    x.executed,
+                    x.words,
+                    percent(x.executed, x.words)
+                )?;
+            }
+        }
+
+        for bank in 0..ROM_NUM_BANKS {
+            writeln!(out, "\nBank {:02o}", bank)?;
+            let map = self.fixed_map(bank);
+            for (idx, line) in map.as_bytes().chunks(MAP_LINE_WORDS).enumerate() {
+                let addr = AgcAddr::Fixed {
+                    bank: bank as u8,
+                    offset: (idx * MAP_LINE_WORDS) as u16,
+                };
+                let line = format!("{:<9}{}", addr, String::from_utf8_lossy(line));
+                writeln!(out, "{}", line.trim_end())?;
+            }
+        }
+        for bank in 0..RAM_NUM_BANKS {
+            writeln!(out, "\nBank E{:o}", bank)?;
+            let map = self.erasable_map(bank);
+            for (idx, line) in map.as_bytes().chunks(MAP_LINE_WORDS).enumerate() {
+                let addr = AgcAddr::Erasable {
+                    bank: bank as u8,
+                    offset: (idx * MAP_LINE_WORDS) as u16,
+                };
+                writeln!(out, "{:<9}{}", addr, String::from_utf8_lossy(line))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the coverage as JSON, one bank or routine per line.
+    pub fn write_json<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"fixed\": [")?;
+        for bank in 0..ROM_NUM_BANKS {
+            let (executed, words) = self.fixed_bank(bank);
+            writeln!(
+                out,
+                "    {{\"bank\": \"{:02o}\", \"executed\": {}, \"words\": {}, \"map\": \"{}\"}}{}",
+                bank,
+                executed,
+                words,
+                self.fixed_map(bank),
+                separator(bank, ROM_NUM_BANKS)
+            )?;
+        }
+        writeln!(out, "  ],")?;
+        writeln!(out, "  \"erasable\": [")?;
+        for bank in 0..RAM_NUM_BANKS {
+            let (read, written) = self.erasable_bank(bank);
+            writeln!(
+                out,
+                "    {{\"bank\": \"E{:o}\", \"read\": {}, \"written\": {}, \"map\": \"{}\"}}{}",
+                bank,
+                read,
+                written,
+                self.erasable_map(bank),
+                separator(bank, RAM_NUM_BANKS)
+            )?;
+        }
+        writeln!(out, "  ],")?;
+        writeln!(out, "  \"routines\": [")?;
+        let routines = self.routines();
+        for (idx, x) in routines.iter().enumerate() {
+            writeln!(
+                out,
+                "    {{\"name\": \"{}\", \"address\": \"{}\", \"executed\": {}, \"words\": {}}}{}",
+                json_escape(x.name),
+                x.start,
+                x.executed,
+                x.words,
+                separator(idx, routines.len())
+            )?;
+        }
+        writeln!(out, "  ]")?;
+        writeln!(out, "}}")
+    }
+
+    fn mark(&mut self, accesses: &[AgcMemAccess]) {
+        for access in accesses {
+            let (addr, flag) = match access {
+                AgcMemAccess::Read(addr, _) => (addr, READ),
+                AgcMemAccess::Write(addr, _) => (addr, WRITTEN),
+                _ => continue,
+            };
+            if let AgcAddr::Erasable { bank, offset } = addr {
+                let idx = *bank as usize * RAM_BANK_NUM_WORDS + *offset as usize;
+                if let Some(x) = self.erasable.get_mut(idx) {
+                    *x |= flag;
+                }
+            }
+        }
+    }
+
+    /// Rope words in use in a bank, which are its nonzero words.
+    fn in_use(&self, bank: usize, offset: usize) -> bool {
+        rope_word(self.rope, bank, offset) != 0
+    }
+
+    fn fixed_bank(&self, bank: usize) -> (usize, usize) {
+        let executed = &self.executed[bank * ROM_BANK_NUM_WORDS..][..ROM_BANK_NUM_WORDS];
+        let words = (0..ROM_BANK_NUM_WORDS)
+            .filter(|x| self.in_use(bank, *x))
+            .count();
+        (executed.iter().filter(|x| **x).count(), words)
+    }
+
+    fn erasable_bank(&self, bank: usize) -> (usize, usize) {
+        let flags = &self.erasable[bank * RAM_BANK_NUM_WORDS..][..RAM_BANK_NUM_WORDS];
+        let read = flags.iter().filter(|x| **x & READ != 0).count();
+        let written = flags.iter().filter(|x| **x & WRITTEN != 0).count();
+        (read, written)
+    }
+
+    fn fixed_map(&self, bank: usize) -> String {
+        (0..ROM_BANK_NUM_WORDS)
+            .map(|x| {
+                if self.executed[bank * ROM_BANK_NUM_WORDS + x] {
+                    'X'
+                } else if self.in_use(bank, x) {
+                    '.'
+                } else {
+                    ' '
+                }
+            })
+            .collect()
+    }
+
+    fn erasable_map(&self, bank: usize) -> String {
+        self.erasable[bank * RAM_BANK_NUM_WORDS..][..RAM_BANK_NUM_WORDS]
+            .iter()
+            .map(|x| match *x {
+                READ => 'r',
+                WRITTEN => 'w',
+                0 => '.',
+                _ => 'b',
+            })
+            .collect()
+    }
+
+    /// Coverage of the fixed memory routines, in address order. Each
+    /// symbol covers the words in use up to the next symbol of its bank.
+    fn routines(&self) -> Vec<RoutineCoverage<'s>> {
+        let symbols = match self.symbols {
+            Some(x) => x,
+            None => return vec![],
+        };
+
+        let mut routines: Vec<RoutineCoverage<'s>> = vec![];
+        for bank in 0..ROM_NUM_BANKS {
+            for offset in 0..ROM_BANK_NUM_WORDS {
+                let addr = AgcAddr::Fixed {
+                    bank: bank as u8,
+                    offset: offset as u16,
+                };
+                match symbols.symbol_at(addr) {
+                    Some((name, 0)) => routines.push(RoutineCoverage {
+                        name,
+                        start: addr,
+                        executed: 0,
+                        words: 0,
+                    }),
+                    Some(_) => {}
+                    None => continue,
+                }
+                let routine = routines.last_mut().unwrap();
+                if self.in_use(bank, offset) {
+                    routine.words += 1;
+                }
+                if self.executed[bank * ROM_BANK_NUM_WORDS + offset] {
+                    routine.executed += 1;
+                }
+            }
+        }
+        routines
+    }
+}
+
+impl<'r, 's> CpuObserver for Coverage<'r, 's> {
+    fn programmed(&mut self, inst: &AgcInst, info: &AgcStepInfo) {
+        if let Some(AgcAddr::Fixed { bank, offset }) = info.banks.resolve(inst.pc) {
+            self.executed[bank as usize * ROM_BANK_NUM_WORDS + offset as usize] = true;
+        }
+        self.mark(info.accesses);
+    }
+
+    fn unprogrammed(&mut self, _seq: &AgcUnprogSeq, info: &AgcStepInfo) {
+        self.mark(info.accesses);
+    }
+}
+
+fn percent(x: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    x as f64 * 100.0 / total as f64
+}
+
+fn separator(idx: usize, len: usize) -> &'static str {
+    if idx + 1 < len {
+        ","
+    } else {
+        ""
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut res = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res
+}
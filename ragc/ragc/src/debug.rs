@@ -0,0 +1,383 @@
+use crossbeam_channel::Receiver;
+use std::io::{BufRead, Write};
+
+use ragc_core::consts::io::NUM_CHANNELS;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::debugger::{
+    AgcDebugger, AgcStopReason, AgcWatchKind, AgcWatchTarget, AgcWatchpoint,
+};
+use ragc_core::disasm::AgcDisasm;
+use ragc_core::fault::{AgcFault, AgcFaultKind, AgcFaultTrigger};
+use ragc_core::mem::AgcAddr;
+use ragc_core::observer::AgcMemAccess;
+use ragc_core::symbols::{parse_symbolic, AgcSymbolic, AgcSymbols};
+
+type Symbols<'s> = Option<&'s dyn AgcSymbols>;
+
+// Number of cycles run between checks for a Ctrl-C while continuing.
+const RUN_CHUNK_CYCLES: usize = 100000;
+
+const HELP: &str = "\
+step [n]               Execute n instructions (s)
+next                   Step over a TC subroutine call (n)
+continue               Run until a breakpoint, watchpoint or Ctrl-C (c)
+until <cycle>          Run until the given total cycle count (u)
+break <addr>           Set a breakpoint (b)
+delete <addr>          Remove a breakpoint (d)
+watch <target> [r|w|rw]
+                       Watch an erasable address or a channel (chNN) (w)
+unwatch <target>       Remove the watchpoints on a target
+info                   List breakpoints, watchpoints and the last restart (i)
+regs                   Show the central and bank registers (r)
+x <addr> [n]           Examine n words of memory
+io <ch>                Read an IO channel
+fault <when> <kind>    Inject a fault. <when> is a cycle count or @<addr>,
+                       <kind> is eparity, fparity, goj, rupt <n>,
+                       stuck <ch> <mask> <value> or drop <counter> <n>
+fault clear            Remove every injected fault
+save <file>            Save a machine snapshot
+load <file>            Restore a machine snapshot
+quit                   Exit (q)
+
+Addresses are octal, in yaYUL form: 1400, E3,1400, 24,2000, 4000. With a
+symbol table loaded they can also be symbols, with an optional octal
+offset: REFSMMAT+4.";
+
+fn parse_addr(s: &str, symbols: Symbols) -> Option<AgcAddr> {
+    parse_symbolic(s, symbols)
+}
+
+fn parse_octal(s: &str) -> Option<usize> {
+    usize::from_str_radix(s, 8).ok()
+}
+
+fn parse_target(s: &str, symbols: Symbols) -> Option<AgcWatchTarget> {
+    match s.strip_prefix("ch") {
+        Some(ch) => parse_octal(ch).map(AgcWatchTarget::Channel),
+        None => parse_addr(s, symbols).map(AgcWatchTarget::Memory),
+    }
+}
+
+/// Parses the arguments of the fault command. Channels, counters, masks
+/// and values are octal.
+fn parse_fault(args: &[&str], symbols: Symbols) -> Option<AgcFault> {
+    let (when, kind, rest) = match args {
+        [when, kind, rest @ ..] => (when, *kind, rest),
+        _ => return None,
+    };
+
+    let trigger = match when.strip_prefix('@') {
+        Some(addr) => AgcFaultTrigger::Address(parse_addr(addr, symbols)?),
+        None => AgcFaultTrigger::Cycle(when.parse::<usize>().ok()?),
+    };
+
+    let kind = match (kind, rest) {
+        ("eparity", []) => AgcFaultKind::ErasableParity,
+        ("fparity", []) => AgcFaultKind::FixedParity,
+        ("goj", []) => AgcFaultKind::ForcedGoj,
+        ("rupt", [n]) => AgcFaultKind::SpuriousRupt(n.parse::<u8>().ok()?),
+        ("stuck", [ch, mask, value]) => AgcFaultKind::StuckBits {
+            channel: parse_octal(ch)?,
+            mask: parse_octal(mask)? as u16,
+            value: parse_octal(value)? as u16,
+        },
+        ("drop", [counter, n]) => AgcFaultKind::DropCounter {
+            counter: parse_octal(counter)?,
+            count: n.parse::<u16>().ok()?,
+        },
+        _ => return None,
+    };
+
+    Some(AgcFault { trigger, kind })
+}
+
+fn fmt_target(target: &AgcWatchTarget, symbols: Symbols) -> String {
+    match target {
+        AgcWatchTarget::Memory(addr) => format!("{}", AgcSymbolic::new(*addr, symbols)),
+        AgcWatchTarget::Channel(ch) => format!("ch{:02o}", ch),
+    }
+}
+
+fn fmt_access(access: &AgcMemAccess, symbols: Symbols) -> String {
+    match access {
+        AgcMemAccess::Read(addr, val) => {
+            format!("read {} = {:05o}", AgcSymbolic::new(*addr, symbols), val)
+        }
+        AgcMemAccess::Write(addr, val) => {
+            format!("write {} = {:05o}", AgcSymbolic::new(*addr, symbols), val)
+        }
+        AgcMemAccess::IoRead(ch, val) => format!("read ch{:02o} = {:05o}", ch, val),
+        AgcMemAccess::IoWrite(ch, val) => format!("write ch{:02o} = {:05o}", ch, val),
+    }
+}
+
+/// Prints the next instruction. With a symbol table, the location is
+/// followed by its symbol, as in `04,2063  30001    CA     A   <CHECKMM +3>`.
+fn print_location(cpu: &AgcCpu) {
+    let addr = cpu.resolve(cpu.registers().z);
+    let label = match cpu.symbols().and_then(|x| x.symbol_at(addr)) {
+        Some(_) => format!("   <{}>", AgcSymbolic::new(addr, cpu.symbols())),
+        None => String::new(),
+    };
+    let inst = cpu.next_instruction();
+    let disasm = AgcDisasm::new(&inst, cpu.bank_context()).with_symbols(cpu.symbols());
+    let disasm = format!("{}", disasm);
+    let pad = if label.is_empty() { 0 } else { 20 };
+    println!(
+        "{:<9}{:05o}    {:<pad$}{}",
+        addr,
+        inst.inst_data & 0o77777,
+        disasm,
+        label,
+        pad = pad
+    );
+}
+
+fn print_stop(cpu: &AgcCpu, reason: &AgcStopReason) {
+    match reason {
+        AgcStopReason::Step | AgcStopReason::CycleLimit => {}
+        AgcStopReason::Breakpoint(addr) => {
+            println!("Breakpoint at {}", AgcSymbolic::new(*addr, cpu.symbols()))
+        }
+        AgcStopReason::Watchpoint(wp, access) => {
+            println!(
+                "Watchpoint on {}: {}",
+                fmt_target(&wp.target, cpu.symbols()),
+                fmt_access(access, cpu.symbols())
+            )
+        }
+        AgcStopReason::Error(x) => println!("AGC halted: {:?}", x),
+    }
+    println!("[cycle {}]", cpu.total_cycles);
+    print_location(cpu);
+}
+
+fn print_registers(cpu: &AgcCpu) {
+    let r = cpu.registers();
+    println!("A={:06o} L={:06o} Q={:06o} Z={:05o}", r.a, r.l, r.q, r.z);
+    println!(
+        "EB={:05o} FB={:05o} BB={:05o} cycles={}",
+        r.eb, r.fb, r.bb, cpu.total_cycles
+    );
+}
+
+fn examine(cpu: &AgcCpu, addr: AgcAddr, count: usize) {
+    let (bank_words, offset) = match addr {
+        AgcAddr::Erasable { offset, .. } => (0o400, offset),
+        AgcAddr::Fixed { offset, .. } => (0o2000, offset),
+    };
+    let end = std::cmp::min(offset as usize + count, bank_words);
+    for offset in offset as usize..end {
+        let addr = match addr {
+            AgcAddr::Erasable { bank, .. } => AgcAddr::Erasable {
+                bank,
+                offset: offset as u16,
+            },
+            AgcAddr::Fixed { bank, .. } => AgcAddr::Fixed {
+                bank,
+                offset: offset as u16,
+            },
+        };
+        println!("{:<9}{:05o}", addr, cpu.peek(addr));
+    }
+}
+
+/// Runs until the debugger stops, or until the CPU is about to execute
+/// `target` if given, checking for a Ctrl-C between chunks of
+/// `RUN_CHUNK_CYCLES` cycles.
+fn run(
+    dbg: &mut AgcDebugger,
+    cpu: &mut AgcCpu,
+    ctrlc_rx: &Receiver<()>,
+    cycle_limit: Option<usize>,
+    target: Option<AgcAddr>,
+) -> AgcStopReason {
+    loop {
+        let mut limit = cpu.total_cycles + RUN_CHUNK_CYCLES;
+        if let Some(x) = cycle_limit {
+            limit = std::cmp::min(limit, x);
+        }
+
+        let reason = match target {
+            Some(addr) => dbg.run_until(cpu, limit, addr),
+            None => dbg.run(cpu, limit),
+        };
+        match reason {
+            AgcStopReason::CycleLimit => {
+                if let Some(x) = cycle_limit {
+                    if cpu.total_cycles >= x {
+                        return AgcStopReason::CycleLimit;
+                    }
+                }
+                if ctrlc_rx.try_recv().is_ok() {
+                    println!("Interrupted");
+                    return AgcStopReason::CycleLimit;
+                }
+            }
+            x => return x,
+        }
+    }
+}
+
+/// Interactive debugger prompt reading commands from stdin. Returns when the
+/// user quits or stdin is closed.
+pub fn repl(cpu: &mut AgcCpu, ctrlc_rx: &Receiver<()>) {
+    let mut dbg = AgcDebugger::new();
+    let symbols = cpu.symbols();
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    print_location(cpu);
+    loop {
+        print!("(ragc) ");
+        let _ = std::io::stdout().flush();
+
+        let line = match lines.next() {
+            Some(Ok(x)) => x,
+            _ => return,
+        };
+        while ctrlc_rx.try_recv().is_ok() {}
+
+        let args: Vec<&str> = line.split_whitespace().collect();
+        if args.is_empty() {
+            continue;
+        }
+
+        match (args[0], &args[1..]) {
+            ("s", rest) | ("step", rest) => {
+                let count = match rest.first() {
+                    Some(x) => match x.parse::<usize>() {
+                        Ok(x) => x,
+                        Err(_) => {
+                            println!("Invalid count: {}", x);
+                            continue;
+                        }
+                    },
+                    None => 1,
+                };
+                let mut reason = AgcStopReason::Step;
+                for _ in 0..count {
+                    reason = dbg.step(cpu);
+                    if reason != AgcStopReason::Step {
+                        break;
+                    }
+                }
+                print_stop(cpu, &reason);
+            }
+            ("n", _) | ("next", _) => {
+                let reason = match dbg.return_address(cpu) {
+                    Some(ret) => run(&mut dbg, cpu, ctrlc_rx, None, Some(ret)),
+                    None => dbg.step(cpu),
+                };
+                print_stop(cpu, &reason);
+            }
+            ("c", _) | ("continue", _) => {
+                let reason = run(&mut dbg, cpu, ctrlc_rx, None, None);
+                print_stop(cpu, &reason);
+            }
+            ("u", [cycle]) | ("until", [cycle]) => match cycle.parse::<usize>() {
+                Ok(x) => {
+                    let reason = run(&mut dbg, cpu, ctrlc_rx, Some(x), None);
+                    print_stop(cpu, &reason);
+                }
+                Err(_) => println!("Invalid cycle count: {}", cycle),
+            },
+            ("b", [addr]) | ("break", [addr]) => match parse_addr(addr, symbols) {
+                Some(x) => match dbg.add_breakpoint(x) {
+                    Ok(()) => println!("Breakpoint at {}", AgcSymbolic::new(x, symbols)),
+                    Err(e) => println!("Unable to add breakpoint: {:?}", e),
+                },
+                None => println!("Invalid address: {}", addr),
+            },
+            ("d", [addr]) | ("delete", [addr]) => match parse_addr(addr, symbols) {
+                Some(x) => {
+                    if !dbg.remove_breakpoint(x) {
+                        println!("No breakpoint at {}", x);
+                    }
+                }
+                None => println!("Invalid address: {}", addr),
+            },
+            ("w", [target, rest @ ..]) | ("watch", [target, rest @ ..]) => {
+                let kind = match rest.first() {
+                    None | Some(&"w") => AgcWatchKind::Write,
+                    Some(&"r") => AgcWatchKind::Read,
+                    Some(&"rw") => AgcWatchKind::Access,
+                    Some(x) => {
+                        println!("Invalid watch kind: {}", x);
+                        continue;
+                    }
+                };
+                match parse_target(target, symbols) {
+                    Some(target) => match dbg.add_watchpoint(AgcWatchpoint { target, kind }) {
+                        Ok(()) => println!(
+                            "Watchpoint on {} ({:?})",
+                            fmt_target(&target, symbols),
+                            kind
+                        ),
+                        Err(e) => println!("Unable to add watchpoint: {:?}", e),
+                    },
+                    None => println!("Invalid watch target: {}", target),
+                }
+            }
+            ("unwatch", [target]) => match parse_target(target, symbols) {
+                Some(x) => {
+                    if !dbg.remove_watchpoint(x) {
+                        println!("No watchpoint on {}", fmt_target(&x, symbols));
+                    }
+                }
+                None => println!("Invalid watch target: {}", target),
+            },
+            ("i", _) | ("info", _) => {
+                for addr in dbg.breakpoints() {
+                    println!("break  {}", AgcSymbolic::new(*addr, symbols));
+                }
+                for wp in dbg.watchpoints() {
+                    println!("watch  {} ({:?})", fmt_target(&wp.target, symbols), wp.kind);
+                }
+                if let Some(x) = cpu.last_restart() {
+                    println!(
+                        "restart {:?} at cycle {}, Z={:05o} ({} total)",
+                        x.cause,
+                        x.total_cycles,
+                        x.z,
+                        cpu.restart_count()
+                    );
+                }
+            }
+            ("r", _) | ("regs", _) => print_registers(cpu),
+            ("x", [addr, rest @ ..]) => {
+                let count = rest
+                    .first()
+                    .and_then(|x| x.parse::<usize>().ok())
+                    .unwrap_or(1);
+                match parse_addr(addr, symbols) {
+                    Some(x) => examine(cpu, x, count),
+                    None => println!("Invalid address: {}", addr),
+                }
+            }
+            ("io", [ch]) => match parse_octal(ch) {
+                Some(x) if x < NUM_CHANNELS => println!("ch{:02o}  {:05o}", x, cpu.peek_io(x)),
+                _ => println!("Invalid channel: {}", ch),
+            },
+            ("fault", ["clear"]) => cpu.clear_faults(),
+            ("fault", rest) => match parse_fault(rest, symbols) {
+                Some(x) => match cpu.inject_fault(x) {
+                    Ok(()) => println!("Fault {:?} armed on {:?}", x.kind, x.trigger),
+                    Err(e) => println!("Unable to inject fault: {:?}", e),
+                },
+                None => println!("Invalid fault: {}", rest.join(" ")),
+            },
+            ("save", [path]) => match crate::save_state(cpu, path) {
+                Ok(()) => println!("Saved snapshot to {}", path),
+                Err(x) => println!("{}", x),
+            },
+            ("load", [path]) => match crate::load_state(cpu, path) {
+                Ok(()) => print_stop(cpu, &AgcStopReason::Step),
+                Err(x) => println!("{}", x),
+            },
+            ("h", _) | ("help", _) => println!("{}", HELP),
+            ("q", _) | ("quit", _) => return,
+            _ => println!("Unknown command: {}. Type 'help' for a list.", line.trim()),
+        }
+    }
+}
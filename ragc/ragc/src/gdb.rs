@@ -0,0 +1,524 @@
+//! GDB remote serial protocol server.
+//!
+//! Registers are exposed in the order Z, A, L, Q, EB, FB, BB, each as a
+//! 16-bit little-endian value. Memory is word addressed, with every word
+//! taking two bytes (byte address = word address * 2):
+//!
+//! * 00000-07777: the CPU's view through the current EB, FB and superbank.
+//! * 10000-13777: erasable banks E0-E7, at 10000 + E * 400.
+//! * 20000-127777: fixed banks 00-43, at 20000 + F * 2000.
+//!
+//! All addresses above are octal word addresses.
+use crossbeam_channel::Receiver;
+use log::{debug, error, info};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use ragc_core::consts::cpu::*;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::debugger::{
+    AgcDebugger, AgcStopReason, AgcWatchKind, AgcWatchTarget, AgcWatchpoint,
+};
+use ragc_core::mem::AgcAddr;
+use ragc_core::observer::AgcMemAccess;
+
+const PHYS_ERASABLE_BASE: usize = 0o10000;
+const PHYS_FIXED_BASE: usize = 0o20000;
+const RAM_NUM_BANKS: usize = 8;
+const ROM_NUM_BANKS: usize = 36;
+
+// Register numbers as seen by GDB, mapped to their erasable addresses.
+const GDB_REGS: [usize; 7] = [REG_Z, REG_A, REG_L, REG_Q, REG_EB, REG_FB, REG_BB];
+
+// Number of cycles run between checks for a break request while continuing.
+const RUN_CHUNK_CYCLES: usize = 100000;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+
+/// Target description served through `qXfer:features:read`, so the client
+/// knows the register names and sizes.
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.ragc.agc.core">
+    <reg name="z" bitsize="16" type="code_ptr" regnum="0"/>
+    <reg name="a" bitsize="16" type="uint16"/>
+    <reg name="l" bitsize="16" type="uint16"/>
+    <reg name="q" bitsize="16" type="code_ptr"/>
+    <reg name="eb" bitsize="16" type="uint16"/>
+    <reg name="fb" bitsize="16" type="uint16"/>
+    <reg name="bb" bitsize="16" type="uint16"/>
+  </feature>
+</target>
+"#;
+
+/// Byte stream a GDB client is served on.
+pub trait GdbStream: Read + Write {
+    /// Returns true if the client sent a break (Ctrl-C) while the target
+    /// was running. Must not block.
+    fn break_requested(&mut self) -> bool;
+}
+
+impl GdbStream for TcpStream {
+    fn break_requested(&mut self) -> bool {
+        let mut buf = [0u8; 1];
+        if self.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let res = matches!(self.read(&mut buf), Ok(1) if buf[0] == 0x03);
+        let _ = self.set_nonblocking(false);
+        res
+    }
+}
+
+enum Packet {
+    Command(String),
+    Break,
+}
+
+struct GdbConnection<'a, S: GdbStream> {
+    stream: &'a mut S,
+    no_ack: bool,
+}
+
+impl<'a, S: GdbStream> GdbConnection<'a, S> {
+    fn read_byte(&mut self) -> Option<u8> {
+        let mut buf = [0u8; 1];
+        match self.stream.read(&mut buf) {
+            Ok(1) => Some(buf[0]),
+            _ => None,
+        }
+    }
+
+    /// Reads the next packet, acknowledging it unless no-ack mode is on.
+    fn read_packet(&mut self) -> Option<Packet> {
+        loop {
+            match self.read_byte()? {
+                b'$' => {}
+                0x03 => return Some(Packet::Break),
+                _ => continue,
+            }
+
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    b'#' => break,
+                    x => data.push(x),
+                }
+            }
+            let hi = self.read_byte()?;
+            let lo = self.read_byte()?;
+            let sum = std::str::from_utf8(&[hi, lo])
+                .ok()
+                .and_then(|x| u8::from_str_radix(x, 16).ok());
+            let expected = data.iter().fold(0u8, |acc, x| acc.wrapping_add(*x));
+
+            if !self.no_ack {
+                if sum != Some(expected) {
+                    let _ = self.stream.write_all(b"-");
+                    continue;
+                }
+                let _ = self.stream.write_all(b"+");
+            }
+            return Some(Packet::Command(String::from_utf8_lossy(&data).into_owned()));
+        }
+    }
+
+    fn send(&mut self, data: &str) -> std::io::Result<()> {
+        let sum = data.bytes().fold(0u8, |acc, x| acc.wrapping_add(x));
+        debug!("GDB <- {}", data);
+        write!(self.stream, "${}#{:02x}", data, sum)?;
+        self.stream.flush()?;
+        if self.no_ack {
+            return Ok(());
+        }
+
+        // Wait for the acknowledgement, resending on a NAK.
+        loop {
+            match self.read_byte() {
+                Some(b'+') => return Ok(()),
+                Some(b'-') => {
+                    write!(self.stream, "${}#{:02x}", data, sum)?;
+                    self.stream.flush()?;
+                }
+                Some(_) => {}
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "connection closed",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// Maps a GDB word address to the AGC word it refers to.
+fn word_addr(cpu: &AgcCpu, word: usize) -> Option<AgcAddr> {
+    match word {
+        0..=0o7777 => Some(cpu.resolve(word as u16)),
+        x if (PHYS_ERASABLE_BASE..PHYS_ERASABLE_BASE + RAM_NUM_BANKS * 0o400).contains(&x) => {
+            let x = x - PHYS_ERASABLE_BASE;
+            Some(AgcAddr::Erasable {
+                bank: (x >> 8) as u8,
+                offset: (x & 0o377) as u16,
+            })
+        }
+        x if (PHYS_FIXED_BASE..PHYS_FIXED_BASE + ROM_NUM_BANKS * 0o2000).contains(&x) => {
+            let x = x - PHYS_FIXED_BASE;
+            Some(AgcAddr::Fixed {
+                bank: (x >> 10) as u8,
+                offset: (x & 0o1777) as u16,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Physical GDB byte address of an AGC word, used when reporting
+/// watchpoint hits.
+fn gdb_addr(addr: &AgcAddr) -> usize {
+    let word = match *addr {
+        AgcAddr::Erasable { bank, offset } => {
+            PHYS_ERASABLE_BASE + (bank as usize) * 0o400 + offset as usize
+        }
+        AgcAddr::Fixed { bank, offset } => {
+            PHYS_FIXED_BASE + (bank as usize) * 0o2000 + offset as usize
+        }
+    };
+    word * 2
+}
+
+fn parse_hex(s: &str) -> Option<usize> {
+    usize::from_str_radix(s, 16).ok()
+}
+
+fn decode_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    if s.len() & 1 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn read_register(cpu: &AgcCpu, reg: usize) -> u16 {
+    let r = cpu.registers();
+    match reg {
+        REG_Z => r.z,
+        REG_A => r.a,
+        REG_L => r.l,
+        REG_Q => r.q,
+        REG_EB => r.eb,
+        REG_FB => r.fb,
+        _ => r.bb,
+    }
+}
+
+fn write_register(cpu: &mut AgcCpu, reg: usize, val: u16) {
+    match reg {
+        // Writing Z also has to refetch the instruction register.
+        REG_Z => cpu.update_pc(val & 0o7777),
+        _ => cpu.poke(
+            AgcAddr::Erasable {
+                bank: 0,
+                offset: reg as u16,
+            },
+            val,
+        ),
+    }
+}
+
+fn read_memory(cpu: &AgcCpu, addr: usize, len: usize) -> Option<String> {
+    let mut res = String::new();
+    for byte in addr..addr.checked_add(len)? {
+        let word = cpu.peek(word_addr(cpu, byte / 2)?);
+        let val = if byte & 1 == 0 {
+            word & 0xff
+        } else {
+            word >> 8
+        };
+        res.push_str(&format!("{:02x}", val));
+    }
+    Some(res)
+}
+
+fn write_memory(cpu: &mut AgcCpu, addr: usize, data: &[u8]) -> Option<()> {
+    for (idx, byte) in data.iter().enumerate() {
+        let byte_addr = addr.checked_add(idx)?;
+        let agc_addr = word_addr(cpu, byte_addr / 2)?;
+        let word = cpu.peek(agc_addr);
+        let word = if byte_addr & 1 == 0 {
+            (word & 0xff00) | *byte as u16
+        } else {
+            (word & 0x00ff) | ((*byte as u16) << 8)
+        };
+        cpu.poke(agc_addr, word);
+    }
+    Some(())
+}
+
+/// Handles a `qXfer:features:read:annex:offset,length` request.
+fn read_features(args: &str) -> String {
+    let mut parts = args.splitn(2, ':');
+    let annex = parts.next();
+    let mut range = parts.next().unwrap_or("").split(',');
+    let offset = range.next().and_then(parse_hex);
+    let len = range.next().and_then(parse_hex);
+    match (annex, offset, len) {
+        (Some("target.xml"), Some(offset), Some(len)) => {
+            let data = TARGET_XML.get(offset..).unwrap_or("");
+            if data.len() > len {
+                format!("m{}", &data[..len])
+            } else {
+                format!("l{}", data)
+            }
+        }
+        (Some("target.xml"), _, _) => "E01".to_string(),
+        _ => "E00".to_string(),
+    }
+}
+
+fn stop_reply(reason: &AgcStopReason) -> String {
+    match reason {
+        AgcStopReason::Watchpoint(_, access) => match access {
+            AgcMemAccess::Read(addr, _) => format!("T{:02x}rwatch:{:x};", SIGTRAP, gdb_addr(addr)),
+            AgcMemAccess::Write(addr, _) => format!("T{:02x}watch:{:x};", SIGTRAP, gdb_addr(addr)),
+            _ => format!("S{:02x}", SIGTRAP),
+        },
+        AgcStopReason::Error(_) => format!("S{:02x}", SIGILL),
+        _ => format!("S{:02x}", SIGTRAP),
+    }
+}
+
+fn watch_kind(kind: char) -> Option<AgcWatchKind> {
+    match kind {
+        '2' => Some(AgcWatchKind::Write),
+        '3' => Some(AgcWatchKind::Read),
+        '4' => Some(AgcWatchKind::Access),
+        _ => None,
+    }
+}
+
+/// Handles a Z or z packet. Software and hardware breakpoints both map to
+/// debugger breakpoints, watchpoints only work on erasable memory.
+fn handle_point(cpu: &AgcCpu, dbg: &mut AgcDebugger, insert: bool, args: &str) -> &'static str {
+    let mut parts = args.split(',');
+    let kind = parts.next().and_then(|x| x.chars().next());
+    let addr = match parts.next().and_then(parse_hex) {
+        Some(x) => x,
+        None => return "E01",
+    };
+    let agc_addr = match word_addr(cpu, addr / 2) {
+        Some(x) => x,
+        None => return "E01",
+    };
+
+    match kind {
+        Some('0') | Some('1') => {
+            if insert {
+                match dbg.add_breakpoint(agc_addr) {
+                    Ok(()) => "OK",
+                    Err(_) => "E02",
+                }
+            } else {
+                dbg.remove_breakpoint(agc_addr);
+                "OK"
+            }
+        }
+        Some(x) => match watch_kind(x) {
+            Some(kind) => {
+                let target = AgcWatchTarget::Memory(agc_addr);
+                if insert {
+                    match dbg.add_watchpoint(AgcWatchpoint { target, kind }) {
+                        Ok(()) => "OK",
+                        Err(_) => "E02",
+                    }
+                } else {
+                    dbg.remove_watchpoint(target);
+                    "OK"
+                }
+            }
+            None => "",
+        },
+        None => "E01",
+    }
+}
+
+/// Runs the CPU until a breakpoint or watchpoint hits, the client sends a
+/// break, or the host receives a Ctrl-C.
+fn run<S: GdbStream>(
+    conn: &mut GdbConnection<S>,
+    dbg: &mut AgcDebugger,
+    cpu: &mut AgcCpu,
+    ctrlc_rx: &Receiver<()>,
+) -> Option<String> {
+    loop {
+        let limit = cpu.total_cycles + RUN_CHUNK_CYCLES;
+        match dbg.run(cpu, limit) {
+            AgcStopReason::CycleLimit => {
+                if conn.stream.break_requested() {
+                    return Some(format!("S{:02x}", SIGINT));
+                }
+                if ctrlc_rx.try_recv().is_ok() {
+                    return None;
+                }
+            }
+            x => return Some(stop_reply(&x)),
+        }
+    }
+}
+
+/// Serves one client on `stream` until it detaches or the stream closes,
+/// which return true, or until the client kills the target or the host
+/// receives a Ctrl-C, which return false.
+pub fn handle_connection<S: GdbStream>(
+    stream: &mut S,
+    cpu: &mut AgcCpu,
+    ctrlc_rx: &Receiver<()>,
+) -> std::io::Result<bool> {
+    let mut conn = GdbConnection {
+        stream,
+        no_ack: false,
+    };
+    let mut dbg = AgcDebugger::new();
+    loop {
+        let packet = match conn.read_packet() {
+            Some(Packet::Command(x)) => x,
+            Some(Packet::Break) => continue,
+            None => return Ok(true),
+        };
+        debug!("GDB -> {}", packet);
+
+        let (cmd, args) = packet.split_at(1.min(packet.len()));
+        let reply = match cmd {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => GDB_REGS
+                .iter()
+                .map(|reg| {
+                    let val = read_register(cpu, *reg);
+                    format!("{:02x}{:02x}", val & 0xff, val >> 8)
+                })
+                .collect(),
+            "G" => match decode_hex_bytes(args) {
+                Some(data) if data.len() == GDB_REGS.len() * 2 => {
+                    for (reg, val) in GDB_REGS.iter().zip(data.chunks(2)) {
+                        write_register(cpu, *reg, u16::from_le_bytes([val[0], val[1]]));
+                    }
+                    "OK".to_string()
+                }
+                _ => "E01".to_string(),
+            },
+            "p" => match parse_hex(args).and_then(|x| GDB_REGS.get(x)) {
+                Some(reg) => {
+                    let val = read_register(cpu, *reg);
+                    format!("{:02x}{:02x}", val & 0xff, val >> 8)
+                }
+                None => "E01".to_string(),
+            },
+            "P" => {
+                let mut parts = args.split('=');
+                let reg = parts
+                    .next()
+                    .and_then(parse_hex)
+                    .and_then(|x| GDB_REGS.get(x));
+                let val = parts.next().and_then(decode_hex_bytes);
+                match (reg, val) {
+                    (Some(reg), Some(val)) if val.len() == 2 => {
+                        write_register(cpu, *reg, u16::from_le_bytes([val[0], val[1]]));
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                }
+            }
+            "m" => {
+                let mut parts = args.split(',');
+                let addr = parts.next().and_then(parse_hex);
+                let len = parts.next().and_then(parse_hex);
+                match (addr, len) {
+                    (Some(addr), Some(len)) => {
+                        read_memory(cpu, addr, len).unwrap_or_else(|| "E01".to_string())
+                    }
+                    _ => "E01".to_string(),
+                }
+            }
+            "M" => {
+                let (header, data) = args.split_at(args.find(':').unwrap_or(args.len()));
+                let mut parts = header.split(',');
+                let addr = parts.next().and_then(parse_hex);
+                let data = decode_hex_bytes(data.get(1..).unwrap_or(""));
+                match (addr, data) {
+                    (Some(addr), Some(data)) => match write_memory(cpu, addr, &data) {
+                        Some(()) => "OK".to_string(),
+                        None => "E01".to_string(),
+                    },
+                    _ => "E01".to_string(),
+                }
+            }
+            "s" => stop_reply(&dbg.step(cpu)),
+            "c" => match run(&mut conn, &mut dbg, cpu, ctrlc_rx) {
+                Some(x) => x,
+                None => return Ok(false),
+            },
+            "Z" => handle_point(cpu, &mut dbg, true, args).to_string(),
+            "z" => handle_point(cpu, &mut dbg, false, args).to_string(),
+            "H" => "OK".to_string(),
+            "D" => {
+                conn.send("OK")?;
+                return Ok(true);
+            }
+            "k" => return Ok(false),
+            _ => match packet.as_str() {
+                "QStartNoAckMode" => {
+                    conn.send("OK")?;
+                    conn.no_ack = true;
+                    continue;
+                }
+                "qAttached" => "1".to_string(),
+                x if x.starts_with("qSupported") => {
+                    "PacketSize=1000;QStartNoAckMode+;qXfer:features:read+".to_string()
+                }
+                x if x.starts_with("qXfer:features:read:") => {
+                    read_features(&x["qXfer:features:read:".len()..])
+                }
+                _ => String::new(),
+            },
+        };
+        conn.send(&reply)?;
+    }
+}
+
+/// Serves GDB clients on a local TCP port until a client kills the target
+/// or the host receives a Ctrl-C. The CPU only runs while a client asks it
+/// to.
+pub fn serve(cpu: &mut AgcCpu, port: u16, ctrlc_rx: &Receiver<()>) {
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(x) => x,
+        Err(x) => {
+            error!("Unable to listen on port {}: {}", port, x);
+            return;
+        }
+    };
+    info!("Waiting for GDB on 127.0.0.1:{}", port);
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(x) => x,
+            Err(x) => {
+                error!("GDB connection failed: {}", x);
+                continue;
+            }
+        };
+        let _ = stream.set_nodelay(true);
+        info!("GDB connected");
+
+        match handle_connection(&mut stream, cpu, ctrlc_rx) {
+            Ok(true) => info!("GDB disconnected"),
+            Ok(false) => return,
+            Err(x) => error!("GDB connection error: {}", x),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod coverage;
+pub mod gdb;
+pub mod profile;
+pub mod trace;
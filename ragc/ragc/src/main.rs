@@ -1,11 +1,18 @@
 use crossbeam_channel::bounded;
 use ctrlc;
 use env_logger;
-use log::error;
+use log::{error, info, warn};
+use std::io::{BufRead, Write};
 extern crate clap;
 
+mod debug;
+
+use ragc::{coverage, gdb, profile, trace};
+use ragc_asm::SymbolTable;
 use ragc_binaries;
-use ragc_core::{cpu, mem};
+use ragc_core::observer::{AgcStepInfo, CpuObserver};
+use ragc_core::symbols::AgcSymbols;
+use ragc_core::{core_dump, cpu, disasm, inst_cache, mem, pacer, padload, state};
 use ragc_peripherals;
 
 pub const ROM_BANKS_NUM: usize = 36;
@@ -16,11 +23,514 @@ fn fetch_config<'a>() -> clap::ArgMatches<'a> {
     let c = clap::App::new("RAGC")
         .version("0.1")
         .about(about)
-        .subcommand(clap::SubCommand::with_name("retread50").help("Run AGC with RETREAD50"));
+        .arg(
+            clap::Arg::with_name("debug")
+                .long("debug")
+                .help("Start an interactive debugger instead of running the AGC"),
+        )
+        .arg(
+            clap::Arg::with_name("gdb")
+                .long("gdb")
+                .takes_value(true)
+                .value_name("PORT")
+                .help("Serve the GDB remote protocol on a local TCP port"),
+        )
+        .arg(
+            clap::Arg::with_name("load-state")
+                .long("load-state")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Restore a machine snapshot before starting"),
+        )
+        .arg(
+            clap::Arg::with_name("save-state")
+                .long("save-state")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Save a machine snapshot when the AGC stops"),
+        )
+        .arg(
+            clap::Arg::with_name("core")
+                .long("core")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Keep erasable memory in a yaAGC core file, loaded at start and saved on exit"),
+        )
+        .arg(
+            clap::Arg::with_name("padload")
+                .long("padload")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Set erasable values from a pad-load file before power-up"),
+        )
+        .arg(
+            clap::Arg::with_name("symbols")
+                .long("symbols")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("yaYUL listing or symbol file naming the locations of the rope"),
+        )
+        .arg(
+            clap::Arg::with_name("deterministic")
+                .long("deterministic")
+                .help("Run in AGC time only, unpaced and independent of the host clock"),
+        )
+        .arg(
+            clap::Arg::with_name("cycles")
+                .long("cycles")
+                .takes_value(true)
+                .requires("deterministic")
+                .help("Stop after this many MCTs"),
+        )
+        .arg(
+            clap::Arg::with_name("keys")
+                .long("keys")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("deterministic")
+                .help("DSKY key presses to replay, one '<cycle> <key>' per line"),
+        )
+        .arg(
+            clap::Arg::with_name("trace")
+                .long("trace")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("deterministic")
+                .help("Write an execution trace"),
+        )
+        .arg(
+            clap::Arg::with_name("profile")
+                .long("profile")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("deterministic")
+                .help("Write a cycle profile by interrupt, bank, symbol and address"),
+        )
+        .arg(
+            clap::Arg::with_name("folded")
+                .long("folded")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("deterministic")
+                .help("Write call stacks in MCTs as folded stacks for flamegraph tools"),
+        )
+        .arg(
+            clap::Arg::with_name("coverage")
+                .long("coverage")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("deterministic")
+                .help("Write a per-bank map of executed fixed and accessed erasable words"),
+        )
+        .arg(
+            clap::Arg::with_name("coverage-json")
+                .long("coverage-json")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("deterministic")
+                .help("Write the coverage map as JSON"),
+        )
+        .arg(
+            clap::Arg::with_name("speed")
+                .long("speed")
+                .takes_value(true)
+                .conflicts_with("deterministic")
+                .help("Speed relative to real time, 'max' to run unpaced or 'paused'"),
+        )
+        .subcommand(clap::SubCommand::with_name("retread50").help("Run AGC with RETREAD50"))
+        .subcommand(
+            clap::SubCommand::with_name("run")
+                .about("Run AGC with a rope image")
+                .arg(
+                    clap::Arg::with_name("rope")
+                        .long("rope")
+                        .takes_value(true)
+                        .required(true)
+                        .value_name("FILE")
+                        .help("Rope image (.bin, .binsource or an octal dump) or retread50"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("verify")
+                .about("Check the bank checksums of a rope and print its hashes")
+                .arg(
+                    clap::Arg::with_name("rope")
+                        .long("rope")
+                        .takes_value(true)
+                        .required(true)
+                        .value_name("FILE")
+                        .help("Rope image (.bin, .binsource or an octal dump) or retread50"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("disasm")
+                .about("Disassemble a rope into a yaYUL style listing")
+                .arg(
+                    clap::Arg::with_name("rope")
+                        .long("rope")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Rope image (.bin, .binsource or an octal dump) or retread50"),
+                )
+                .arg(
+                    clap::Arg::with_name("bank")
+                        .long("bank")
+                        .takes_value(true)
+                        .help("Fixed bank to list, in octal. Lists every bank if omitted"),
+                ),
+        );
     let a = c.get_matches();
     a
 }
 
+/// Loads a rope by the name of a built-in rope or from an image in any
+/// format `ragc_binaries::load_rope` detects.
+fn load_rope(name: &str) -> Result<Box<ragc_binaries::AgcRope>, String> {
+    if name == "retread50" {
+        return Ok(Box::new(*ragc_binaries::RETREAD50_ROPE));
+    }
+
+    let data = std::fs::read(name).map_err(|x| format!("Unable to read {}: {}", name, x))?;
+    let mut rope = Box::new([[0; ROM_BANK_NUM_WORDS]; ROM_BANKS_NUM]);
+    let format = ragc_binaries::load_rope(&data, &mut rope)
+        .map_err(|x| format!("Invalid rope {}: {}", name, x))?;
+    info!("Loaded {} as {:?}", name, format);
+    Ok(rope)
+}
+
+/// Warns about the banks of a rope that fail their checksum.
+fn check_rope(name: &str, rope: &ragc_binaries::AgcRope) {
+    let report = ragc_binaries::verify_rope(rope);
+    for (bank, sum) in report.bad_banks() {
+        warn!(
+            "{}: bank {:02o} sums to {:05o} instead of +-{:02o}",
+            name, bank, sum, bank
+        );
+    }
+}
+
+/// Prints the checksum of every bank and the hashes of a rope. Exits with
+/// an error if any bank is bad.
+fn verify_rope(matches: &clap::ArgMatches) {
+    let rope = match load_rope(matches.value_of("rope").unwrap()) {
+        Ok(x) => x,
+        Err(x) => {
+            error!("{}", x);
+            std::process::exit(1);
+        }
+    };
+
+    let report = ragc_binaries::verify_rope(&rope);
+    for (bank, status) in report.banks.iter().enumerate() {
+        let sum = ragc_binaries::bank_checksum(&rope, bank);
+        let status = match status {
+            ragc_binaries::AgcBankStatus::Good => "good",
+            ragc_binaries::AgcBankStatus::Blank => "blank",
+            ragc_binaries::AgcBankStatus::Bad(_) => "BAD",
+        };
+        println!("bank {:02o}  {:05o}  {}", bank, sum, status);
+    }
+    println!("image crc32 {:08x}", report.image_crc32);
+    println!("words crc32 {:08x}", report.words_crc32);
+
+    if !report.is_good() {
+        std::process::exit(1);
+    }
+}
+
+/// Reads a DSKY key schedule. Each line holds the cycle at which the key
+/// is pressed and the key name (0-9, VERB, NOUN, ENTR, CLR, RSET, KEYREL,
+/// +, -, PRO, PROREL). Empty lines and lines starting with # are skipped.
+fn load_keys(path: &str) -> Result<Vec<(usize, u16)>, String> {
+    let data =
+        std::fs::read_to_string(path).map_err(|x| format!("Unable to read {}: {}", path, x))?;
+
+    let mut keys = vec![];
+    for (idx, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let cycle = fields.next().and_then(|x| x.parse::<usize>().ok());
+        let key = fields
+            .next()
+            .and_then(|x| ragc_peripherals::dsky::dsky_keycode(&x.to_uppercase()));
+        match (cycle, key) {
+            (Some(cycle), Some(key)) => keys.push((cycle, key)),
+            _ => return Err(format!("{}:{}: invalid key entry: {}", path, idx + 1, line)),
+        }
+    }
+    Ok(keys)
+}
+
+pub fn save_state(cpu: &cpu::AgcCpu, path: &str) -> Result<(), String> {
+    let snapshot = cpu
+        .save_state()
+        .map_err(|x| format!("Unable to snapshot machine: {:?}", x))?;
+    std::fs::write(path, snapshot.as_bytes())
+        .map_err(|x| format!("Unable to write {}: {}", path, x))
+}
+
+/// Loads a yaAGC core file before power-up. A missing file is not an
+/// error, the core simply starts out blank.
+pub fn load_core(mm: &mut mem::MemoryMap, path: &str) -> Result<(), String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(x) => x,
+        Err(x) if x.kind() == std::io::ErrorKind::NotFound => {
+            info!("No core file {}, starting with blank erasable", path);
+            return Ok(());
+        }
+        Err(x) => return Err(format!("Unable to read {}: {}", path, x)),
+    };
+    let dump = core_dump::AgcCoreDump::parse(&text)
+        .map_err(|x| format!("Invalid core file {}: {}", path, x))?;
+    mm.load_core(&dump);
+    Ok(())
+}
+
+/// Loads a yaYUL listing or symbol file.
+pub fn load_symbols(path: &str) -> Result<SymbolTable, String> {
+    let data = std::fs::read(path).map_err(|x| format!("Unable to read {}: {}", path, x))?;
+    let table =
+        SymbolTable::parse(&data).map_err(|x| format!("Invalid symbols {}: {}", path, x))?;
+    info!("Loaded {} symbols from {}", table.len(), path);
+    Ok(table)
+}
+
+/// Applies a pad-load file before power-up. Symbols are looked up in
+/// `symbols`, if any.
+pub fn load_padload(
+    mm: &mut mem::MemoryMap,
+    path: &str,
+    symbols: Option<&SymbolTable>,
+) -> Result<(), String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|x| format!("Unable to read {}: {}", path, x))?;
+    let count = padload::AgcPadLoad::new(&text, |x| symbols.and_then(|s| s.get(x)))
+        .apply(mm)
+        .map_err(|x| format!("{}: {}", path, x))?;
+    info!("Pad-loaded {} words from {}", count, path);
+    Ok(())
+}
+
+/// Creates `path` and fills it through `write`.
+fn write_file<F>(path: &str, write: F) -> Result<(), String>
+where
+    F: FnOnce(&mut std::io::BufWriter<std::fs::File>) -> std::io::Result<()>,
+{
+    let file =
+        std::fs::File::create(path).map_err(|x| format!("Unable to create {}: {}", path, x))?;
+    let mut out = std::io::BufWriter::new(file);
+    write(&mut out)
+        .and_then(|_| out.flush())
+        .map_err(|x| format!("Unable to write {}: {}", path, x))
+}
+
+pub fn save_core(cpu: &cpu::AgcCpu, path: &str) -> Result<(), String> {
+    std::fs::write(path, cpu.save_core().to_string())
+        .map_err(|x| format!("Unable to write {}: {}", path, x))
+}
+
+pub fn load_state(cpu: &mut cpu::AgcCpu, path: &str) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|x| format!("Unable to read {}: {}", path, x))?;
+    let snapshot = state::MachineState::from_bytes(&data)
+        .map_err(|x| format!("Invalid snapshot {}: {:?}", path, x))?;
+    cpu.restore_state(&snapshot)
+        .map_err(|x| format!("Unable to restore {}: {:?}", path, x))
+}
+
+fn disasm_rope(matches: &clap::ArgMatches, symbols: Option<&SymbolTable>) {
+    let rope = match load_rope(matches.value_of("rope").unwrap()) {
+        Ok(x) => x,
+        Err(x) => {
+            error!("{}", x);
+            return;
+        }
+    };
+
+    let banks = match matches.value_of("bank") {
+        Some(x) => match usize::from_str_radix(x, 8) {
+            Ok(bank) if bank < ROM_BANKS_NUM => bank..bank + 1,
+            _ => {
+                error!("Invalid bank: {}", x);
+                return;
+            }
+        },
+        None => 0..ROM_BANKS_NUM,
+    };
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for bank in banks {
+        let listing = disasm::AgcBankListing::new(&rope, bank)
+            .with_symbols(symbols.map(|x| x as &dyn AgcSymbols));
+        for line in listing {
+            if writeln!(out, "{}", line).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Parses the --speed argument into a pacing mode.
+fn parse_speed(speed: Option<&str>) -> Result<pacer::PaceMode, String> {
+    match speed {
+        None => Ok(pacer::PaceMode::RealTime),
+        Some("max") => Ok(pacer::PaceMode::MaxSpeed),
+        Some("paused") => Ok(pacer::PaceMode::Paused),
+        Some(x) => match x.parse::<f64>() {
+            Ok(factor) if factor > 0.0 && factor.is_finite() => {
+                Ok(pacer::PaceMode::Accelerated(factor))
+            }
+            _ => Err(format!("Invalid speed: {}", x)),
+        },
+    }
+}
+
+/// Reads pacing commands from stdin, one per line.
+fn speed_commands() -> crossbeam_channel::Receiver<String> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let sent = match line {
+                Ok(x) => tx.send(x).is_ok(),
+                Err(_) => false,
+            };
+            if !sent {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Runs the AGC paced by `mode` until a Ctrl-C or until the CPU halts.
+/// Lines on stdin change the pace: `pause`, `resume`, or any speed
+/// --speed takes.
+fn run(
+    cpu: &mut cpu::AgcCpu,
+    ctrlc_rx: &crossbeam_channel::Receiver<()>,
+    mode: pacer::PaceMode,
+) {
+    let mut pacer = pacer::Pacer::new(mode);
+    let mut resume_mode = match mode {
+        pacer::PaceMode::Paused => pacer::PaceMode::RealTime,
+        x => x,
+    };
+    let commands = speed_commands();
+    let mut last_timestamp = std::time::Instant::now();
+    let mut last_report = last_timestamp;
+    loop {
+        // Check to see if we received a ctrlc signal
+        if !ctrlc_rx.is_empty() {
+            break;
+        }
+
+        while let Ok(line) = commands.try_recv() {
+            let mode = match line.trim() {
+                "" => continue,
+                "pause" => pacer::PaceMode::Paused,
+                "resume" => resume_mode,
+                x => match parse_speed(Some(x)) {
+                    Ok(x) => x,
+                    Err(x) => {
+                        error!("{}", x);
+                        continue;
+                    }
+                },
+            };
+            if mode != pacer::PaceMode::Paused {
+                resume_mode = mode;
+            }
+            info!("Pace {:?}", mode);
+            pacer.set_mode(mode);
+        }
+
+        let now = std::time::Instant::now();
+        let budget = pacer.budget(now.duration_since(last_timestamp).as_nanos() as u64);
+        last_timestamp = now;
+
+        let mut cycle_counter = 0;
+        while cycle_counter < budget {
+            match cpu.step() {
+                Ok(cycles) => {
+                    cycle_counter += cycles as u64;
+                }
+                Err(x) => {
+                    error!("AGC halted: {:?}", x);
+                    return;
+                }
+            }
+        }
+        pacer.ran(cycle_counter);
+
+        if last_report.elapsed().as_secs() >= 10 {
+            let stats = pacer.stats();
+            info!(
+                "{:.0} MCT/s, target {:?}, {} cycles dropped",
+                stats.achieved_rate, stats.target_rate, stats.dropped_cycles
+            );
+            pacer.reset_stats();
+            last_report = std::time::Instant::now();
+        }
+
+        if pacer.mode() != pacer::PaceMode::MaxSpeed {
+            std::thread::sleep(std::time::Duration::new(0, 5000000));
+        }
+    }
+}
+
+/// Observer handing every sequence to several observers in turn.
+struct Observers<'o, 'a>(&'o mut [&'a mut dyn CpuObserver]);
+
+impl<'o, 'a> CpuObserver for Observers<'o, 'a> {
+    fn programmed(&mut self, inst: &ragc_core::instructions::AgcInst, info: &AgcStepInfo) {
+        for x in self.0.iter_mut() {
+            x.programmed(inst, info);
+        }
+    }
+
+    fn unprogrammed(&mut self, seq: &cpu::AgcUnprogSeq, info: &AgcStepInfo) {
+        for x in self.0.iter_mut() {
+            x.unprogrammed(seq, info);
+        }
+    }
+}
+
+/// Runs the AGC as fast as possible in AGC time only, until `cycles` MCTs
+/// have run, a Ctrl-C, or the CPU halts.
+fn run_deterministic(
+    cpu: &mut cpu::AgcCpu,
+    ctrlc_rx: &crossbeam_channel::Receiver<()>,
+    cycles: Option<usize>,
+    observers: &mut [&mut dyn CpuObserver],
+) {
+    let limit = cycles.unwrap_or(usize::MAX);
+    let mut steps: usize = 0;
+    while cpu.total_cycles < limit {
+        // Checking for a Ctrl-C every step would dominate the run time.
+        steps += 1;
+        if steps == 100000 {
+            steps = 0;
+            if !ctrlc_rx.is_empty() {
+                break;
+            }
+        }
+
+        let res = if observers.is_empty() {
+            cpu.step()
+        } else {
+            cpu.step_observed(&mut Observers(observers))
+        };
+        if let Err(x) = res {
+            error!("AGC halted: {:?}", x);
+            return;
+        }
+    }
+}
+
 fn main() {
     env_logger::init();
     let (ctrlc_tx, ctrlc_rx) = bounded(1);
@@ -40,8 +550,37 @@ fn main() {
     }
 
     let matches = fetch_config();
-    let rope = match matches.subcommand_name() {
-        Some("retread50") => *ragc_binaries::RETREAD50_ROPE,
+    let symbols = match matches.value_of("symbols").map(load_symbols) {
+        Some(Ok(x)) => Some(x),
+        Some(Err(x)) => {
+            error!("{}", x);
+            return;
+        }
+        None => None,
+    };
+    let rope = match matches.subcommand() {
+        ("retread50", _) => Box::new(*ragc_binaries::RETREAD50_ROPE),
+        ("run", Some(x)) => {
+            let name = x.value_of("rope").unwrap();
+            match load_rope(name) {
+                Ok(x) => {
+                    check_rope(name, &x);
+                    x
+                }
+                Err(x) => {
+                    error!("{}", x);
+                    return;
+                }
+            }
+        }
+        ("verify", Some(x)) => {
+            verify_rope(x);
+            return;
+        }
+        ("disasm", Some(x)) => {
+            disasm_rope(x, symbols.as_ref());
+            return;
+        }
         _ => {
             error!("Invalid subcommand. Exiting");
             return;
@@ -49,32 +588,161 @@ fn main() {
     };
 
     let mut q1 = heapless::spsc::Queue::new();
-    let (rupt_tx, _rupt_rx) = q1.split();
+    let (_rupt_tx, rupt_rx) = q1.split();
 
-    let mut dsky = ragc_peripherals::dsky::DskyDisplay::new();
-    let mut downrupt = ragc_peripherals::downrupt::DownruptPeriph::new();
+    let deterministic = matches.is_present("deterministic");
+    let mut dsky = if deterministic {
+        let keys = match matches.value_of("keys") {
+            Some(path) => match load_keys(path) {
+                Ok(x) => x,
+                Err(x) => {
+                    error!("{}", x);
+                    return;
+                }
+            },
+            None => vec![],
+        };
+        ragc_peripherals::dsky::DskyDisplay::new_deterministic(keys)
+    } else {
+        ragc_peripherals::dsky::DskyDisplay::new()
+    };
+    let mut downrupt = if deterministic {
+        ragc_peripherals::downrupt::DownruptPeriph::new_offline()
+    } else {
+        ragc_peripherals::downrupt::DownruptPeriph::new()
+    };
+    let mut cache = Box::new(inst_cache::AgcInstCache::new());
 
-    let mm = mem::MemoryMap::new(&rope, &mut downrupt, &mut dsky, rupt_tx);
+    let mut mm = mem::MemoryMap::new(&rope, &mut downrupt, &mut dsky, rupt_rx);
+    if let Some(path) = matches.value_of("core") {
+        if let Err(x) = load_core(&mut mm, path) {
+            error!("{}", x);
+            return;
+        }
+    }
+    if let Some(path) = matches.value_of("padload") {
+        if let Err(x) = load_padload(&mut mm, path, symbols.as_ref()) {
+            error!("{}", x);
+            return;
+        }
+    }
     let mut _cpu = cpu::AgcCpu::new(mm);
 
+    _cpu.set_inst_cache(&mut cache);
+    if let Some(x) = &symbols {
+        _cpu.set_symbols(x);
+    }
     _cpu.reset();
-    let mut last_timestamp = std::time::Instant::now();
-    loop {
-        // Check to see if we received a ctrlc signal
-        if ctrlc_rx.len() > 0 {
-            break;
+    if let Some(path) = matches.value_of("load-state") {
+        if let Err(x) = load_state(&mut _cpu, path) {
+            error!("{}", x);
+            return;
         }
+    }
 
-        if last_timestamp.elapsed().as_millis() == 0 {
-            std::thread::sleep(std::time::Duration::new(0, 5000000));
-            continue;
+    if let Some(port) = matches.value_of("gdb") {
+        match port.parse::<u16>() {
+            Ok(x) => gdb::serve(&mut _cpu, x, &ctrlc_rx),
+            Err(_) => error!("Invalid GDB port: {}", port),
         }
+    } else if matches.is_present("debug") {
+        debug::repl(&mut _cpu, &ctrlc_rx);
+    } else if deterministic {
+        let cycles = match matches.value_of("cycles").map(|x| x.parse::<usize>()) {
+            Some(Ok(x)) => Some(x),
+            Some(Err(_)) => {
+                error!("Invalid cycle count");
+                return;
+            }
+            None => None,
+        };
+        let mut tracer = match matches.value_of("trace") {
+            Some(path) => match std::fs::File::create(path) {
+                Ok(x) => Some(
+                    trace::TraceWriter::new(std::io::BufWriter::new(x))
+                        .with_symbols(_cpu.symbols()),
+                ),
+                Err(x) => {
+                    error!("Unable to create {}: {}", path, x);
+                    return;
+                }
+            },
+            None => None,
+        };
 
-        let mut cycle_counter = 0;
-        let expected_cycles = ((last_timestamp.elapsed().as_micros() as f64) / 11.7) as i64;
-        while cycle_counter < expected_cycles {
-            cycle_counter += _cpu.step() as i64;
+        let profiling =
+            matches.value_of("profile").is_some() || matches.value_of("folded").is_some();
+        let mut profiler = if profiling {
+            Some(profile::Profiler::new().with_symbols(_cpu.symbols()))
+        } else {
+            None
+        };
+
+        let covering =
+            matches.value_of("coverage").is_some() || matches.value_of("coverage-json").is_some();
+        let mut coverage = if covering {
+            Some(coverage::Coverage::new(&rope).with_symbols(_cpu.symbols()))
+        } else {
+            None
+        };
+
+        let mut observers: Vec<&mut dyn CpuObserver> = vec![];
+        if let Some(x) = &mut tracer {
+            observers.push(x);
+        }
+        if let Some(x) = &mut profiler {
+            observers.push(x);
+        }
+        if let Some(x) = &mut coverage {
+            observers.push(x);
+        }
+        run_deterministic(&mut _cpu, &ctrlc_rx, cycles, &mut observers);
+        drop(observers);
+
+        if let Some(x) = &mut tracer {
+            if x.failed() || x.flush().is_err() {
+                error!("Unable to write the execution trace");
+            }
+        }
+        if let Some(x) = &profiler {
+            if let Some(path) = matches.value_of("profile") {
+                if let Err(e) = write_file(path, |out| x.write_report(out)) {
+                    error!("{}", e);
+                }
+            }
+            if let Some(path) = matches.value_of("folded") {
+                if let Err(e) = write_file(path, |out| x.write_folded(out)) {
+                    error!("{}", e);
+                }
+            }
+        }
+        if let Some(x) = &coverage {
+            if let Some(path) = matches.value_of("coverage") {
+                if let Err(e) = write_file(path, |out| x.write_text(out)) {
+                    error!("{}", e);
+                }
+            }
+            if let Some(path) = matches.value_of("coverage-json") {
+                if let Err(e) = write_file(path, |out| x.write_json(out)) {
+                    error!("{}", e);
+                }
+            }
+        }
+    } else {
+        match parse_speed(matches.value_of("speed")) {
+            Ok(mode) => run(&mut _cpu, &ctrlc_rx, mode),
+            Err(x) => error!("{}", x),
+        }
+    }
+
+    if let Some(path) = matches.value_of("save-state") {
+        if let Err(x) = save_state(&_cpu, path) {
+            error!("{}", x);
+        }
+    }
+    if let Some(path) = matches.value_of("core") {
+        if let Err(x) = save_core(&_cpu, path) {
+            error!("{}", x);
         }
-        last_timestamp = std::time::Instant::now();
     }
 }
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use ragc_core::consts::cpu::{REG_Q, RUPT_HANDRUPT};
+use ragc_core::consts::io::CHANNEL_DSALMOUT;
+use ragc_core::consts::{ROM_BANK_NUM_WORDS, ROM_NUM_BANKS};
+use ragc_core::cpu::AgcUnprogSeq;
+use ragc_core::instructions::{AgcInst, AgcMnem};
+use ragc_core::mem::AgcAddr;
+use ragc_core::observer::{AgcMemAccess, AgcStepInfo, CpuObserver};
+use ragc_core::pacer::MCTS_PER_SECOND;
+use ragc_core::symbols::{AgcSymbolic, AgcSymbols};
+
+/// Calls kept on a reconstructed stack. Jobs leave many routines with a
+/// plain TC, so past this depth the outermost calls are dropped. A TC to a
+/// routine already on the stack is taken as a loop back to it.
+const MAX_DEPTH: usize = 64;
+
+/// Number of entries in the address and symbol tables of the report.
+const REPORT_TOP: usize = 30;
+
+/// COMP ACTY lamp in channel 11, lit by the executive while a job runs.
+const COMP_ACTY: u16 = 0o2;
+
+const RUPT_NAMES: [&str; RUPT_HANDRUPT as usize + 1] = [
+    "GOJAM", "T6RUPT", "T5RUPT", "T3RUPT", "T4RUPT", "KEYRUPT1", "KEYRUPT2", "UPRUPT", "DOWNRUPT",
+    "RADARUPT", "HANDRUPT",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Frame {
+    Executive,
+    Interrupt(u8),
+    Unprogrammed,
+    /// Routine entered through a TC.
+    Call(AgcAddr),
+    /// Routine the instruction belongs to, by the symbol table.
+    Routine(AgcAddr),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Call {
+    entry: Frame,
+    /// CPU address the callee returns to, as left in Q by the TC.
+    ret: u16,
+}
+
+/// Observer counting MCTs per fixed memory word and per call stack.
+///
+/// Call stacks are rebuilt from TC, RETURN, interrupt entry and RESUME,
+/// separately for the executive and the running interrupt. The load is
+/// measured by the COMP ACTY lamp: executive time with the lamp off is
+/// idle time, spent in the dummy job. A load close to 100% for long is
+/// what ends in a 1202 executive overflow.
+pub struct Profiler<'s> {
+    symbols: Option<&'s dyn AgcSymbols>,
+    fixed: Vec<u64>,
+    erasable: u64,
+    total: u64,
+    executive: u64,
+    interrupts: [u64; RUPT_HANDRUPT as usize + 1],
+    unprogrammed: u64,
+    rupt: Option<u8>,
+    exec_stack: Vec<Call>,
+    rupt_stack: Vec<Call>,
+    stacks: HashMap<Vec<Frame>, u64>,
+    key: Vec<Frame>,
+    comp_acty: Option<bool>,
+    idle: u64,
+    window: u64,
+    window_idle: u64,
+    peak_load: Option<f64>,
+}
+
+impl<'s> Profiler<'s> {
+    pub fn new() -> Self {
+        Profiler {
+            symbols: None,
+            fixed: vec![0; ROM_NUM_BANKS * ROM_BANK_NUM_WORDS],
+            erasable: 0,
+            total: 0,
+            executive: 0,
+            interrupts: [0; RUPT_HANDRUPT as usize + 1],
+            unprogrammed: 0,
+            rupt: None,
+            exec_stack: vec![],
+            rupt_stack: vec![],
+            stacks: HashMap::new(),
+            key: vec![],
+            comp_acty: None,
+            idle: 0,
+            window: 0,
+            window_idle: 0,
+            peak_load: None,
+        }
+    }
+
+    /// Adds a per-symbol profile and names stack frames by symbol.
+    pub fn with_symbols(mut self, symbols: Option<&'s dyn AgcSymbols>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Writes the flat profile: time in the executive and in each
+    /// interrupt, the load, and the hottest banks, symbols and words.
+    pub fn write_report<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let total = self.total;
+        writeln!(
+            out,
+            "Total          {:>12} MCT  {:.2} s",
+            total,
+            total as f64 / MCTS_PER_SECOND as f64
+        )?;
+        writeln!(
+            out,
+            "Executive      {:>12}      {:5.1}%",
+            self.executive,
+            percent(self.executive, total)
+        )?;
+        let rupts: u64 = self.interrupts.iter().sum();
+        writeln!(
+            out,
+            "Interrupts     {:>12}      {:5.1}%",
+            rupts,
+            percent(rupts, total)
+        )?;
+        for (name, count) in RUPT_NAMES.iter().zip(self.interrupts.iter()) {
+            if *count != 0 {
+                writeln!(
+                    out,
+                    "  {:<12} {:>12}      {:5.1}%",
+                    name,
+                    count,
+                    percent(*count, total)
+                )?;
+            }
+        }
+        writeln!(
+            out,
+            "Unprogrammed   {:>12}      {:5.1}%",
+            self.unprogrammed,
+            percent(self.unprogrammed, total)
+        )?;
+        match (self.comp_acty, self.peak_load) {
+            (None, _) => writeln!(out, "Load           unknown, COMP ACTY never changed")?,
+            (Some(_), peak) => {
+                write!(
+                    out,
+                    "Load           {:5.1}% average",
+                    100.0 - percent(self.idle, total)
+                )?;
+                match peak {
+                    Some(x) => writeln!(out, ", {:5.1}% peak over 1 s", x)?,
+                    None => writeln!(out)?,
+                }
+            }
+        }
+
+        writeln!(out, "\nBanks")?;
+        for (bank, words) in self.fixed.chunks(ROM_BANK_NUM_WORDS).enumerate() {
+            let count: u64 = words.iter().sum();
+            if count != 0 {
+                writeln!(
+                    out,
+                    "  {:02o}           {:>12}      {:5.1}%",
+                    bank,
+                    count,
+                    percent(count, total)
+                )?;
+            }
+        }
+        if self.erasable != 0 {
+            writeln!(
+                out,
+                "  erasable     {:>12}      {:5.1}%",
+                self.erasable,
+                percent(self.erasable, total)
+            )?;
+        }
+
+        if let Some(symbols) = self.symbols {
+            let mut by_symbol: HashMap<&str, u64> = HashMap::new();
+            for (addr, count) in self.fixed_counts() {
+                let name = symbols.symbol_at(addr).map_or("?", |x| x.0);
+                *by_symbol.entry(name).or_insert(0) += count;
+            }
+            let mut by_symbol: Vec<(&str, u64)> = by_symbol.into_iter().collect();
+            by_symbol.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+            writeln!(out, "\nSymbols")?;
+            for (name, count) in by_symbol.iter().take(REPORT_TOP) {
+                writeln!(
+                    out,
+                    "  {:<12} {:>12}      {:5.1}%",
+                    name,
+                    count,
+                    percent(*count, total)
+                )?;
+            }
+        }
+
+        let mut by_addr: Vec<(AgcAddr, u64)> = self.fixed_counts().collect();
+        by_addr.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        writeln!(out, "\nAddresses")?;
+        for (addr, count) in by_addr.iter().take(REPORT_TOP) {
+            write!(
+                out,
+                "  {:<9}{:>16}      {:5.1}%",
+                addr,
+                count,
+                percent(*count, total)
+            )?;
+            match self.symbols.and_then(|x| x.symbol_at(*addr)) {
+                Some(_) => writeln!(out, "  {}", AgcSymbolic::new(*addr, self.symbols))?,
+                None => writeln!(out)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the call-stack profile as folded stacks, one
+    /// `frame;frame;frame count` line per stack, as flamegraph tools read
+    /// them. Counts are in MCTs.
+    pub fn write_folded<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let mut merged: HashMap<String, u64> = HashMap::new();
+        for (frames, count) in self.stacks.iter() {
+            let names: Vec<String> = frames.iter().map(|x| self.frame_name(*x)).collect();
+            *merged.entry(names.join(";")).or_insert(0) += count;
+        }
+        let mut lines: Vec<(String, u64)> = merged.into_iter().collect();
+        lines.sort();
+        for (stack, count) in lines {
+            writeln!(out, "{} {}", stack, count)?;
+        }
+        Ok(())
+    }
+
+    fn fixed_counts(&self) -> impl Iterator<Item = (AgcAddr, u64)> + '_ {
+        self.fixed
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count != 0)
+            .map(|(idx, count)| {
+                let addr = AgcAddr::Fixed {
+                    bank: (idx / ROM_BANK_NUM_WORDS) as u8,
+                    offset: (idx % ROM_BANK_NUM_WORDS) as u16,
+                };
+                (addr, *count)
+            })
+    }
+
+    fn frame_name(&self, frame: Frame) -> String {
+        match frame {
+            Frame::Executive => "executive".to_string(),
+            Frame::Interrupt(x) => RUPT_NAMES[x as usize].to_string(),
+            Frame::Unprogrammed => "unprogrammed".to_string(),
+            Frame::Call(x) | Frame::Routine(x) => {
+                format!("{}", AgcSymbolic::new(x, self.symbols)).replace(' ', "")
+            }
+        }
+    }
+
+    /// Adds `cycles` to the current call stack, ending in `leaf`.
+    fn record(&mut self, root: Frame, leaf: Option<Frame>, cycles: u64) {
+        let stack: &[Call] = match root {
+            Frame::Executive => &self.exec_stack,
+            Frame::Interrupt(_) => &self.rupt_stack,
+            _ => &[],
+        };
+        self.key.clear();
+        self.key.push(root);
+        self.key.extend(stack.iter().map(|x| x.entry));
+        if let Some(x) = leaf {
+            if self.key.last().map(|f| same_routine(*f, x)) != Some(true) {
+                self.key.push(x);
+            }
+        }
+
+        match self.stacks.get_mut(&self.key[..]) {
+            Some(x) => *x += cycles,
+            None => {
+                self.stacks.insert(self.key.clone(), cycles);
+            }
+        }
+    }
+
+    fn elapse(&mut self, cycles: u64, idle: bool) {
+        self.total += cycles;
+        self.window += cycles;
+        if idle {
+            self.idle += cycles;
+            self.window_idle += cycles;
+        }
+
+        if self.window >= MCTS_PER_SECOND {
+            if self.comp_acty.is_some() {
+                let load = 100.0 - percent(self.window_idle, self.window);
+                self.peak_load = Some(self.peak_load.map_or(load, |x| x.max(load)));
+            }
+            self.window = 0;
+            self.window_idle = 0;
+        }
+    }
+
+    fn call(&mut self, entry: Frame, ret: u16) {
+        let stack = match self.rupt {
+            Some(_) => &mut self.rupt_stack,
+            None => &mut self.exec_stack,
+        };
+        if let Some(idx) = stack.iter().position(|x| x.entry == entry) {
+            stack.truncate(idx);
+        } else if stack.len() == MAX_DEPTH {
+            stack.remove(0);
+        }
+        stack.push(Call { entry, ret });
+    }
+
+    /// Unwinds to the call returning to `z`. Returns that do not match any
+    /// call leave the stack alone.
+    fn ret(&mut self, z: u16) {
+        let stack = match self.rupt {
+            Some(_) => &mut self.rupt_stack,
+            None => &mut self.exec_stack,
+        };
+        if let Some(idx) = stack.iter().rposition(|x| x.ret == z) {
+            stack.truncate(idx);
+        }
+    }
+}
+
+impl<'s> Default for Profiler<'s> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'s> CpuObserver for Profiler<'s> {
+    fn programmed(&mut self, inst: &AgcInst, info: &AgcStepInfo) {
+        let cycles = info.cycles as u64;
+        let addr = info.banks.resolve(inst.pc);
+        match addr {
+            Some(AgcAddr::Fixed { bank, offset }) => {
+                self.fixed[bank as usize * ROM_BANK_NUM_WORDS + offset as usize] += cycles
+            }
+            _ => self.erasable += cycles,
+        }
+
+        let root = match self.rupt {
+            Some(x) => {
+                self.interrupts[x as usize] += cycles;
+                Frame::Interrupt(x)
+            }
+            None => {
+                self.executive += cycles;
+                Frame::Executive
+            }
+        };
+        let leaf = match (self.symbols, addr) {
+            (Some(symbols), Some(addr)) => symbols
+                .symbol_at(addr)
+                .and_then(|(name, _)| symbols.address_of(name))
+                .map(Frame::Routine),
+            _ => None,
+        };
+        self.record(root, leaf, cycles);
+        let idle = self.rupt.is_none() && self.comp_acty == Some(false);
+        self.elapse(cycles, idle);
+
+        for access in info.accesses {
+            if let AgcMemAccess::IoWrite(CHANNEL_DSALMOUT, val) = access {
+                self.comp_acty = Some(val & COMP_ACTY != 0);
+            }
+        }
+
+        match inst.mnem {
+            // TC Q jumps to Q, whose word then runs as a TC back to the
+            // caller. That second TC is part of the return, not a call.
+            AgcMnem::TC if inst.get_kaddr() == REG_Q => self.ret(info.before.q),
+            AgcMnem::TC if inst.pc as usize == REG_Q => {}
+            AgcMnem::TC => {
+                if let Some(entry) = info.banks.resolve(info.after.z) {
+                    self.call(Frame::Call(entry), info.after.q);
+                }
+            }
+            AgcMnem::RESUME => {
+                self.rupt = None;
+                self.rupt_stack.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn unprogrammed(&mut self, seq: &AgcUnprogSeq, info: &AgcStepInfo) {
+        let cycles = info.cycles as u64;
+        match seq {
+            AgcUnprogSeq::RUPT => {
+                let vector = info.after.z.wrapping_sub(0o4000) / 4;
+                if vector as usize >= RUPT_NAMES.len() {
+                    self.unprogrammed += cycles;
+                    self.record(Frame::Unprogrammed, None, cycles);
+                } else {
+                    self.rupt = Some(vector as u8);
+                    self.rupt_stack.clear();
+                    self.interrupts[vector as usize] += cycles;
+                    self.record(Frame::Interrupt(vector as u8), None, cycles);
+                }
+            }
+            AgcUnprogSeq::GOJ => {
+                self.rupt = None;
+                self.rupt_stack.clear();
+                self.exec_stack.clear();
+                self.unprogrammed += cycles;
+                self.record(Frame::Unprogrammed, None, cycles);
+            }
+            _ => {
+                self.unprogrammed += cycles;
+                self.record(Frame::Unprogrammed, None, cycles);
+            }
+        }
+        self.elapse(cycles, false);
+    }
+}
+
+/// Whether two frames stand for the same routine, so that the routine a
+/// call landed in is not repeated as the leaf.
+fn same_routine(a: Frame, b: Frame) -> bool {
+    match (a, b) {
+        (Frame::Call(x), Frame::Routine(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+fn percent(x: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    x as f64 * 100.0 / total as f64
+}
@@ -0,0 +1,83 @@
+use std::io::Write;
+
+use ragc_core::cpu::AgcUnprogSeq;
+use ragc_core::disasm::AgcDisasm;
+use ragc_core::instructions::AgcInst;
+use ragc_core::mem::AgcAddr;
+use ragc_core::observer::{AgcStepInfo, CpuObserver};
+use ragc_core::symbols::{AgcSymbolic, AgcSymbols};
+
+/// Observer writing one line per executed sequence. Every field is derived
+/// from AGC state only, so deterministic runs produce identical traces.
+pub struct TraceWriter<'s, W: Write> {
+    out: W,
+    failed: bool,
+    symbols: Option<&'s dyn AgcSymbols>,
+}
+
+impl<'s, W: Write> TraceWriter<'s, W> {
+    pub fn new(out: W) -> Self {
+        TraceWriter {
+            out,
+            failed: false,
+            symbols: None,
+        }
+    }
+
+    /// Shows operands by symbol and adds the symbolic location of each
+    /// instruction at the end of its line.
+    pub fn with_symbols(mut self, symbols: Option<&'s dyn AgcSymbols>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Returns true if writing to the trace failed at some point. Tracing
+    /// stops at the first failure.
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn write_line(&mut self, args: std::fmt::Arguments) {
+        if self.failed {
+            return;
+        }
+        if self.out.write_fmt(args).is_err() {
+            self.failed = true;
+        }
+    }
+}
+
+impl<'s, W: Write> CpuObserver for TraceWriter<'s, W> {
+    fn programmed(&mut self, inst: &AgcInst, info: &AgcStepInfo) {
+        let banks = info.banks;
+        let addr = AgcAddr::from_cpu(inst.pc, banks.ebank.unwrap_or(0), banks.fbank);
+        let disasm = format!("{}", AgcDisasm::new(inst, banks).with_symbols(self.symbols));
+        let label = match self.symbols.and_then(|x| x.symbol_at(addr)) {
+            Some(_) => format!("  {}", AgcSymbolic::new(addr, self.symbols)),
+            None => String::new(),
+        };
+        self.write_line(format_args!(
+            "{:>10} {:<9}{:05o}  {:<20} A={:06o} L={:06o} Q={:06o}{}\n",
+            info.total_cycles,
+            addr,
+            inst.inst_data & 0o77777,
+            disasm,
+            info.after.a,
+            info.after.l,
+            info.after.q,
+            label
+        ));
+    }
+
+    fn unprogrammed(&mut self, seq: &AgcUnprogSeq, info: &AgcStepInfo) {
+        self.write_line(format_args!("{:>10} {:?}\n", info.total_cycles, seq));
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use ragc::coverage::Coverage;
+use ragc_asm::assemble;
+use ragc_core::symbols::AgcSymbols;
+
+/// Runs START to DONE, leaving SKIPPED and the constant unexecuted.
+const SOURCE: &str = "
+        SETLOC  61
+COUNT   ERASE
+OUT     ERASE
+        SETLOC  4000
+START   CA      ONE
+        TS      COUNT
+        CA      COUNT
+        TCF     DONE
+SKIPPED TS      OUT
+DONE    TCF     DONE
+ONE     DEC     1
+";
+
+/// Runs SOURCE with coverage and returns its text and JSON output.
+fn run() -> (String, String) {
+    let asm = assemble(SOURCE).unwrap();
+    let symbols = asm.symbol_table();
+
+    let mut coverage = Coverage::new(asm.rope()).with_symbols(Some(&symbols as &dyn AgcSymbols));
+    common::with_cpu(asm.rope(), |cpu| {
+        for _ in 0..6 {
+            cpu.step_observed(&mut coverage).unwrap();
+        }
+    });
+
+    let (mut text, mut json) = (vec![], vec![]);
+    coverage.write_text(&mut text).unwrap();
+    coverage.write_json(&mut json).unwrap();
+    (
+        String::from_utf8(text).unwrap(),
+        String::from_utf8(json).unwrap(),
+    )
+}
+
+/// Erasable bank 0 map: A and Z are read and written by every
+/// instruction, COUNT by TS and CA. OUT is never touched.
+fn e0_map() -> String {
+    let mut map = vec![b'.'; 0o400];
+    for &x in [0, 0o5, 0o61].iter() {
+        map[x] = b'b';
+    }
+    String::from_utf8(map).unwrap()
+}
+
+#[test]
+fn text_report() {
+    let (text, _) = run();
+    let lines: Vec<&str> = text.lines().collect();
+    let has = |line: &str| lines.contains(&line);
+
+    assert!(has("  02             5          7   71.4%"));
+    assert!(has("  03             0          0    0.0%"));
+    assert!(has("  E0             3          3"));
+    assert!(has("  E1             0          0"));
+
+    let routines = lines.iter().position(|x| *x == "Routines").unwrap();
+    assert_eq!(
+        lines[routines + 1..routines + 5],
+        [
+            "  START        4000          4/4      100.0%",
+            "  SKIPPED      4004          0/1        0.0%",
+            "  DONE         4005          1/1      100.0%",
+            "  ONE          4006          0/1        0.0%",
+        ]
+    );
+
+    let bank = lines.iter().position(|x| *x == "Bank 02").unwrap();
+    assert_eq!(lines[bank + 1], "4000     XXXX.X.");
+    assert_eq!(lines[bank + 2], "4100");
+    let bank = lines.iter().position(|x| *x == "Bank E0").unwrap();
+    assert_eq!(lines[bank + 1], format!("0000     {}", &e0_map()[..0o100]));
+}
+
+#[test]
+fn json_report() {
+    let (_, json) = run();
+    let lines: Vec<&str> = json.lines().collect();
+
+    // 36 fixed and 8 erasable banks, 4 routines.
+    assert_eq!(lines.len(), 56);
+    assert_eq!(lines[..2], ["{", "  \"fixed\": ["]);
+    assert_eq!(lines[38..40], ["  ],", "  \"erasable\": ["]);
+    assert_eq!(lines[48..50], ["  ],", "  \"routines\": ["]);
+    assert_eq!(lines[54..], ["  ]", "}"]);
+    assert!(lines[2..37].iter().all(|x| x.ends_with("},")));
+    assert!(lines[37].ends_with("\"}"));
+
+    assert_eq!(
+        lines[4],
+        format!(
+            "    {{\"bank\": \"02\", \"executed\": 5, \"words\": 7, \"map\": \"{:<1024}\"}},",
+            "XXXX.X."
+        )
+    );
+    assert_eq!(
+        lines[40],
+        format!(
+            "    {{\"bank\": \"E0\", \"read\": 3, \"written\": 3, \"map\": \"{}\"}},",
+            e0_map()
+        )
+    );
+    assert_eq!(
+        lines[50..54],
+        [
+            "    {\"name\": \"START\", \"address\": \"4000\", \"executed\": 4, \"words\": 4},",
+            "    {\"name\": \"SKIPPED\", \"address\": \"4004\", \"executed\": 0, \"words\": 1},",
+            "    {\"name\": \"DONE\", \"address\": \"4005\", \"executed\": 1, \"words\": 1},",
+            "    {\"name\": \"ONE\", \"address\": \"4006\", \"executed\": 0, \"words\": 1}",
+        ]
+    );
+}
@@ -0,0 +1,52 @@
+use ragc::trace::TraceWriter;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::mem::MemoryMap;
+use ragc_peripherals::downrupt::DownruptPeriph;
+use ragc_peripherals::dsky::{dsky_keycode, DskyDisplay};
+
+const CYCLES: usize = 400000;
+
+/// Runs RETREAD50 in AGC time with a key schedule and returns its trace.
+fn run(keys: &[(usize, &str)]) -> Vec<u8> {
+    let keys = keys
+        .iter()
+        .map(|(cycle, name)| (*cycle, dsky_keycode(name).unwrap()))
+        .collect();
+    let mut dsky = DskyDisplay::new_deterministic(keys);
+    let mut downrupt = DownruptPeriph::new_offline();
+    let mut queue = heapless::spsc::Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mm = MemoryMap::new(
+        ragc_binaries::RETREAD50_ROPE,
+        &mut downrupt,
+        &mut dsky,
+        rupt_rx,
+    );
+    let mut cpu = AgcCpu::new(mm);
+    cpu.reset();
+
+    let mut trace = TraceWriter::new(vec![]);
+    while cpu.total_cycles < CYCLES {
+        cpu.step_observed(&mut trace).unwrap();
+    }
+    assert!(!trace.failed());
+    trace.into_inner()
+}
+
+#[test]
+fn runs_are_bit_identical() {
+    let keys = [
+        (100000, "VERB"),
+        (110000, "3"),
+        (120000, "5"),
+        (130000, "ENTR"),
+        (200000, "RSET"),
+    ];
+    let first = run(&keys);
+    let second = run(&keys);
+    assert!(first.len() > 1000);
+    assert!(first == second, "traces of identical runs differ");
+
+    // The schedule does take part in the run.
+    assert!(run(&keys[..1]) != first);
+}
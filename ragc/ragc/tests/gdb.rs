@@ -0,0 +1,162 @@
+//! Drives the GDB stub packet by packet over an in-memory stream.
+
+use std::io::{Cursor, Read, Write};
+
+use crossbeam_channel::bounded;
+
+mod common;
+
+use ragc::gdb::{handle_connection, GdbStream};
+use ragc_asm::{assemble, AgcAssembly};
+
+const SOURCE: &str = "
+        SETLOC  100
+X       ERASE
+        SETLOC  4000
+START   CA      ONE
+        TS      X
+        INCR    X
+BRK     TCF     BRK
+ONE     DEC     1
+";
+
+/// Client side of a session, with every packet queued up front.
+struct Client {
+    input: Cursor<Vec<u8>>,
+    output: Vec<u8>,
+}
+
+impl Read for Client {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl Write for Client {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.output.write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl GdbStream for Client {
+    fn break_requested(&mut self) -> bool {
+        false
+    }
+}
+
+fn packet(data: &str) -> String {
+    let sum = data.bytes().fold(0u8, |acc, x| acc.wrapping_add(x));
+    format!("${}#{:02x}", data, sum)
+}
+
+/// Runs a session of `packets` against `asm` and returns the replies. The
+/// session turns acknowledgements off first, its reply is not returned.
+fn session(asm: &AgcAssembly, packets: &[&str]) -> Vec<String> {
+    let mut input = packet("QStartNoAckMode") + "+";
+    for x in packets {
+        input.push_str(&packet(x));
+    }
+    let mut client = Client {
+        input: Cursor::new(input.into_bytes()),
+        output: vec![],
+    };
+
+    let (_ctrlc_tx, ctrlc_rx) = bounded(1);
+    common::with_cpu(asm.rope(), |cpu| {
+        handle_connection(&mut client, cpu, &ctrlc_rx).unwrap();
+    });
+
+    let output = String::from_utf8(client.output).unwrap();
+    output
+        .split('$')
+        .skip(2)
+        .map(|x| {
+            let (data, sum) = x.split_once('#').unwrap();
+            assert_eq!(packet(data), format!("${}#{}", data, &sum[..2]));
+            data.to_string()
+        })
+        .collect()
+}
+
+/// A word as the stub encodes it, two little-endian bytes.
+fn word(val: u16) -> String {
+    format!("{:02x}{:02x}", val & 0xff, val >> 8)
+}
+
+#[test]
+fn runs_to_a_breakpoint_and_reads_state() {
+    let asm = assemble(SOURCE).unwrap();
+    let brk = asm.addr("BRK").unwrap().cpu_addr();
+    let x = asm.addr("X").unwrap().cpu_addr();
+
+    let z0 = format!("Z0,{:x},2", brk as usize * 2);
+    let m = format!("m{:x},2", x as usize * 2);
+    let replies = session(&asm, &["?", &z0, "c", "g", &m, "k"]);
+    assert_eq!(replies.len(), 5);
+    assert_eq!(replies[0], "S05");
+    assert_eq!(replies[1], "OK");
+    assert_eq!(replies[2], "S05");
+
+    // Z, A, L, Q, EB, FB, BB.
+    let regs = &replies[3];
+    assert_eq!(regs.len(), 7 * 4);
+    assert_eq!(&regs[..4], word(brk));
+    assert_eq!(&regs[4..8], word(1));
+    assert_eq!(replies[4], word(2));
+}
+
+#[test]
+fn removed_breakpoints_no_longer_stop() {
+    let asm = assemble(SOURCE).unwrap();
+    let incr = asm.addr("START").unwrap().cpu_addr() + 2;
+    let brk = asm.addr("BRK").unwrap().cpu_addr();
+
+    let z_incr = format!("Z0,{:x},2", incr as usize * 2);
+    let z_brk = format!("Z0,{:x},2", brk as usize * 2);
+    let r_incr = format!("z0,{:x},2", incr as usize * 2);
+    let replies = session(&asm, &[&z_incr, &z_brk, &r_incr, "c", "p0", "k"]);
+    assert_eq!(replies, vec!["OK", "OK", "OK", "S05", &word(brk)]);
+}
+
+#[test]
+fn out_of_range_memory_reads_fail() {
+    let asm = assemble(SOURCE).unwrap();
+    let replies = session(
+        &asm,
+        &[
+            "mffffffffffffffff,2",
+            "m2,ffffffffffffffff",
+            "m200000,2",
+            "m0,0",
+        ],
+    );
+    assert_eq!(replies, vec!["E01", "E01", "E01", ""]);
+}
+
+#[test]
+fn serves_the_target_description() {
+    let asm = assemble(SOURCE).unwrap();
+    let replies = session(
+        &asm,
+        &[
+            "qSupported:multiprocess+;xmlRegisters=i386",
+            "qXfer:features:read:target.xml:0,10",
+            "qXfer:features:read:target.xml:10,fff",
+            "qXfer:features:read:other.xml:0,fff",
+        ],
+    );
+    assert!(replies[0].split(';').any(|x| x == "qXfer:features:read+"));
+
+    assert!(replies[1].starts_with('m'));
+    assert_eq!(replies[1].len(), 1 + 0x10);
+    assert!(replies[2].starts_with('l'));
+    let xml = format!("{}{}", &replies[1][1..], &replies[2][1..]);
+    assert!(xml.starts_with("<?xml"));
+    for reg in ["z", "a", "l", "q", "eb", "fb", "bb"].iter() {
+        assert!(xml.contains(&format!("<reg name=\"{}\" bitsize=\"16\"", reg)));
+    }
+    assert_eq!(replies[3], "E00");
+}
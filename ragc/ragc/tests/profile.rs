@@ -0,0 +1,85 @@
+mod common;
+
+use ragc::profile::Profiler;
+use ragc_asm::assemble;
+use ragc_core::consts::cpu::RUPT_KEY1;
+use ragc_core::symbols::AgcSymbols;
+
+/// The executive calls WORK, which calls INNER. KEYRUPT1 lands while INNER
+/// runs and calls KEYSUB before resuming. Returns are TC Q, which runs the
+/// word in Q as a TC back to the caller.
+const SOURCE: &str = "
+        SETLOC  4000
+        TCF     START
+        SETLOC  4024
+KEYRUPT CA      Q
+        TS      QRUPT
+        TC      KEYSUB
+        CA      QRUPT
+        TS      Q
+        RESUME
+        SETLOC  4100
+START   TC      WORK
+        TCF     START
+WORK    CA      Q
+        TS      SAVEQ
+        TC      INNER
+        CA      SAVEQ
+        TS      Q
+        TC      Q
+INNER   CA      ONE
+        CA      ONE
+        TC      Q
+KEYSUB  CA      ONE
+        TC      Q
+ONE     DEC     1
+        SETLOC  100
+SAVEQ   ERASE
+";
+
+#[test]
+fn frames_follow_calls_and_interrupts() {
+    let asm = assemble(SOURCE).unwrap();
+    let symbols = asm.symbol_table();
+
+    let mut profiler = Profiler::new().with_symbols(Some(&symbols as &dyn AgcSymbols));
+    common::with_rupts(asm.rope(), |cpu, rupt_tx| {
+        cpu.gint = true;
+        let inner = asm.addr("INNER").unwrap().cpu_addr();
+        while cpu.registers().z != inner + 1 || cpu.unprog_pending() {
+            cpu.step_observed(&mut profiler).unwrap();
+        }
+        rupt_tx.enqueue(RUPT_KEY1).unwrap();
+        let start = asm.addr("START").unwrap().cpu_addr();
+        while cpu.registers().z != start + 1 || cpu.unprog_pending() {
+            cpu.step_observed(&mut profiler).unwrap();
+        }
+    });
+
+    let mut folded = vec![];
+    profiler.write_folded(&mut folded).unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "\
+KEYRUPT1 3
+KEYRUPT1;KEYRUPT 11
+KEYRUPT1;KEYSUB 3
+executive 2
+executive;START 1
+executive;WORK 11
+executive;WORK;INNER 5
+"
+    );
+
+    let mut report = vec![];
+    profiler.write_report(&mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    let mcts = |name: &str| {
+        let line = report.lines().find(|x| x.trim_start().starts_with(name));
+        line.and_then(|x| x.split_whitespace().nth(1))
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(mcts("Executive"), "19");
+    assert_eq!(mcts("KEYRUPT1"), "17");
+}
@@ -0,0 +1,132 @@
+use ragc::trace::TraceWriter;
+use ragc_core::cpu::AgcCpu;
+use ragc_core::fault::{AgcFault, AgcFaultKind, AgcFaultTrigger};
+use ragc_core::mem::mods::AgcIoPeriph;
+use ragc_core::mem::MemoryMap;
+use ragc_core::state::{AgcStateError, MachineState};
+use ragc_peripherals::downrupt::DownruptPeriph;
+use ragc_peripherals::dsky::{dsky_keycode, DskyDisplay};
+
+const SAVE_CYCLES: usize = 115000;
+const CYCLES: usize = 400000;
+
+const KEYS: [(usize, &str); 5] = [
+    (100000, "VERB"),
+    (110000, "3"),
+    (120000, "5"),
+    (130000, "ENTR"),
+    (200000, "RSET"),
+];
+
+/// Runs RETREAD50 in AGC time with a key schedule, either from `snapshot`
+/// or from power-up with a restart forced at cycle 50000. Stops at `until`
+/// and returns the trace of the run, the snapshot taken there and the
+/// restart count.
+fn run(keys: &[(usize, &str)], snapshot: Option<&[u8]>, until: usize) -> (Vec<u8>, Vec<u8>, u32) {
+    let keys = keys
+        .iter()
+        .map(|(cycle, name)| (*cycle, dsky_keycode(name).unwrap()))
+        .collect();
+    let mut dsky = DskyDisplay::new_deterministic(keys);
+    let mut downrupt = DownruptPeriph::new_offline();
+    let mut queue = heapless::spsc::Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mm = MemoryMap::new(
+        ragc_binaries::RETREAD50_ROPE,
+        &mut downrupt,
+        &mut dsky,
+        rupt_rx,
+    );
+    let mut cpu = AgcCpu::new(mm);
+    cpu.reset();
+    match snapshot {
+        Some(x) => cpu
+            .restore_state(&MachineState::from_bytes(x).unwrap())
+            .unwrap(),
+        None => cpu
+            .inject_fault(AgcFault {
+                trigger: AgcFaultTrigger::Cycle(50000),
+                kind: AgcFaultKind::ForcedGoj,
+            })
+            .unwrap(),
+    }
+
+    let mut trace = TraceWriter::new(vec![]);
+    while cpu.total_cycles < until {
+        cpu.step_observed(&mut trace).unwrap();
+    }
+    assert!(!trace.failed());
+    let state = cpu.save_state().unwrap();
+    (
+        trace.into_inner(),
+        state.as_bytes().to_vec(),
+        cpu.restart_count(),
+    )
+}
+
+#[test]
+fn restored_machines_continue_identically() {
+    let (_, snapshot, restarts) = run(&KEYS, None, SAVE_CYCLES);
+    assert_eq!(restarts, 1);
+    let (expected, end, _) = run(&KEYS, Some(&snapshot), CYCLES);
+
+    // The machine restoring the snapshot has no schedule of its own, the
+    // keys still to come are taken from the snapshot.
+    let (trace, restored_end, restored_restarts) = run(&[], Some(&snapshot), CYCLES);
+    assert!(expected.len() > 1000);
+    assert!(trace == expected, "restored run differs");
+    assert!(restored_end == end, "restored machine state differs");
+    assert_eq!(restored_restarts, 1);
+}
+
+#[test]
+fn snapshots_include_the_key_schedule() {
+    let (_, with_keys, _) = run(&KEYS, None, SAVE_CYCLES);
+    let (_, without_keys, _) = run(&KEYS[..2], None, SAVE_CYCLES);
+    assert!(with_keys != without_keys);
+
+    let (expected, _, _) = run(&KEYS, Some(&with_keys), CYCLES);
+    let (trace, _, _) = run(&[], Some(&without_keys), CYCLES);
+    assert!(trace != expected);
+}
+
+#[test]
+fn dsky_state_reports_a_short_buffer() {
+    let dsky = DskyDisplay::new_deterministic(vec![]);
+    let mut buf = [0; 16];
+    assert!(dsky.save_state(&mut buf) > buf.len());
+
+    let mut buf = [0; 256];
+    let len = dsky.save_state(&mut buf);
+    assert!(len > 0 && len <= buf.len());
+}
+
+/// Saves a freshly reset machine whose DSKY has `count` keys scheduled.
+fn save_with_schedule(count: usize) -> Result<Vec<u8>, AgcStateError> {
+    let keys = (0..count).map(|x| (1000000 + x * 1000, 0o21)).collect();
+    let mut dsky = DskyDisplay::new_deterministic(keys);
+    let mut downrupt = DownruptPeriph::new_offline();
+    let mut queue = heapless::spsc::Queue::new();
+    let (_rupt_tx, rupt_rx) = queue.split();
+    let mm = MemoryMap::new(
+        ragc_binaries::RETREAD50_ROPE,
+        &mut downrupt,
+        &mut dsky,
+        rupt_rx,
+    );
+    let mut cpu = AgcCpu::new(mm);
+    cpu.reset();
+    cpu.save_state().map(|x| x.as_bytes().to_vec())
+}
+
+#[test]
+fn long_key_schedules_fill_the_snapshot() {
+    let snapshot = save_with_schedule(200).unwrap();
+    let (_, restored, _) = run(&[], Some(&snapshot), 0);
+    assert!(restored == snapshot);
+
+    assert_eq!(
+        save_with_schedule(1000).err(),
+        Some(AgcStateError::BufferTooSmall)
+    );
+}